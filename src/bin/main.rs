@@ -1,8 +1,45 @@
-use flash_backup::config::modes::cli::args_to_map;
-use flash_backup::make_backup;
+use flash_backup::config::modes::cli::{get_args, args_to_map, args_to_backup_map, args_to_resolved_map, args_to_restore_options, args_to_maps, args_to_version, args_to_diff_maps, args_to_chosen_maps, get_output_folder, args_to_status_folders, args_to_days};
+use flash_backup::{make_backup, make_restore, make_file_restore, make_list, make_show, make_diff, make_verify, make_repair, make_migrate, make_status};
+use std::process;
 
-/// Gets initial version of map, then executes backup process.
+/// Reads arguments, then executes subcommand, or backup process if no subcommand is provided.
 fn main() {
-    let map = args_to_map();
-    make_backup(map);
+    let matches = get_args();
+    match matches.subcommand() {
+        ("restore", Some(restore_matches)) => make_restore(args_to_resolved_map(&matches, restore_matches), args_to_restore_options(restore_matches)),
+        ("versions", Some(versions_matches)) => {
+            let maps = args_to_maps(&matches, versions_matches);
+            make_file_restore(maps, versions_matches.value_of("path").unwrap_or(""), args_to_version(versions_matches), versions_matches.value_of("to").unwrap_or(""));
+        }
+        ("list", Some(list_matches)) => make_list(args_to_maps(&matches, list_matches), list_matches.is_present("json")),
+        ("show", Some(show_matches)) => make_show(args_to_backup_map(&matches, show_matches), show_matches.is_present("json")),
+        ("diff", Some(diff_matches)) => {
+            let (old, new) = args_to_diff_maps(&matches, diff_matches);
+            make_diff(old, new, diff_matches.is_present("json"));
+        }
+        ("verify", Some(verify_matches)) => {
+            let maps = args_to_chosen_maps(&matches, verify_matches);
+            if !make_verify(maps, verify_matches.is_present("save"), verify_matches.is_present("json")) {
+                process::exit(1);
+            }
+        }
+        ("repair", Some(repair_matches)) => {
+            let maps = args_to_chosen_maps(&matches, repair_matches);
+            if !make_repair(maps, repair_matches.is_present("json")) {
+                process::exit(1);
+            }
+        }
+        ("migrate", Some(migrate_matches)) => {
+            if !make_migrate(&get_output_folder(&matches, migrate_matches), migrate_matches.is_present("json")) {
+                process::exit(1);
+            }
+        }
+        ("status", Some(status_matches)) => {
+            let (folders, markers) = args_to_status_folders(&matches);
+            if !make_status(&folders, markers, args_to_days(status_matches), status_matches.is_present("json")) {
+                process::exit(1);
+            }
+        }
+        _ => make_backup(args_to_map(&matches)),
+    }
 }
\ No newline at end of file
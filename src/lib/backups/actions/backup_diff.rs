@@ -0,0 +1,146 @@
+use crate::backups::map::backup_map::BackupMap;
+use crate::backups::map::backup_dir::BackupDir;
+use crate::backups::map::backup_entry::BackupEntry;
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::map::backup_mode::BackupMode;
+
+    fn map_with_files(files: &[(&str, &str)]) -> BackupMap {
+        let mut dir = BackupDir::new();
+        dir.root_input = String::from("/home/user");
+        for (path, hash) in files {
+            dir.backup_entries.push(BackupEntry { input_path: format!("/home/user/{}", path), output_path: String::new(), is_file: true, hash: String::from(*hash), ..BackupEntry::new() });
+        }
+        let mut map = BackupMap::new(BackupMode::Multiple);
+        map.backup_dirs = vec![dir];
+        map
+    }
+
+    #[test]
+    fn test_diff_maps() {
+        let old = map_with_files(&[("same.txt", "1"), ("modified.txt", "2"), ("removed.txt", "3"), ("moved.txt", "4")]);
+        let new = map_with_files(&[("same.txt", "1"), ("modified.txt", "5"), ("added.txt", "6"), ("folder/moved.txt", "4")]);
+        let diff = diff_maps(&old, &new);
+        assert_eq!(diff.added, vec!["/home/user/added.txt"]);
+        assert_eq!(diff.removed, vec!["/home/user/removed.txt"]);
+        assert_eq!(diff.modified, vec!["/home/user/modified.txt"]);
+        assert_eq!(diff.moved, vec![(String::from("/home/user/moved.txt"), String::from("/home/user/folder/moved.txt"))]);
+        assert!(!diff.is_empty());
+        assert!(diff_maps(&old, &old).is_empty());
+    }
+}
+
+/// Contains differences between two backups - every path is input path of file.
+#[derive(Serialize, Default)]
+pub struct BackupDiff {
+    pub old_id: String,
+    pub new_id: String,
+    /// Files present only in newer backup.
+    pub added: Vec<String>,
+    /// Files present only in older backup.
+    pub removed: Vec<String>,
+    /// Files present in both backups, with different hash.
+    pub modified: Vec<String>,
+    /// Files that changed their path, but not hash - first path is from older backup, second from newer.
+    pub moved: Vec<(String, String)>,
+}
+
+impl BackupDiff {
+    /// Returns true if there are no differences between backups.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty() && self.moved.is_empty()
+    }
+
+    /// Returns differences as human-readable text, one file per line.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("Changes between {} and {}:\n", &self.old_id, &self.new_id);
+        for path in &self.added {
+            text += &format!("+ {}\n", path);
+        }
+        for path in &self.removed {
+            text += &format!("- {}\n", path);
+        }
+        for path in &self.modified {
+            text += &format!("M {}\n", path);
+        }
+        for (old_path, new_path) in &self.moved {
+            text += &format!("R {} -> {}\n", old_path, new_path);
+        }
+        text += &format!("{} added, {} removed, {} modified, {} moved", self.added.len(), self.removed.len(), self.modified.len(), self.moved.len());
+        text
+    }
+}
+
+/// Finds differences between two backups.
+///
+/// Folders are matched by their root input, and files inside them by path relative to root input. Files with the same path and different hash are modified.
+/// Removed and added files with the same hash are treated as moved, even if they are in different folders.
+pub fn diff_maps(old: &BackupMap, new: &BackupMap) -> BackupDiff {
+    let mut diff = BackupDiff { old_id: old.id.clone(), new_id: new.id.clone(), ..BackupDiff::default() };
+    let mut added: Vec<&BackupEntry> = vec![];
+    let mut removed: Vec<&BackupEntry> = vec![];
+
+    for new_dir in &new.backup_dirs {
+        match old.backup_dirs.iter().find(|x| x.root_input == new_dir.root_input) {
+            Some(old_dir) => diff_one_folder(old_dir, new_dir, &mut added, &mut removed, &mut diff.modified),
+            None => added.extend(new_dir.backup_entries.iter().filter(|x| x.is_file)),
+        }
+    }
+    for old_dir in old.backup_dirs.iter().filter(|x| !new.backup_dirs.iter().any(|y| y.root_input == x.root_input)) {
+        removed.extend(old_dir.backup_entries.iter().filter(|x| x.is_file));
+    }
+
+    // Pairing removed and added files with the same hash
+    let mut removed_by_hash: HashMap<&str, Vec<&BackupEntry>> = HashMap::new();
+    for entry in &removed {
+        removed_by_hash.entry(entry.hash.as_str()).or_default().push(entry);
+    }
+    for entry in added {
+        match removed_by_hash.get_mut(entry.hash.as_str()).and_then(|x| x.pop()) {
+            Some(old_entry) => diff.moved.push((old_entry.input_path.clone(), entry.input_path.clone())),
+            None => diff.added.push(entry.input_path.clone()),
+        }
+    }
+    diff.removed = removed.iter()
+        .filter(|x| !diff.moved.iter().any(|(old_path, _)| old_path == &x.input_path))
+        .map(|x| x.input_path.clone())
+        .collect();
+    diff
+}
+
+/// Compares files of two matching folders, adding files present only in one of them to ```added``` or ```removed```, and paths of files with different hash to ```modified```.
+fn diff_one_folder<'a>(old_dir: &'a BackupDir, new_dir: &'a BackupDir, added: &mut Vec<&'a BackupEntry>, removed: &mut Vec<&'a BackupEntry>, modified: &mut Vec<String>) {
+    let old_files: HashMap<&str, &BackupEntry> = old_dir.backup_entries.iter()
+        .filter(|x| x.is_file)
+        .map(|x| (relative_path(&x.input_path, &old_dir.root_input), x))
+        .collect();
+    let new_files: HashMap<&str, &BackupEntry> = new_dir.backup_entries.iter()
+        .filter(|x| x.is_file)
+        .map(|x| (relative_path(&x.input_path, &new_dir.root_input), x))
+        .collect();
+
+    for entry in new_dir.backup_entries.iter().filter(|x| x.is_file) {
+        match old_files.get(relative_path(&entry.input_path, &new_dir.root_input)) {
+            Some(old_entry) => {
+                if old_entry.hash != entry.hash {
+                    modified.push(entry.input_path.clone());
+                }
+            }
+            None => added.push(entry),
+        }
+    }
+    for entry in old_dir.backup_entries.iter().filter(|x| x.is_file) {
+        if !new_files.contains_key(relative_path(&entry.input_path, &old_dir.root_input)) {
+            removed.push(entry);
+        }
+    }
+}
+
+/// Returns path of entry relative to root of its folder.
+fn relative_path<'a>(path: &'a str, root: &str) -> &'a str {
+    path.strip_prefix(root).unwrap_or(path)
+}
@@ -0,0 +1,178 @@
+use crate::backups::map::backup_map::BackupMap;
+use crate::backups::map::backup_dir::BackupDir;
+use crate::backups::map::backup_mode::BackupMode;
+use crate::backups::map::archive_format::ArchiveFormat;
+use crate::backups::map::compression_codec::CompressionCodec;
+use crate::backups::map::encryption_cipher::EncryptionCipher;
+use crate::backups::helpers::archive::archive_path;
+use crate::backups::helpers::formatting::{format_timestamp, format_size};
+use crate::backups::helpers::chunking::stored_paths;
+use serde::Serialize;
+use std::fs;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::map::backup_entry::BackupEntry;
+
+    #[test]
+    fn test_summarize_map() {
+        let mut dir = BackupDir::new();
+        dir.root_input = String::from("/home/user/a");
+        dir.files = 1;
+        dir.backup_entries.push(BackupEntry { input_path: String::from("/home/user/a/missing.txt"), output_path: String::from("/non/existing/missing.txt"), is_file: true, hash: String::new(), ..BackupEntry::new() });
+        let mut map = BackupMap::new(BackupMode::Cloud);
+        map.id = String::from("123");
+        map.files = 1;
+        map.input_folders = vec![String::from("/home/user/a")];
+        map.backup_dirs = vec![dir];
+
+        let summary = summarize_map(&map);
+        assert_eq!(summary.id, "123");
+        assert_eq!(summary.files, 1);
+        assert_eq!(summary.size, 0);
+        assert!(list_backups(&[map.clone()], true).unwrap().contains("\"id\": \"123\""));
+        assert!(show_backup(&map, false).unwrap().contains("/home/user/a"));
+    }
+}
+
+/// Contains basic informations about one backup, intended to be printed to user.
+#[derive(Serialize)]
+pub struct BackupSummary {
+    pub id: String,
+    pub timestamp: usize,
+    pub date: String,
+    pub mode: BackupMode,
+    pub files: usize,
+    pub folders: usize,
+    /// Total size of files present in backup, in bytes - for backups stored in archive, size of archive.
+    pub size: u64,
+    pub archive: ArchiveFormat,
+    pub compression: CompressionCodec,
+    pub encryption: EncryptionCipher,
+    pub output_folder: String,
+    pub input_folders: Vec<String>,
+    /// Date of last verification, "never" if backup was never verified.
+    pub last_verified: String,
+    /// Id of previous backup in chain, empty if backup isn't incremental.
+    pub parent_id: String,
+}
+
+/// Contains informations about one folder of backup, intended to be printed to user.
+#[derive(Serialize)]
+pub struct BackupDirDetails {
+    pub root_input: String,
+    pub root_output: String,
+    pub files: usize,
+    pub folders: usize,
+    /// Total size of files present in backup, in bytes.
+    pub size: u64,
+}
+
+/// Contains all informations about one backup printed by ```show``` command.
+#[derive(Serialize)]
+pub struct BackupDetails {
+    #[serde(flatten)]
+    pub summary: BackupSummary,
+    pub dirs: Vec<BackupDirDetails>,
+}
+
+/// Returns total size of all files from folder that are present in backup, in bytes.
+///
+/// Files that don't exist are not counted.
+pub fn folder_size(folder: &BackupDir) -> u64 {
+    folder.backup_entries.iter()
+        .filter(|x| x.is_file)
+        .flat_map(stored_paths)
+        .filter_map(|x| fs::metadata(x).ok())
+        .map(|x| x.len())
+        .sum()
+}
+
+/// Creates summary of provided map.
+pub fn summarize_map(map: &BackupMap) -> BackupSummary {
+    BackupSummary {
+        id: map.id.clone(),
+        timestamp: map.timestamp,
+        date: format_timestamp(map.timestamp),
+        mode: map.backup_mode.clone(),
+        files: map.files,
+        folders: map.folders,
+        size: match map.archive {
+            ArchiveFormat::Folder => map.backup_dirs.iter().map(folder_size).sum(),
+            _ => fs::metadata(archive_path(map)).map(|x| x.len()).unwrap_or(0),
+        },
+        archive: map.archive.clone(),
+        compression: map.compression.clone(),
+        encryption: map.encryption.clone(),
+        output_folder: map.output_folder.clone(),
+        input_folders: map.input_folders.clone(),
+        last_verified: match map.last_verified {
+            0 => String::from("never"),
+            timestamp => format_timestamp(timestamp),
+        },
+        parent_id: map.parent_id.clone(),
+    }
+}
+
+/// Returns list of provided backups, sorted from oldest to newest, as human-readable text or JSON.
+///
+/// Returns error only if JSON can't be created.
+pub fn list_backups(maps: &[BackupMap], json: bool) -> Result<String, String> {
+    let mut maps: Vec<&BackupMap> = maps.iter().collect();
+    maps.sort_by_key(|x| x.timestamp);
+    let summaries: Vec<BackupSummary> = maps.iter().map(|x| summarize_map(x)).collect();
+
+    if json {
+        return serde_json::to_string_pretty(&summaries).map_err(|e| format!("Can't convert list of backups to JSON: {}", e));
+    }
+    let mut text = format!("Found {} backups:\n", summaries.len());
+    for summary in &summaries {
+        let mode = match summary.archive {
+            ArchiveFormat::Folder => summary.mode.to_string(),
+            _ => format!("{} ({})", &summary.mode, &summary.archive),
+        };
+        text += &format!("{} - {}, {} mode, {} files, {} folders, {}\n", &summary.id, &summary.date, mode, summary.files, summary.folders, format_size(summary.size));
+        text += &format!("    Input folders: {}\n", summary.input_folders.join(", "));
+    }
+    Ok(text)
+}
+
+/// Returns details of provided backup, with informations about every folder, as human-readable text or JSON.
+///
+/// Returns error only if JSON can't be created.
+pub fn show_backup(map: &BackupMap, json: bool) -> Result<String, String> {
+    let dirs: Vec<BackupDirDetails> = map.backup_dirs.iter().map(|x| BackupDirDetails {
+        root_input: x.root_input.clone(),
+        root_output: x.root_output.clone(),
+        files: x.files,
+        folders: x.folders,
+        size: folder_size(x),
+    }).collect();
+    let details = BackupDetails { summary: summarize_map(map), dirs };
+
+    if json {
+        return serde_json::to_string_pretty(&details).map_err(|e| format!("Can't convert backup details to JSON: {}", e));
+    }
+    let summary = &details.summary;
+    let mut text = format!("Backup {}\n", &summary.id);
+    text += &format!("Date: {}\nMode: {}\nOutput folder: {}\n", &summary.date, &summary.mode, &summary.output_folder);
+    if summary.archive != ArchiveFormat::Folder {
+        text += &format!("Stored in archive: {}\n", archive_path(map));
+    }
+    text += &format!("Files: {}, folders: {}, size: {}\n", summary.files, summary.folders, format_size(summary.size));
+    if summary.compression != CompressionCodec::None && summary.archive == ArchiveFormat::Folder {
+        text += &format!("Files compressed with: {}\n", &summary.compression);
+    }
+    if summary.encryption != EncryptionCipher::None {
+        text += &format!("Encrypted with: {}\n", &summary.encryption);
+    }
+    text += &format!("Last verified: {}\n", &summary.last_verified);
+    if !summary.parent_id.is_empty() {
+        text += &format!("Parent backup: {}\n", &summary.parent_id);
+    }
+    for dir in &details.dirs {
+        text += &format!("{} -> {}\n    {} files, {} folders, {}\n", &dir.root_input, &dir.root_output, dir.files, dir.folders, format_size(dir.size));
+    }
+    Ok(text)
+}
@@ -0,0 +1,117 @@
+use crate::backups::map::backup_map::{BackupMap, MAP_FORMAT_VERSION};
+use crate::backups::map::map_version::MapVersion;
+use crate::backups::helpers::dirs::{load_map, read_map_file};
+use crate::backups::traits::backup_serialize::save_map_to_file;
+use crate::S_MAP;
+use serde::Serialize;
+use std::path::Path;
+use walkdir::WalkDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::map::backup_mode::BackupMode;
+    use crate::backups::map::map_format::MapFormat;
+    use std::fs;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_migrate_folder() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(root.join("old")).unwrap();
+        fs::create_dir_all(root.join("current")).unwrap();
+        fs::create_dir_all(root.join("newer")).unwrap();
+        let old = r#"{"id":"old","timestamp":1,"backup_mode":"Multiple","max_backups":1,"files":0,"folders":0,"output_folder":"","input_folders":[],"ignore_extensions":[],"ignore_folders":[],"backup_dirs":[]}"#;
+        fs::write(root.join("old").join(S_MAP), old).unwrap();
+        let mut map = BackupMap::new(BackupMode::Multiple);
+        map.map_format = MapFormat::Cbor;
+        save_map_to_file(&mut map, root.join("current").join(S_MAP).to_str().unwrap()).unwrap();
+        fs::write(root.join("newer").join(S_MAP), format!(r#"{{"format_version":{},"backup_dirs":"changed"}}"#, MAP_FORMAT_VERSION + 1)).unwrap();
+
+        let report = migrate_folder(root.to_str().unwrap());
+        assert_eq!(report.migrated.len(), 1);
+        assert_eq!(report.up_to_date.len(), 1);
+        assert_eq!(report.failed.len(), 1);
+        assert!(report.failed[0].1.contains("update program"));
+        let migrated = load_map(&root.join("old").join(S_MAP)).unwrap();
+        assert_eq!(migrated.id, "old");
+        assert_eq!(read_map_file::<MapVersion>(&root.join("old").join(S_MAP)).unwrap().format_version, MAP_FORMAT_VERSION);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_keeps_map_on_error() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&root).unwrap();
+        let old = r#"{"id":"old","timestamp":1,"backup_mode":"Multiple","max_backups":1,"files":0,"folders":0,"output_folder":"","input_folders":[],"ignore_extensions":[],"ignore_folders":[],"backup_dirs":[]}"#;
+        fs::write(root.join(S_MAP), old).unwrap();
+        // Temporary file can't be created in place of folder, so migrated map can't be saved
+        fs::create_dir_all(root.join(format!("{}.part", S_MAP))).unwrap();
+
+        let report = migrate_folder(root.to_str().unwrap());
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(fs::read_to_string(root.join(S_MAP)).unwrap(), old);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
+
+/// Contains result of migration of all maps in folder with backups - every path is path of map file.
+#[derive(Serialize, Default)]
+pub struct MigrateReport {
+    /// Maps rewritten in current format version, with their previous version.
+    pub migrated: Vec<(String, u32)>,
+    /// Maps that already were in current format version.
+    pub up_to_date: Vec<String>,
+    /// Maps that couldn't be migrated, with reason.
+    pub failed: Vec<(String, String)>,
+}
+
+impl MigrateReport {
+    /// Returns report as human-readable text, one map per line.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("Migrated {} maps to format version {}, {} maps were up to date", self.migrated.len(), MAP_FORMAT_VERSION, self.up_to_date.len());
+        for (path, version) in &self.migrated {
+            text += &format!("\n    Migrated from version {}: {}", version, path);
+        }
+        for (path, reason) in &self.failed {
+            text += &format!("\n    Can't migrate {}: {}", path, reason);
+        }
+        if !self.failed.is_empty() {
+            text += &format!("\n    {} maps couldn't be migrated", self.failed.len());
+        }
+        text
+    }
+}
+
+/// Rewrites every map found in folder with backups in current format version, keeping its format and cipher. Maps embedded in zip archives are not changed.
+///
+/// Map is written to temporary file first, which replaces map only after it's completely saved, so interrupted migration never leaves damaged map.
+pub fn migrate_folder(folder: &str) -> MigrateReport {
+    let mut report = MigrateReport::default();
+    for entry in WalkDir::new(folder).into_iter().filter_map(|e| e.ok()).filter(|e| e.path().ends_with(S_MAP)) {
+        let path = entry.path().to_str().unwrap_or("").to_string();
+        match migrate_map_file(entry.path()) {
+            Ok(None) => report.up_to_date.push(path),
+            Ok(Some(version)) => {
+                println!("Migrated map {} from format version {}", &path, version);
+                report.migrated.push((path, version));
+            }
+            Err(e) => report.failed.push((path, e)),
+        }
+    }
+    report
+}
+
+/// Rewrites one map file in current format version.
+///
+/// Returns previous version of map, None if map already was in current version, or error if map can't be loaded or saved.
+fn migrate_map_file(path: &Path) -> Result<Option<u32>, String> {
+    let version = read_map_file::<MapVersion>(path)?.format_version;
+    if version == MAP_FORMAT_VERSION {
+        return Ok(None);
+    }
+    let mut map: BackupMap = load_map(path)?;
+    let path = path.to_str().ok_or_else(|| String::from("Path to map isn't valid UTF-8"))?;
+    save_map_to_file(&mut map, path).map_err(|e| format!("Can't save migrated map: {}", e))?;
+    Ok(Some(version))
+}
@@ -0,0 +1,129 @@
+use crate::backups::map::backup_map::BackupMap;
+use crate::backups::map::backup_entry::BackupEntry;
+use crate::backups::map::archive_format::ArchiveFormat;
+use crate::backups::actions::backup_verify::VerifyReport;
+use crate::backups::traits::backup_copy::copy_file;
+use crate::backups::helpers::chunking::{stored_paths, store_chunks};
+use crate::backups::helpers::hashing::generate_hash_meow_hash;
+use crate::backups::helpers::compression::hash_entry;
+use crate::backups::helpers::parity::store_parity;
+use serde::Serialize;
+use std::collections::HashSet;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::map::backup_dir::BackupDir;
+    use crate::backups::map::backup_mode::BackupMode;
+    use crate::backups::actions::backup_verify::verify_map;
+    use std::fs;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_repair_map() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(root.join("source")).unwrap();
+        fs::create_dir_all(root.join("backup")).unwrap();
+        let path = |name: &str| root.join(name).to_str().unwrap().to_string();
+        fs::write(path("source/a.txt"), "a").unwrap();
+        fs::write(path("backup/a.txt"), "rotten").unwrap();
+        fs::write(path("source/b.txt"), "changed").unwrap();
+
+        let mut dir = BackupDir::new();
+        dir.backup_entries.push(BackupEntry { input_path: path("source/a.txt"), output_path: path("backup/a.txt"), is_file: true, hash: generate_hash_meow_hash(&path("source/a.txt")).unwrap(), ..BackupEntry::new() });
+        dir.backup_entries.push(BackupEntry { input_path: path("source/b.txt"), output_path: path("backup/b.txt"), is_file: true, hash: String::from("12345"), ..BackupEntry::new() });
+        let mut map = BackupMap::new(BackupMode::Cloud);
+        map.backup_dirs = vec![dir];
+
+        let report = repair_map(&map, &verify_map(&map));
+        assert_eq!(report.repaired, vec![path("backup/a.txt")]);
+        assert_eq!(report.unrepairable.len(), 1);
+        assert_eq!(fs::read_to_string(path("backup/a.txt")).unwrap(), "a");
+        assert!(verify_map(&map).missing.len() == 1);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
+
+/// Contains result of repair of one backup - every path is output path of file.
+#[derive(Serialize, Default)]
+pub struct RepairReport {
+    pub id: String,
+    /// Files copied again from source.
+    pub repaired: Vec<String>,
+    /// Files that couldn't be repaired, with reason.
+    pub unrepairable: Vec<(String, String)>,
+}
+
+impl RepairReport {
+    /// Returns report as human-readable text, one file per line.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("Backup {}: repaired {} files", &self.id, self.repaired.len());
+        for path in &self.repaired {
+            text += &format!("\n    Repaired: {}", path);
+        }
+        for (path, reason) in &self.unrepairable {
+            text += &format!("\n    Can't repair {}: {}", path, reason);
+        }
+        if !self.unrepairable.is_empty() {
+            text += &format!("\n    {} files couldn't be repaired", self.unrepairable.len());
+        }
+        text
+    }
+}
+
+/// Repairs every missing or corrupted file found during verification of backup, by copying it again from its source.
+///
+/// File is repaired only if its source still exists and has the same hash as saved in map, so backup never contains different version of file than its map. Files of backups stored in archive can't be repaired.
+pub fn repair_map(map: &BackupMap, verify_report: &VerifyReport) -> RepairReport {
+    let mut report = RepairReport { id: map.id.clone(), ..RepairReport::default() };
+    let damaged: HashSet<&str> = verify_report.missing.iter().chain(verify_report.corrupted.iter()).map(|x| x.as_str()).collect();
+    if damaged.is_empty() {
+        return report;
+    }
+
+    if map.archive != ArchiveFormat::Folder {
+        for path in damaged {
+            report.unrepairable.push((String::from(path), String::from("backup is stored in archive, which can't be changed - create new backup instead")));
+        }
+        return report;
+    }
+
+    println!("Repairing {} files in backup {}...", damaged.len(), &map.id);
+    for dir in &map.backup_dirs {
+        for entry in dir.backup_entries.iter().filter(|x| x.is_file) {
+            // Chunked files are reported by their damaged chunks, like during verification
+            let paths: Vec<String> = stored_paths(entry).into_iter().filter(|x| damaged.contains(x.as_str())).collect();
+            if paths.is_empty() {
+                continue;
+            }
+            match repair_entry(entry) {
+                Ok(_) => report.repaired.extend(paths),
+                Err(e) => report.unrepairable.extend(paths.into_iter().map(|x| (x, e.clone()))),
+            }
+        }
+    }
+    report
+}
+
+/// Copies file again from its input path to output path, then verifies it and creates its parity again. Missing and corrupted chunks of chunked files are stored again.
+///
+/// Returns error if source doesn't exist, source has different hash than saved in map, copying fails or copied file is still corrupted.
+pub fn repair_entry(entry: &BackupEntry) -> Result<(), String> {
+    match generate_hash_meow_hash(&entry.input_path) {
+        Err(e) => return Err(format!("source isn't available: {}", e)),
+        Ok(hash) => {
+            if hash != entry.hash {
+                return Err(String::from("source was modified since backup"));
+            }
+        }
+    }
+    match entry.chunked {
+        true => store_chunks(entry, true).map(|_| ())?,
+        false => copy_file(entry)?,
+    }
+    match hash_entry(entry) {
+        Ok(hash) if hash == entry.hash => store_parity(entry),
+        Ok(_) => Err(String::from("copied file is still corrupted")),
+        Err(e) => Err(format!("can't verify copied file: {}", e)),
+    }
+}
@@ -0,0 +1,555 @@
+use crate::backups::map::backup_map::BackupMap;
+use crate::backups::map::backup_dir::BackupDir;
+use crate::backups::map::backup_entry::BackupEntry;
+use crate::backups::map::archive_format::ArchiveFormat;
+use crate::backups::traits::backup_copy::{copy_file, create_folder, create_parent_folder};
+use crate::backups::helpers::archive::{archive_path, member_name, read_archive};
+use crate::backups::helpers::hashing::generate_hash_meow_hash;
+use crate::backups::helpers::compression::{hash_entry, open_entry};
+use crate::backups::map::compression_codec::CompressionCodec;
+use crate::backups::map::encryption_cipher::EncryptionCipher;
+use crate::backups::helpers::dirs::get_last_subdir;
+use crate::backups::traits::backup_input::modified_time;
+use crate::S_SEPARATOR;
+use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+use std::fs;
+use scoped_threadpool::Pool;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::map::backup_mode::BackupMode;
+    use crate::backups::helpers::archive::write_archive;
+    use std::path::PathBuf;
+    use uuid::Uuid;
+
+    fn temp_folder() -> PathBuf {
+        let path = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_restore_file() {
+        let root = temp_folder();
+        let backup = root.join("backup.txt");
+        fs::write(&backup, "restored content").unwrap();
+        let entry = BackupEntry { input_path: root.join("source").join("a.txt").to_str().unwrap().to_string(), output_path: backup.to_str().unwrap().to_string(), is_file: true, hash: generate_hash_meow_hash(backup.to_str().unwrap()).unwrap(), ..BackupEntry::new() };
+        restore_file(&entry).unwrap();
+        assert_eq!(fs::read_to_string(&entry.input_path).unwrap(), "restored content");
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_restore_file_corrupted() {
+        let root = temp_folder();
+        let backup = root.join("backup.txt");
+        fs::write(&backup, "changed content").unwrap();
+        let entry = BackupEntry { input_path: root.join("a.txt").to_str().unwrap().to_string(), output_path: backup.to_str().unwrap().to_string(), is_file: true, hash: String::from("12345"), ..BackupEntry::new() };
+        assert!(restore_file(&entry).is_err());
+        assert!(!root.join("a.txt").exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_restore() {
+        let root = temp_folder();
+        let output = root.join("backup");
+        fs::create_dir_all(output.join("folder")).unwrap();
+        fs::write(output.join("folder").join("b.txt"), "b").unwrap();
+        let source = root.join("source");
+        let mut dir = BackupDir::new();
+        dir.root_input = source.to_str().unwrap().to_string();
+        dir.root_output = output.to_str().unwrap().to_string();
+        dir.backup_entries.push(BackupEntry { input_path: source.join("folder").to_str().unwrap().to_string(), output_path: output.join("folder").to_str().unwrap().to_string(), is_file: false, hash: String::new(), ..BackupEntry::new() });
+        dir.backup_entries.push(BackupEntry { input_path: source.join("folder").join("b.txt").to_str().unwrap().to_string(), output_path: output.join("folder").join("b.txt").to_str().unwrap().to_string(), is_file: true, hash: generate_hash_meow_hash(output.join("folder").join("b.txt").to_str().unwrap()).unwrap(), ..BackupEntry::new() });
+        let mut map = BackupMap::new(BackupMode::Multiple);
+        map.backup_dirs = vec![dir];
+
+        let summary = BackupRestore::new(map.clone(), RestoreOptions::default()).restore().unwrap();
+        assert_eq!(summary.restored, 1);
+        assert_eq!(summary.corrupted, 0);
+        assert_eq!(fs::read_to_string(source.join("folder").join("b.txt")).unwrap(), "b");
+
+        fs::write(source.join("folder").join("b.txt"), "changed").unwrap();
+        let options = RestoreOptions { conflict: ConflictPolicy::Skip, ..RestoreOptions::default() };
+        let summary = BackupRestore::new(map.clone(), options).restore().unwrap();
+        assert_eq!(summary.skipped, 1);
+        assert_eq!(fs::read_to_string(source.join("folder").join("b.txt")).unwrap(), "changed");
+
+        let options = RestoreOptions { conflict: ConflictPolicy::Rename, ..RestoreOptions::default() };
+        BackupRestore::new(map, options).restore().unwrap();
+        assert_eq!(fs::read_to_string(source.join("folder").join("b (restored).txt")).unwrap(), "b");
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_restore_archive() {
+        let root = temp_folder();
+        let source = root.join("source");
+        fs::create_dir_all(&source).unwrap();
+        fs::create_dir_all(root.join("backup")).unwrap();
+        fs::write(source.join("a.txt"), "a").unwrap();
+        let path = |name: &str| root.join(name).to_str().unwrap().to_string();
+
+        let mut dir = BackupDir::new();
+        dir.root_input = path("source");
+        dir.backup_entries.push(BackupEntry { input_path: path("source/a.txt"), output_path: path("backup/source/a.txt"), is_file: true, hash: generate_hash_meow_hash(&path("source/a.txt")).unwrap(), ..BackupEntry::new() });
+        let mut map = BackupMap::new(BackupMode::Multiple);
+        map.output_folder = path("backup");
+        map.archive = ArchiveFormat::Tar;
+        map.backup_dirs = vec![dir];
+        write_archive(&map).unwrap();
+
+        let options = RestoreOptions { target_root: path("restored"), ..RestoreOptions::default() };
+        let summary = BackupRestore::new(map.clone(), options).restore().unwrap();
+        assert_eq!(summary.restored, 1);
+        assert_eq!(fs::read_to_string(root.join("restored").join("source").join("a.txt")).unwrap(), "a");
+
+        let version = BackupEntry { input_path: path("version.txt"), ..map.backup_dirs[0].backup_entries[0].clone() };
+        restore_file_from_archive(&version, &archive_path(&map)).unwrap();
+        assert_eq!(fs::read_to_string(path("version.txt")).unwrap(), "a");
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_remap_map() {
+        let mut dir = BackupDir::new();
+        dir.root_input = String::from("/home/olduser/Documents");
+        dir.backup_entries.push(BackupEntry { input_path: String::from("/home/olduser/Documents/a.txt"), output_path: String::new(), is_file: true, hash: String::new(), ..BackupEntry::new() });
+        let mut map = BackupMap::new(BackupMode::Multiple);
+        map.backup_dirs = vec![dir];
+
+        let options = RestoreOptions { remaps: vec![(String::from("/home/olduser"), String::from("/home/newuser"))], ..RestoreOptions::default() };
+        let remapped = remap_map(map.clone(), &options);
+        assert_eq!(remapped.backup_dirs[0].root_input, "/home/newuser/Documents");
+        assert_eq!(remapped.backup_dirs[0].backup_entries[0].input_path, "/home/newuser/Documents/a.txt");
+
+        // Only whole folder names are matched
+        let options = RestoreOptions { remaps: vec![(String::from("/home/old"), String::from("/home/newuser"))], ..RestoreOptions::default() };
+        let remapped = remap_map(map.clone(), &options);
+        assert_eq!(remapped.backup_dirs[0].root_input, "/home/olduser/Documents");
+        let options = RestoreOptions { remaps: vec![(String::from("/home/olduser/Documents"), String::from("/home/newuser/"))], ..RestoreOptions::default() };
+        let remapped = remap_map(map.clone(), &options);
+        assert_eq!(remapped.backup_dirs[0].root_input, "/home/newuser/");
+        assert_eq!(remapped.backup_dirs[0].backup_entries[0].input_path, "/home/newuser/a.txt");
+
+        let options = RestoreOptions { target_root: String::from("/mnt/restore"), ..RestoreOptions::default() };
+        let remapped = remap_map(map, &options);
+        assert_eq!(remapped.backup_dirs[0].backup_entries[0].input_path, format!("/mnt/restore{}Documents/a.txt", S_SEPARATOR));
+    }
+
+    #[test]
+    fn test_resolve_conflict_keep_newer() {
+        let root = temp_folder();
+        let existing = root.join("a.txt");
+        fs::write(&existing, "existing content").unwrap();
+        let modified = modified_time(&fs::metadata(&existing).unwrap());
+        let entry = BackupEntry { input_path: existing.to_str().unwrap().to_string(), output_path: root.join("missing").to_str().unwrap().to_string(), is_file: true, hash: String::from("12345"), ..BackupEntry::new() };
+
+        // Source file was modified later than file in backup was made
+        assert_eq!(resolve_conflict(&BackupEntry { modified: modified - 1, ..entry.clone() }, ConflictPolicy::KeepNewer), None);
+        assert_eq!(resolve_conflict(&BackupEntry { modified: modified + 1, ..entry }, ConflictPolicy::KeepNewer), Some(existing.to_str().unwrap().to_string()));
+        fs::remove_dir_all(root).unwrap();
+    }
+}
+
+/// Contains numbers of files processed while restoring backup.
+#[derive(Clone, Default)]
+pub struct RestoreSummary {
+    pub restored: usize,
+    pub skipped: usize,
+    pub corrupted: usize,
+    pub failed: usize,
+}
+
+/// Enum to store all possible ways of handling files that already exist in restore location.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ConflictPolicy {
+    /// Existing file is replaced with file from backup.
+    Overwrite,
+    /// Existing file is left untouched.
+    Skip,
+    /// Existing file is replaced only if it was modified earlier than source file saved in backup.
+    KeepNewer,
+    /// File from backup is restored next to existing file, with " (restored)" added to its name.
+    Rename,
+}
+
+/// Contains settings of restore process.
+#[derive(Clone)]
+pub struct RestoreOptions {
+    /// If not empty, every folder is restored inside this folder, instead of its original location.
+    pub target_root: String,
+    /// Pairs of path prefixes - if original location of folder starts with first one, it's replaced with second one. Takes precedence over ```target_root```.
+    pub remaps: Vec<(String, String)>,
+    pub conflict: ConflictPolicy,
+}
+
+impl Default for RestoreOptions {
+    fn default() -> RestoreOptions {
+        RestoreOptions { target_root: String::new(), remaps: vec![], conflict: ConflictPolicy::Overwrite }
+    }
+}
+
+/// Restores files from already created backup back to their original locations.
+///
+/// Works for every backup mode, because it relies only on data saved in map - for every entry, file from output path is copied to input path.
+pub struct BackupRestore {
+    pub map: BackupMap,
+    pub options: RestoreOptions,
+}
+
+impl BackupRestore {
+    /// Creates new BackupRestore struct, requires BackupMap loaded from existing backup.
+    ///
+    /// Input paths in map are changed to restore locations, according to provided options.
+    ///
+    /// May panic if map doesn't contain any folders.
+    pub fn new(map: BackupMap, options: RestoreOptions) -> BackupRestore {
+        if map.backup_dirs.is_empty() {
+            panic!("Map of backup to restore doesn't contain any folders. Program will stop");
+        }
+        let map = remap_map(map, &options);
+        BackupRestore { map, options }
+    }
+
+    /// Restores all folders from map, copying every entry from its output path to its input path.
+    ///
+    /// Works concurrently, using max 2 threads at once - too many copying operations working at once can slow down process. Backups stored in archive are restored by reading archive once, in one thread.
+    ///
+    /// Before copying, every file in backup is checked against hash saved in map, corrupted files are not restored. Restored files are checked again after copying.
+    ///
+    /// Returns summary of restored, corrupted and not restored files. Function may panic if fatal error occurs during multithreading operations.
+    pub fn restore(&self) -> Result<RestoreSummary, String> {
+        println!("Restoring backup {}...", &self.map.id);
+        let summary = match self.map.archive {
+            ArchiveFormat::Folder => self.restore_folders(),
+            _ => self.restore_archive(),
+        };
+        match summary.corrupted + summary.failed {
+            0 => println!("Restore completed, restored {} files, skipped {}", summary.restored, summary.skipped),
+            _ => println!("Restore completed, restored {} files, skipped {}, {} corrupted and {} not restored", summary.restored, summary.skipped, summary.corrupted, summary.failed)
+        }
+        Ok(summary)
+    }
+
+    /// Restores all folders copied to backup folder, one thread per folder.
+    fn restore_folders(&self) -> RestoreSummary {
+        let summary = Arc::new(Mutex::new(RestoreSummary::default()));
+        let mut thread_pool = Pool::new(2);
+
+        // Restoring - one thread per one BackupDir
+        thread_pool.scoped(|scoped| {
+            for dir in &self.map.backup_dirs {
+                let summary_ref = Arc::clone(&summary);
+                let conflict = self.options.conflict;
+                scoped.execute(move || {
+                    let folder_summary = restore_one_folder(dir, conflict);
+                    let mut summary_temp = summary_ref.lock().unwrap();
+                    summary_temp.restored += folder_summary.restored;
+                    summary_temp.skipped += folder_summary.skipped;
+                    summary_temp.corrupted += folder_summary.corrupted;
+                    summary_temp.failed += folder_summary.failed;
+                });
+            }
+        });
+
+        let summary = summary.lock().unwrap().clone();
+        summary
+    }
+
+    /// Restores all folders stored in archive - folders are created first, then archive is read once and every file from it is restored to its input path.
+    ///
+    /// Files that already exist are handled according to conflict policy, with modification time of archive used as time of files in backup. Files not found in archive are not restored.
+    fn restore_archive(&self) -> RestoreSummary {
+        let mut summary = RestoreSummary::default();
+        let mut files: HashMap<String, &BackupEntry> = HashMap::new();
+        for dir in &self.map.backup_dirs {
+            println!("Restoring folder {}...", &dir.root_input);
+            for entry in &dir.backup_entries {
+                match entry.is_file {
+                    true => {
+                        files.insert(member_name(&self.map.output_folder, &entry.output_path), entry);
+                    }
+                    false => {
+                        if let Err(e) = create_folder(&entry.input_path) {
+                            println!("{}", e);
+                        }
+                    }
+                }
+            }
+        }
+
+        let path = archive_path(&self.map);
+        let conflict = self.options.conflict;
+        let result = read_archive(&path, |name, reader| {
+            let mut entry = match files.remove(name) {
+                Some(entry) => entry.clone(),
+                None => return true,
+            };
+            if Path::new(&entry.input_path).exists() {
+                let checked = BackupEntry { output_path: path.clone(), ..entry.clone() };
+                match resolve_conflict(&checked, conflict) {
+                    None => {
+                        summary.skipped += 1;
+                        return true;
+                    }
+                    Some(new_path) => entry.input_path = new_path,
+                }
+            }
+            match restore_file_from_reader(&entry, reader) {
+                Ok(_) => summary.restored += 1,
+                Err(RestoreError::Corrupted(e)) => {
+                    println!("{}", e);
+                    summary.corrupted += 1;
+                }
+                Err(RestoreError::Failed(e)) => {
+                    println!("{}", e);
+                    summary.failed += 1;
+                }
+            }
+            true
+        });
+        if let Err(e) = result {
+            println!("{}", e);
+        }
+        for entry in files.values() {
+            println!("File {} not found in archive, it won't be restored", &entry.output_path);
+            summary.failed += 1;
+        }
+        summary
+    }
+}
+
+/// Changes input paths in every BackupDir to locations where they should be restored.
+///
+/// If root input of folder is inside first path of any remap pair (whole folder names are compared), this part is replaced with second path. Else if target root is provided, folder is restored inside it, with the same name as original folder.
+/// Folders that don't match any rule are left unchanged.
+pub fn remap_map(mut map: BackupMap, options: &RestoreOptions) -> BackupMap {
+    for dir in &mut map.backup_dirs {
+        let new_root_input = match options.remaps.iter().find_map(|(old, new)| replace_prefix(&dir.root_input, old, new)) {
+            Some(new_root_input) => new_root_input,
+            None => {
+                if options.target_root.is_empty() {
+                    continue;
+                }
+                match get_last_subdir(&dir.root_input) {
+                    Ok(last_subdir) => format!("{}{}{}", options.target_root.trim_end_matches(S_SEPARATOR), S_SEPARATOR, last_subdir),
+                    Err(e) => {
+                        println!("Can't change restore location of {}: {}, it will be restored to original location", &dir.root_input, e);
+                        continue;
+                    }
+                }
+            }
+        };
+        for entry in &mut dir.backup_entries {
+            if let Some(input_path) = replace_prefix(&entry.input_path, &dir.root_input, &new_root_input) {
+                entry.input_path = input_path;
+            }
+        }
+        println!("Folder {} will be restored to {}", &dir.root_input, &new_root_input);
+        dir.root_input = new_root_input;
+    }
+    map
+}
+
+/// Replaces beginning of provided path with new path, if path is inside old path - whole folder names are compared, so ```/home/user``` doesn't match ```/home/user2```.
+///
+/// Returns None if path isn't inside old path.
+fn replace_prefix(path: &str, old: &str, new: &str) -> Option<String> {
+    let rest = Path::new(path).strip_prefix(old).ok()?;
+    match rest.as_os_str().is_empty() {
+        true => Some(String::from(new)),
+        false => Path::new(new).join(rest).to_str().map(String::from),
+    }
+}
+
+/// Restores content of one BackupDir - for each entry, from output path to input path.
+///
+/// Folders are created first, then files are copied. Files that already exist are handled according to conflict policy, files identical to ones in backup are always skipped.
+/// Every error is printed to user and doesn't stop function execution.
+pub fn restore_one_folder(folder: &BackupDir, conflict: ConflictPolicy) -> RestoreSummary {
+    println!("Restoring folder {}...", &folder.root_input);
+    let mut summary = RestoreSummary::default();
+
+    for entry in folder.backup_entries.iter().filter(|x| !x.is_file) {
+        if let Err(e) = create_folder(&entry.input_path) {
+            println!("{}", e);
+        }
+    }
+    for entry in folder.backup_entries.iter().filter(|x| x.is_file) {
+        let mut entry = entry.clone();
+        if Path::new(&entry.input_path).exists() {
+            match resolve_conflict(&entry, conflict) {
+                None => {
+                    summary.skipped += 1;
+                    continue;
+                }
+                Some(path) => entry.input_path = path,
+            }
+        }
+        match restore_file(&entry) {
+            Ok(_) => summary.restored += 1,
+            Err(RestoreError::Corrupted(e)) => {
+                println!("{}", e);
+                summary.corrupted += 1;
+            }
+            Err(RestoreError::Failed(e)) => {
+                println!("{}", e);
+                summary.failed += 1;
+            }
+        }
+    }
+    println!("Restored {} files to {}", summary.restored, &folder.root_input);
+    summary
+}
+
+/// Decides what to do with file that already exists in restore location.
+///
+/// Returns path to which file should be restored, or None if file should be skipped. Files with the same hash as file in backup are always skipped.
+pub fn resolve_conflict(entry: &BackupEntry, conflict: ConflictPolicy) -> Option<String> {
+    if let Ok(hash) = generate_hash_meow_hash(&entry.input_path) {
+        if hash == entry.hash {
+            return None;
+        }
+    }
+    match conflict {
+        ConflictPolicy::Overwrite => Some(entry.input_path.clone()),
+        ConflictPolicy::Skip => {
+            println!("File {} already exists, skipping...", &entry.input_path);
+            None
+        }
+        ConflictPolicy::KeepNewer => {
+            let existing = fs::metadata(&entry.input_path).map(|x| modified_time(&x));
+            // Time of modification of source file is saved in entry - maps saved by older versions of program only have time of copying file to backup
+            let backup = match entry.modified {
+                0 => fs::metadata(&entry.output_path).map(|x| modified_time(&x)),
+                modified => Ok(modified),
+            };
+            match (existing, backup) {
+                (Ok(existing), Ok(backup)) if backup > existing => Some(entry.input_path.clone()),
+                _ => {
+                    println!("File {} is newer than file in backup, skipping...", &entry.input_path);
+                    None
+                }
+            }
+        }
+        ConflictPolicy::Rename => Some(generate_restored_name(&entry.input_path)),
+    }
+}
+
+/// Returns path that doesn't exist yet, created by adding " (restored)" (and number, if needed) to file name, before extension.
+fn generate_restored_name(path: &str) -> String {
+    let as_path = Path::new(path);
+    let stem = as_path.file_stem().and_then(|x| x.to_str()).unwrap_or("");
+    let extension = match as_path.extension().and_then(|x| x.to_str()) {
+        Some(extension) => format!(".{}", extension),
+        None => String::new(),
+    };
+    let parent = path.trim_end_matches(as_path.file_name().and_then(|x| x.to_str()).unwrap_or(""));
+
+    let mut candidate = format!("{}{} (restored){}", parent, stem, extension);
+    let mut counter: usize = 2;
+    while Path::new(&candidate).exists() {
+        candidate = format!("{}{} (restored {}){}", parent, stem, counter, extension);
+        counter += 1;
+    }
+    candidate
+}
+
+/// Type of error that can occur while restoring one file.
+#[derive(Debug)]
+pub enum RestoreError {
+    /// File in backup or restored file doesn't match hash saved in map.
+    Corrupted(String),
+    /// File couldn't be copied.
+    Failed(String),
+}
+
+/// Restores one file from output path to input path (in BackupEntry).
+///
+/// File in backup is checked against hash saved in map before copying, and if it doesn't match, file isn't restored. Restored file is checked again after copying.
+/// Compressed and encrypted files are decrypted and decompressed while restoring, chunked files are put together from their chunks.
+///
+/// Returns error if file is corrupted or if it can't be copied.
+pub fn restore_file(entry: &BackupEntry) -> Result<(), RestoreError> {
+    if entry.compression != CompressionCodec::None || entry.encryption != EncryptionCipher::None || entry.chunked {
+        return match open_entry(entry) {
+            Ok(mut reader) => restore_file_from_reader(entry, &mut reader),
+            Err(e) => Err(RestoreError::Failed(format!("Can't restore {}: {}", &entry.input_path, e))),
+        };
+    }
+    match hash_entry(entry) {
+        Err(e) => return Err(RestoreError::Failed(format!("Can't restore {}: {}", &entry.input_path, e))),
+        Ok(hash) => {
+            if hash != entry.hash {
+                return Err(RestoreError::Corrupted(format!("File {} in backup is corrupted, it won't be restored", &entry.output_path)));
+            }
+        }
+    }
+
+    // Copying is done by reversed entry, so the same function as in backup can be used
+    let reversed = BackupEntry { input_path: entry.output_path.clone(), output_path: entry.input_path.clone(), is_file: true, hash: entry.hash.clone(), ..BackupEntry::new() };
+    if let Err(e) = copy_file(&reversed) {
+        return Err(RestoreError::Failed(e));
+    }
+
+    match generate_hash_meow_hash(&entry.input_path) {
+        Ok(hash) if hash == entry.hash => Ok(()),
+        Ok(_) => Err(RestoreError::Corrupted(format!("Hashes of restored file {} and file in backup {} don't match", &entry.input_path, &entry.output_path))),
+        Err(e) => Err(RestoreError::Corrupted(format!("Can't verify restored file {}: {}", &entry.input_path, e)))
+    }
+}
+
+/// Restores one file from provided reader (for example file inside archive) to input path (in BackupEntry).
+///
+/// Content is written to temporary file next to input path, which is renamed to input path only if its hash matches hash saved in map - otherwise it's removed.
+///
+/// Returns error if file is corrupted or if it can't be written.
+pub fn restore_file_from_reader(entry: &BackupEntry, reader: &mut dyn Read) -> Result<(), RestoreError> {
+    let partial_path = format!("{}.part", &entry.input_path);
+    if let Err(e) = create_parent_folder(&partial_path) {
+        return Err(RestoreError::Failed(format!("Can't restore {}: {}", &entry.input_path, e)));
+    }
+    let written = fs::File::create(&partial_path).and_then(|file| {
+        let mut buf_writer = BufWriter::new(file);
+        std::io::copy(reader, &mut buf_writer)?;
+        buf_writer.flush()
+    });
+    if let Err(e) = written {
+        let _ = fs::remove_file(&partial_path);
+        return Err(RestoreError::Failed(format!("Can't restore {}: {}", &entry.input_path, e)));
+    }
+
+    match generate_hash_meow_hash(&partial_path) {
+        Ok(hash) if hash == entry.hash => fs::rename(&partial_path, &entry.input_path).map_err(|e| RestoreError::Failed(format!("Can't restore {}: {}", &entry.input_path, e))),
+        _ => {
+            let _ = fs::remove_file(&partial_path);
+            Err(RestoreError::Corrupted(format!("File {} in backup is corrupted, it won't be restored", &entry.output_path)))
+        }
+    }
+}
+
+/// Restores one file stored in archive with provided path to input path (in BackupEntry), reading archive only until file is found.
+///
+/// Returns error if archive can't be read, file isn't found in it, or file is corrupted.
+pub fn restore_file_from_archive(entry: &BackupEntry, archive: &str) -> Result<(), RestoreError> {
+    let backup_folder = Path::new(archive).parent().and_then(|x| x.to_str()).unwrap_or("");
+    let member = member_name(backup_folder, &entry.output_path);
+    let mut result = Err(RestoreError::Failed(format!("File {} not found in archive {}", &entry.output_path, archive)));
+    let read = read_archive(archive, |name, reader| {
+        if name != member {
+            return true;
+        }
+        result = restore_file_from_reader(entry, reader);
+        false
+    });
+    match read {
+        Err(e) if result.is_err() => Err(RestoreError::Failed(e)),
+        _ => result,
+    }
+}
@@ -0,0 +1,121 @@
+use crate::backups::helpers::dirs::find_previous_backups;
+use crate::backups::helpers::pool::{is_connected, drive_label};
+use crate::backups::helpers::formatting::format_timestamp;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use chrono::Utc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drive_status() {
+        let now = Utc::now().timestamp() as usize;
+        let mut state = HashMap::new();
+        state.insert(String::from("/non/existing/a"), now - 10 * 86400);
+        state.insert(String::from("/non/existing/b"), now - 86400);
+        let pool = vec![String::from("/non/existing/a"), String::from("/non/existing/b"), String::from("/non/existing/c")];
+
+        let report = pool_status(&pool, true, &state, 7);
+        assert!(!report.is_ok());
+        assert_eq!(report.drives[0].days_since, Some(10));
+        assert!(report.drives[0].stale);
+        assert!(!report.drives[1].stale);
+        assert!(!report.drives[1].connected);
+        assert_eq!(report.drives[2].last_backup, "never");
+        assert!(report.drives[2].stale);
+        assert!(report.to_text().contains("WARNING"));
+    }
+}
+
+/// Contains status of one output folder (drive from pool), intended to be printed by ```status``` command.
+#[derive(Serialize)]
+pub struct DriveStatus {
+    pub folder: String,
+    /// Name of drive from its marker file, or its output folder.
+    pub label: String,
+    pub connected: bool,
+    /// Number of backups on drive, None if drive isn't connected.
+    pub backups: Option<usize>,
+    /// Date of last backup on drive, "never" if drive was never updated.
+    pub last_backup: String,
+    /// Number of full days since last backup, None if drive was never updated.
+    pub days_since: Option<u64>,
+    /// True if drive wasn't updated for more days than allowed.
+    pub stale: bool,
+}
+
+/// Contains status of all output folders checked by ```status``` command.
+#[derive(Serialize)]
+pub struct StatusReport {
+    /// Number of days after which drive is reported as not updated.
+    pub max_days: u64,
+    pub drives: Vec<DriveStatus>,
+}
+
+impl StatusReport {
+    /// Returns true if every drive was updated recently enough.
+    pub fn is_ok(&self) -> bool {
+        self.drives.iter().all(|x| !x.stale)
+    }
+
+    /// Returns report as human-readable text, one drive per line, with warning for every drive that wasn't updated recently enough.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for drive in &self.drives {
+            let connected = match drive.backups {
+                Some(backups) => format!("connected, {} backups", backups),
+                None => String::from("not connected"),
+            };
+            text += &format!("{} ({}): {}, last backup: {}", &drive.label, &drive.folder, connected, &drive.last_backup);
+            if let Some(days) = drive.days_since {
+                text += &format!(" ({} days ago)", days);
+            }
+            text += "\n";
+            if drive.stale {
+                text += &format!("    WARNING: drive wasn't updated for more than {} days\n", self.max_days);
+            }
+        }
+        text.trim_end().to_string()
+    }
+}
+
+/// Checks when every output folder from pool was updated - for connected drives newest backup found on drive is used, for others time saved in status file after last backup.
+///
+/// Drives from pool are connected only if they contain marker file - if ```markers``` is false (output folders aren't pool), every existing folder is connected.
+///
+/// Drive is stale if it was never updated, or its last backup is older than provided amount of days.
+pub fn pool_status(pool: &[String], markers: bool, state: &HashMap<String, usize>, max_days: u64) -> StatusReport {
+    let now = Utc::now().timestamp() as usize;
+    let mut drives = vec![];
+    for folder in pool {
+        let connected = is_connected(folder) || (!markers && Path::new(folder).is_dir());
+        let mut last_backup = state.get(folder).cloned().unwrap_or(0);
+        let mut backups = None;
+        if connected {
+            if let Ok(maps) = find_previous_backups(folder) {
+                last_backup = last_backup.max(maps.iter().map(|x| x.timestamp).max().unwrap_or(0));
+                backups = Some(maps.len());
+            }
+        }
+        let days_since = match last_backup {
+            0 => None,
+            _ => Some((now.saturating_sub(last_backup) / 86400) as u64),
+        };
+        drives.push(DriveStatus {
+            folder: folder.clone(),
+            label: drive_label(folder),
+            connected,
+            backups,
+            last_backup: match last_backup {
+                0 => String::from("never"),
+                _ => format_timestamp(last_backup),
+            },
+            days_since,
+            stale: days_since.is_none_or(|x| x > max_days),
+        });
+    }
+    StatusReport { max_days, drives }
+}
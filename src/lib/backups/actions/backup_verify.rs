@@ -0,0 +1,318 @@
+use crate::backups::map::backup_map::BackupMap;
+use crate::backups::map::backup_dir::BackupDir;
+use crate::backups::map::backup_entry::BackupEntry;
+use crate::backups::map::archive_format::ArchiveFormat;
+use crate::backups::helpers::hashing::generate_hash_meow_hash_reader;
+use crate::backups::helpers::compression::hash_entry;
+use crate::backups::helpers::chunking::verify_chunks;
+use crate::backups::helpers::parity::{parity_path, repair_from_parity};
+use crate::backups::helpers::archive::{archive_path, member_name, read_archive};
+use crate::backups::traits::backup_serialize::save_map_to_json;
+use crate::{S_MAP, S_SEPARATOR};
+use serde::Serialize;
+use scoped_threadpool::Pool;
+use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use walkdir::WalkDir;
+use chrono::Utc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::map::backup_mode::BackupMode;
+    use crate::backups::helpers::hashing::generate_hash_meow_hash;
+    use crate::backups::helpers::archive::write_archive;
+    use crate::backups::helpers::parity::create_parity;
+    use std::fs;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_verify_map() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("ok.txt"), "ok").unwrap();
+        fs::write(root.join("corrupted.txt"), "corrupted").unwrap();
+        fs::write(root.join("extra.txt"), "extra").unwrap();
+        let path = |name: &str| root.join(name).to_str().unwrap().to_string();
+
+        let mut dir = BackupDir::new();
+        dir.root_output = root.to_str().unwrap().to_string();
+        dir.backup_entries.push(BackupEntry { input_path: String::new(), output_path: path("ok.txt"), is_file: true, hash: generate_hash_meow_hash(&path("ok.txt")).unwrap(), ..BackupEntry::new() });
+        dir.backup_entries.push(BackupEntry { input_path: String::new(), output_path: path("corrupted.txt"), is_file: true, hash: String::from("12345"), ..BackupEntry::new() });
+        dir.backup_entries.push(BackupEntry { input_path: String::new(), output_path: path("missing.txt"), is_file: true, hash: String::from("12345"), ..BackupEntry::new() });
+        let mut map = BackupMap::new(BackupMode::Multiple);
+        map.output_folder = root.to_str().unwrap().to_string();
+        map.backup_dirs = vec![dir];
+
+        let report = verify_map(&map);
+        assert_eq!(report.checked, 3);
+        assert_eq!(report.corrupted, vec![path("corrupted.txt")]);
+        assert_eq!(report.missing, vec![path("missing.txt")]);
+        assert_eq!(report.extra, vec![path("extra.txt")]);
+        assert!(!report.is_ok());
+
+        save_verification(&mut map).unwrap();
+        assert!(map.last_verified > 0);
+        assert!(root.join(S_MAP).exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_verify_repairs_from_parity() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&root).unwrap();
+        let path = |name: &str| root.join(name).to_str().unwrap().to_string();
+        fs::write(path("a.txt"), "content of file ".repeat(1000)).unwrap();
+        create_parity(&path("a.txt"), 10).unwrap();
+
+        let mut dir = BackupDir::new();
+        dir.root_output = root.to_str().unwrap().to_string();
+        dir.backup_entries.push(BackupEntry { input_path: String::new(), output_path: path("a.txt"), is_file: true, hash: generate_hash_meow_hash(&path("a.txt")).unwrap(), parity: 10, ..BackupEntry::new() });
+        fs::write(path("a.txt"), "content of file ".repeat(999) + "CONTENT of file ").unwrap();
+
+        let report = verify_folder(&dir);
+        assert_eq!(report.repaired, vec![path("a.txt")]);
+        assert!(report.is_ok());
+        assert_eq!(fs::read_to_string(path("a.txt")).unwrap(), "content of file ".repeat(1000));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_verify_archive() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(root.join("source")).unwrap();
+        fs::create_dir_all(root.join("backup")).unwrap();
+        fs::write(root.join("source").join("ok.txt"), "ok").unwrap();
+        fs::write(root.join("source").join("corrupted.txt"), "corrupted").unwrap();
+        let path = |name: &str| root.join(name).to_str().unwrap().to_string();
+
+        let mut dir = BackupDir::new();
+        dir.backup_entries.push(BackupEntry { input_path: path("source/ok.txt"), output_path: path("backup/source/ok.txt"), is_file: true, hash: generate_hash_meow_hash(&path("source/ok.txt")).unwrap(), ..BackupEntry::new() });
+        dir.backup_entries.push(BackupEntry { input_path: path("source/corrupted.txt"), output_path: path("backup/source/corrupted.txt"), is_file: true, hash: String::from("12345"), ..BackupEntry::new() });
+        let mut map = BackupMap::new(BackupMode::Multiple);
+        map.output_folder = path("backup");
+        map.archive = ArchiveFormat::TarZst;
+        map.backup_dirs = vec![dir];
+        write_archive(&map).unwrap();
+        map.backup_dirs[0].backup_entries.push(BackupEntry { input_path: String::new(), output_path: path("backup/source/missing.txt"), is_file: true, hash: String::from("12345"), ..BackupEntry::new() });
+
+        let report = verify_map(&map);
+        assert_eq!(report.checked, 3);
+        assert_eq!(report.corrupted, vec![path("backup/source/corrupted.txt")]);
+        assert_eq!(report.missing, vec![path("backup/source/missing.txt")]);
+        assert!(report.extra.is_empty());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
+
+/// Contains result of verification of one backup - every path is output path of file.
+#[derive(Serialize, Default, Clone)]
+pub struct VerifyReport {
+    pub id: String,
+    /// Number of files from map that were checked.
+    pub checked: usize,
+    /// Files present in map, but not in backup.
+    pub missing: Vec<String>,
+    /// Files with hash different than hash saved in map.
+    pub corrupted: Vec<String>,
+    /// Files present in backup folders, but not in map.
+    pub extra: Vec<String>,
+    /// Damaged files repaired in place from their parity files - they are OK now.
+    pub repaired: Vec<String>,
+}
+
+impl VerifyReport {
+    /// Returns true if no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.corrupted.is_empty() && self.extra.is_empty()
+    }
+
+    /// Adds results of other report to this one.
+    pub fn merge(&mut self, other: VerifyReport) {
+        self.checked += other.checked;
+        self.missing.extend(other.missing);
+        self.corrupted.extend(other.corrupted);
+        self.extra.extend(other.extra);
+        self.repaired.extend(other.repaired);
+    }
+
+    /// Returns report as human-readable text, one problem per line.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("Backup {}: checked {} files", &self.id, self.checked);
+        for path in &self.missing {
+            text += &format!("\n    Missing: {}", path);
+        }
+        for path in &self.corrupted {
+            text += &format!("\n    Corrupted: {}", path);
+        }
+        for path in &self.extra {
+            text += &format!("\n    Extra: {}", path);
+        }
+        for path in &self.repaired {
+            text += &format!("\n    Repaired from parity: {}", path);
+        }
+        match self.is_ok() {
+            true => text += "\n    All files are OK",
+            false => text += &format!("\n    {} missing, {} corrupted, {} extra files", self.missing.len(), self.corrupted.len(), self.extra.len()),
+        }
+        text
+    }
+}
+
+/// Verifies all files of backup against hashes saved in its map, and searches backup folders for files not present in map.
+///
+/// Works concurrently, max amount of active threads is 4, because reading from too many hard drive's locations at once can slow down process. Backups stored in archive are verified by ```verify_archive```.
+pub fn verify_map(map: &BackupMap) -> VerifyReport {
+    println!("Verifying backup {}...", &map.id);
+    if map.archive != ArchiveFormat::Folder {
+        let report = verify_archive(map);
+        // Damaged archive is repaired from its parity file, if it has one, and verified again
+        let path = archive_path(map);
+        if report.corrupted.is_empty() || !Path::new(&parity_path(&path)).exists() {
+            return report;
+        }
+        return match repair_from_parity(&path) {
+            Ok(_) => {
+                let new_report = verify_archive(map);
+                match new_report.corrupted.is_empty() {
+                    true => VerifyReport { repaired: vec![path], ..new_report },
+                    false => new_report,
+                }
+            }
+            Err(e) => {
+                println!("{}", e);
+                report
+            }
+        };
+    }
+    let report = Arc::new(Mutex::new(VerifyReport { id: map.id.clone(), ..VerifyReport::default() }));
+    let mut thread_pool = Pool::new(4);
+    thread_pool.scoped(|scope| {
+        for dir in &map.backup_dirs {
+            let report_ref = Arc::clone(&report);
+            scope.execute(move || {
+                let folder_report = verify_folder(dir);
+                report_ref.lock().unwrap().merge(folder_report);
+            });
+        }
+    });
+    let report = report.lock().unwrap().clone();
+    report
+}
+
+/// Verifies files of one BackupDir against hashes saved in map, and searches its root output for files not present in map.
+///
+/// Corrupted files of entries with parity are repaired in place from their parity files, and checked again.
+pub fn verify_folder(folder: &BackupDir) -> VerifyReport {
+    let mut report = VerifyReport::default();
+    for entry in folder.backup_entries.iter().filter(|x| x.is_file) {
+        report.checked += 1;
+        let (mut missing, mut corrupted) = verify_entry(entry);
+        if !corrupted.is_empty() && entry.parity > 0 {
+            let repaired = corrupted.clone();
+            match repaired.iter().map(|x| repair_from_parity(x)).collect::<Result<Vec<usize>, String>>() {
+                Ok(_) => {
+                    let (new_missing, new_corrupted) = verify_entry(entry);
+                    if new_corrupted.is_empty() {
+                        report.repaired.extend(repaired);
+                    }
+                    missing = new_missing;
+                    corrupted = new_corrupted;
+                }
+                Err(e) => println!("{}", e),
+            }
+        }
+        report.missing.extend(missing);
+        report.corrupted.extend(corrupted);
+    }
+
+    if !folder.root_output.is_empty() && Path::new(&folder.root_output).is_dir() {
+        let known: HashSet<String> = folder.backup_entries.iter()
+            .flat_map(|x| match x.parity {
+                0 => vec![x.output_path.clone()],
+                _ => vec![x.output_path.clone(), parity_path(&x.output_path)],
+            })
+            .collect();
+        for entry in WalkDir::new(&folder.root_output).into_iter().filter_map(|e| e.ok()) {
+            if !entry.path().is_file() || entry.path().ends_with(S_MAP) {
+                continue;
+            }
+            if let Some(path) = entry.path().to_str() {
+                if !known.contains(path) {
+                    report.extra.push(String::from(path));
+                }
+            }
+        }
+    }
+    report
+}
+
+/// Checks file of one entry against hash saved in map - chunked files are checked chunk after chunk first.
+///
+/// Returns tuple of paths of missing files and paths of corrupted files (both empty if file is OK).
+fn verify_entry(entry: &BackupEntry) -> (Vec<String>, Vec<String>) {
+    if entry.chunked {
+        let (missing, corrupted) = verify_chunks(entry);
+        if !missing.is_empty() || !corrupted.is_empty() {
+            return (missing, corrupted);
+        }
+    } else if !Path::new(&entry.output_path).exists() {
+        return (vec![entry.output_path.clone()], vec![]);
+    }
+    match hash_entry(entry) {
+        Ok(hash) if hash == entry.hash => (vec![], vec![]),
+        Ok(_) => (vec![], vec![entry.output_path.clone()]),
+        Err(e) => {
+            println!("{}", e);
+            (vec![], vec![entry.output_path.clone()])
+        }
+    }
+}
+
+/// Verifies backup stored in archive, reading whole archive once - every file in archive is checked against hash saved in map, and files not present in map are extra.
+///
+/// If archive is damaged, files that couldn't be read from it are corrupted. If it doesn't exist, all files are missing. Paths in report are output paths saved in map.
+pub fn verify_archive(map: &BackupMap) -> VerifyReport {
+    let mut report = VerifyReport { id: map.id.clone(), ..VerifyReport::default() };
+    let mut expected: HashMap<String, &BackupEntry> = map.backup_dirs.iter()
+        .flat_map(|x| x.backup_entries.iter())
+        .filter(|x| x.is_file)
+        .map(|x| (member_name(&map.output_folder, &x.output_path), x))
+        .collect();
+    report.checked = expected.len();
+
+    let path = archive_path(map);
+    let archive_exists = Path::new(&path).exists();
+    let result = read_archive(&path, |name, reader| {
+        match expected.remove(name) {
+            None => report.extra.push(format!("{}{}{}", &map.output_folder, S_SEPARATOR, name.replace('/', S_SEPARATOR))),
+            Some(entry) => match generate_hash_meow_hash_reader(reader, name) {
+                Ok(hash) if hash == entry.hash => {}
+                _ => report.corrupted.push(entry.output_path.clone()),
+            }
+        }
+        true
+    });
+    let mut remaining: Vec<String> = expected.values().map(|x| x.output_path.clone()).collect();
+    remaining.sort();
+    match result {
+        Ok(_) => report.missing.extend(remaining),
+        Err(e) => {
+            println!("{}", e);
+            match archive_exists {
+                true => report.corrupted.extend(remaining),
+                false => report.missing.extend(remaining),
+            }
+        }
+    }
+    report
+}
+
+/// Saves current time as time of last verification in map, then saves map to its file.
+///
+/// Returns error if map can't be saved.
+pub fn save_verification(map: &mut BackupMap) -> Result<(), String> {
+    map.last_verified = Utc::now().timestamp() as usize;
+    save_map_to_json(map)
+}
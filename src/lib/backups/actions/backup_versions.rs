@@ -0,0 +1,135 @@
+use crate::backups::map::backup_map::BackupMap;
+use crate::backups::map::backup_entry::BackupEntry;
+use crate::backups::map::archive_format::ArchiveFormat;
+use crate::backups::actions::backup_restore::{restore_file, restore_file_from_archive, RestoreError};
+use crate::backups::helpers::archive::archive_path;
+use crate::backups::helpers::formatting::format_timestamp;
+use crate::S_SEPARATOR;
+use std::io;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::map::backup_mode::BackupMode;
+    use crate::backups::map::backup_dir::BackupDir;
+
+    fn map_with_file(timestamp: usize, hash: &str) -> BackupMap {
+        let mut dir = BackupDir::new();
+        dir.backup_entries.push(BackupEntry { input_path: String::from("/home/user/a.txt"), output_path: format!("/backup/{}/a.txt", timestamp), is_file: true, hash: String::from(hash), ..BackupEntry::new() });
+        let mut map = BackupMap::new(BackupMode::Multiple);
+        map.id = timestamp.to_string();
+        map.timestamp = timestamp;
+        map.backup_dirs = vec![dir];
+        map
+    }
+
+    #[test]
+    fn test_find_file_versions() {
+        let maps = vec![map_with_file(3, "b"), map_with_file(1, "a"), map_with_file(2, "a")];
+        let versions = find_file_versions(&maps, "/home/user/a.txt");
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].timestamp, 1);
+        assert_eq!(versions[0].entries.len(), 2);
+        assert_eq!(versions[0].entries[0].output_path, "/backup/2/a.txt");
+        assert_eq!(versions[1].timestamp, 3);
+        assert!(find_file_versions(&maps, "/home/user/b.txt").is_empty());
+    }
+}
+
+/// Contains one distinct version of file, found in one or more backups.
+#[derive(Clone)]
+pub struct FileVersion {
+    /// Timestamp of oldest backup containing this version.
+    pub timestamp: usize,
+    /// Id of oldest backup containing this version.
+    pub backup_id: String,
+    pub hash: String,
+    /// Every copy of this version, starting from newest backup.
+    pub entries: Vec<BackupEntry>,
+    /// Path of archive containing every copy from ```entries```, or empty string if copy isn't stored in archive.
+    pub archives: Vec<String>,
+}
+
+/// Finds all distinct versions of file with provided input path in provided maps.
+///
+/// Versions are distinguished by hash - if the same file is present in many backups, it's returned only once, with timestamp of oldest of them.
+///
+/// Returns versions sorted from oldest to newest, vector is empty if file isn't found in any backup.
+pub fn find_file_versions(maps: &[BackupMap], input_path: &str) -> Vec<FileVersion> {
+    let input_path = input_path.trim_end_matches(S_SEPARATOR);
+    let mut maps: Vec<&BackupMap> = maps.iter().collect();
+    maps.sort_by_key(|x| x.timestamp);
+
+    let mut versions: Vec<FileVersion> = vec![];
+    for map in maps {
+        let archive = match map.archive {
+            ArchiveFormat::Folder => String::new(),
+            _ => archive_path(map),
+        };
+        for dir in &map.backup_dirs {
+            for entry in dir.backup_entries.iter().filter(|x| x.is_file && x.input_path == input_path) {
+                match versions.iter_mut().find(|x| x.hash == entry.hash) {
+                    Some(version) => {
+                        version.entries.insert(0, entry.clone());
+                        version.archives.insert(0, archive.clone());
+                    }
+                    None => versions.push(FileVersion { timestamp: map.timestamp, backup_id: map.id.clone(), hash: entry.hash.clone(), entries: vec![entry.clone()], archives: vec![archive.clone()] }),
+                }
+            }
+        }
+    }
+    versions
+}
+
+/// Prints numbered list of provided versions to user.
+pub fn print_file_versions(versions: &[FileVersion]) {
+    for (index, version) in versions.iter().enumerate() {
+        println!("{}. {} - backup {} (found in {} backups)", index + 1, format_timestamp(version.timestamp), &version.backup_id, version.entries.len());
+    }
+}
+
+/// Restores provided version of file to destination path.
+///
+/// Every copy of version is tried, starting from newest backup, until one of them is restored with matching hash.
+///
+/// Returns error if no copy of version could be restored.
+pub fn restore_file_version(version: &FileVersion, destination: &str) -> Result<(), String> {
+    let mut errors = vec![];
+    for (entry, archive) in version.entries.iter().zip(version.archives.iter()) {
+        let restored = BackupEntry { input_path: String::from(destination), ..entry.clone() };
+        let result = match archive.is_empty() {
+            true => restore_file(&restored),
+            false => restore_file_from_archive(&restored, archive),
+        };
+        match result {
+            Ok(_) => {
+                println!("Restored version from {} to {}", format_timestamp(version.timestamp), destination);
+                return Ok(());
+            }
+            Err(RestoreError::Corrupted(e)) | Err(RestoreError::Failed(e)) => {
+                println!("{}, trying another copy...", e);
+                errors.push(e);
+            }
+        }
+    }
+    Err(format!("No copy of this version could be restored: {}", errors.join(", ")))
+}
+
+/// Asks user which version should be restored, checks if it's valid, if yes, its number (starting from 1) is returned.
+///
+/// If user writes invalid string, he's asked to do it again.
+pub fn get_version_from_user(versions_amount: usize) -> usize {
+    let mut version = String::new();
+    println!("Which version do you want to restore (1-{})?:", versions_amount);
+    if io::stdin().read_line(&mut version).is_err() {
+        println!("Error reading input for version, asking again...");
+        return get_version_from_user(versions_amount);
+    }
+    match version.trim().parse::<usize>() {
+        Ok(number) if number >= 1 && number <= versions_amount => number,
+        _ => {
+            println!("Wrong input provided, please write number from 1 to {}", versions_amount);
+            get_version_from_user(versions_amount)
+        }
+    }
+}
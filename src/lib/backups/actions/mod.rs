@@ -0,0 +1,8 @@
+pub mod backup_restore;
+pub mod backup_versions;
+pub mod backup_list;
+pub mod backup_diff;
+pub mod backup_verify;
+pub mod backup_repair;
+pub mod backup_migrate;
+pub mod backup_status;
\ No newline at end of file
@@ -0,0 +1,232 @@
+use crate::backups::map::backup_map::BackupMap;
+use crate::backups::map::backup_dir::BackupDir;
+use crate::backups::map::backup_entry::BackupEntry;
+use crate::backups::map::archive_format::ArchiveFormat;
+use crate::backups::helpers::map_io::write_map;
+use crate::{S_SEPARATOR, S_ARCHIVE, S_MAP};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Seek, Write};
+use std::time::SystemTime;
+use tar::{Archive, Builder};
+use zip::{ZipArchive, ZipWriter, CompressionMethod};
+use zip::write::FileOptions;
+use chrono::{DateTime, Datelike, Local, Timelike};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::map::backup_mode::BackupMode;
+    use std::fs;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_write_and_read_archive() {
+        for format in &[ArchiveFormat::Tar, ArchiveFormat::TarZst, ArchiveFormat::Zip] {
+            let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+            fs::create_dir_all(root.join("source").join("folder")).unwrap();
+            fs::create_dir_all(root.join("backup")).unwrap();
+            fs::write(root.join("source").join("folder").join("a.txt"), "a").unwrap();
+            let path = |name: &str| root.join(name).to_str().unwrap().to_string();
+
+            let mut dir = BackupDir::new();
+            dir.root_input = path("source");
+            dir.root_output = path("backup/source");
+            dir.backup_entries.push(BackupEntry { input_path: path("source/folder"), output_path: path("backup/source/folder"), is_file: false, hash: String::new(), ..BackupEntry::new() });
+            dir.backup_entries.push(BackupEntry { input_path: path("source/folder/a.txt"), output_path: path("backup/source/folder/a.txt"), is_file: true, hash: String::new(), ..BackupEntry::new() });
+            dir.backup_entries.push(BackupEntry { input_path: path("source/missing.txt"), output_path: path("backup/source/missing.txt"), is_file: true, hash: String::new(), ..BackupEntry::new() });
+            let mut map = BackupMap::new(BackupMode::Multiple);
+            map.output_folder = path("backup");
+            map.archive = format.clone();
+            map.backup_dirs = vec![dir];
+
+            let archived = write_archive(&map).unwrap();
+            assert_eq!(archived[0].files, 1);
+            assert_eq!(archived[0].folders, 1);
+            assert!(archive_path(&map).ends_with(format.extension()));
+            if *format == ArchiveFormat::Zip {
+                embed_map_in_zip(&map).unwrap();
+                let mut zip = ZipArchive::new(File::open(archive_path(&map)).unwrap()).unwrap();
+                assert!(zip.by_name(S_MAP).is_ok());
+            }
+
+            let mut members = vec![];
+            read_archive(&archive_path(&map), |name, reader| {
+                let mut content = String::new();
+                reader.read_to_string(&mut content).unwrap();
+                members.push((String::from(name), content));
+                true
+            }).unwrap();
+            assert_eq!(members, vec![(String::from("source/folder/a.txt"), String::from("a"))]);
+            fs::remove_dir_all(root).unwrap();
+        }
+    }
+}
+
+/// Returns path of archive containing files of backup, placed in its output folder.
+pub fn archive_path(map: &BackupMap) -> String {
+    format!("{}{}{}{}", &map.output_folder, S_SEPARATOR, S_ARCHIVE, map.archive.extension())
+}
+
+/// Returns name of file inside archive - its output path relative to folder of backup, always with slashes as separators.
+pub fn member_name(backup_folder: &str, output_path: &str) -> String {
+    let relative = output_path.strip_prefix(backup_folder).unwrap_or(output_path);
+    relative.trim_start_matches(S_SEPARATOR).replace(S_SEPARATOR, "/")
+}
+
+/// Creates archive in output folder of map, in format chosen in map, and adds every entry to it, from input path, with name based on output path.
+///
+/// Files and folders that can't be added are printed to user and skipped.
+///
+/// Returns dirs with only entries added to archive, or error if archive can't be created or finished.
+pub fn write_archive(map: &BackupMap) -> Result<Vec<BackupDir>, String> {
+    let path = archive_path(map);
+    let file = match File::create(&path) {
+        Ok(file) => BufWriter::new(file),
+        Err(e) => return Err(format!("Can't create archive {}: {}", &path, e)),
+    };
+    println!("Creating archive {}...", &path);
+
+    let archived = match map.archive {
+        ArchiveFormat::Zip => {
+            let mut zip = ZipWriter::new(file);
+            let archived = append_entries(map, |entry, name| match entry.is_file {
+                true => add_file_to_zip(&mut zip, &entry.input_path, name),
+                false => zip.add_directory(name, FileOptions::default()).map_err(|e| e.to_string()),
+            });
+            zip.finish().and_then(|mut x| Ok(x.flush()?)).map_err(|e| format!("Can't finish archive {}: {}", &path, e))?;
+            archived
+        }
+        ArchiveFormat::TarZst => {
+            let encoder = zstd::Encoder::new(file, 0).map_err(|e| format!("Can't create compressor for archive {}: {}", &path, e))?;
+            let mut builder = Builder::new(encoder);
+            let archived = append_entries(map, |entry, name| add_entry_to_tar(&mut builder, entry, name));
+            let encoder = builder.into_inner().map_err(|e| format!("Can't finish archive {}: {}", &path, e))?;
+            encoder.finish().and_then(|mut x| x.flush()).map_err(|e| format!("Can't finish archive {}: {}", &path, e))?;
+            archived
+        }
+        _ => {
+            let mut builder = Builder::new(file);
+            let archived = append_entries(map, |entry, name| add_entry_to_tar(&mut builder, entry, name));
+            builder.into_inner().and_then(|mut x| x.flush()).map_err(|e| format!("Can't finish archive {}: {}", &path, e))?;
+            archived
+        }
+    };
+    Ok(archived)
+}
+
+/// Adds every entry of map to archive with provided function, which gets entry and its name inside archive. Returns dirs with only added entries.
+fn append_entries<F: FnMut(&BackupEntry, &str) -> Result<(), String>>(map: &BackupMap, mut append: F) -> Vec<BackupDir> {
+    let mut archived = vec![];
+    for dir in &map.backup_dirs {
+        println!("Archiving folder {}...", &dir.root_input);
+        let mut archived_dir = BackupDir { root_input: dir.root_input.clone(), root_output: dir.root_output.clone(), files: 0, folders: 0, backup_entries: vec![] };
+        for entry in &dir.backup_entries {
+            match append(entry, &member_name(&map.output_folder, &entry.output_path)) {
+                Ok(_) => archived_dir.backup_entries.push(entry.clone()),
+                Err(e) => println!("Couldn't add {} to archive: {}", &entry.input_path, e),
+            }
+        }
+        archived_dir.files = archived_dir.backup_entries.iter().filter(|x| x.is_file).count();
+        archived_dir.folders = archived_dir.backup_entries.iter().filter(|x| !x.is_file).count();
+        println!("Archived {} folders and {} files from folder {}", archived_dir.folders, archived_dir.files, &dir.root_input);
+        archived.push(archived_dir);
+    }
+    archived
+}
+
+/// Adds file or folder to tar archive, with provided name.
+fn add_entry_to_tar<W: Write>(builder: &mut Builder<W>, entry: &BackupEntry, name: &str) -> Result<(), String> {
+    let result = match entry.is_file {
+        true => builder.append_path_with_name(&entry.input_path, name),
+        false => builder.append_dir(name, &entry.input_path),
+    };
+    result.map_err(|e| e.to_string())
+}
+
+/// Adds file to zip archive with provided name, compressed with deflate and with its original modification time.
+fn add_file_to_zip<W: Write + Seek>(zip: &mut ZipWriter<W>, input_path: &str, name: &str) -> Result<(), String> {
+    let file = File::open(input_path).map_err(|e| e.to_string())?;
+    let metadata = file.metadata().map_err(|e| e.to_string())?;
+    let mut options = FileOptions::default().compression_method(CompressionMethod::Deflated).large_file(metadata.len() > u32::MAX as u64);
+    if let Ok(modified) = metadata.modified() {
+        options = options.last_modified_time(zip_time(modified));
+    }
+    zip.start_file(name, options).map_err(|e| e.to_string())?;
+    std::io::copy(&mut BufReader::new(file), zip).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Converts time to format used in zip archives, in local time zone. Times that can't be saved in zip (before 1980) are changed to 1980-01-01.
+fn zip_time(time: SystemTime) -> zip::DateTime {
+    let time: DateTime<Local> = time.into();
+    zip::DateTime::from_date_and_time(time.year() as u16, time.month() as u8, time.day() as u8, time.hour() as u8, time.minute() as u8, time.second() as u8).unwrap_or_default()
+}
+
+/// Adds map of backup to its zip archive as ```.map.json``` file (in format of map), so archive contains everything needed to restore backup, even if it's copied without backup folder.
+///
+/// Returns error if archive can't be opened, or map can't be added to it.
+pub fn embed_map_in_zip(map: &BackupMap) -> Result<(), String> {
+    let path = archive_path(map);
+    let file = OpenOptions::new().read(true).write(true).open(&path).map_err(|e| format!("Can't open archive {}: {}", &path, e))?;
+    let mut zip = ZipWriter::new_append(file).map_err(|e| format!("Can't open archive {}: {}", &path, e))?;
+    zip.start_file(S_MAP, FileOptions::default()).map_err(|e| format!("Can't add map to archive {}: {}", &path, e))?;
+    let mut zip = write_map(map, zip).map_err(|e| format!("Can't add map to archive {}: {}", &path, e))?;
+    zip.finish().map_err(|e| format!("Can't finish archive {}: {}", &path, e))?;
+    Ok(())
+}
+
+/// Reads every file from archive with provided path, one by one, and passes its name and reader with its content to provided function.
+///
+/// Format of archive is recognized by its extension. Folders and other non-file entries are skipped. Reading stops when provided function returns false.
+///
+/// Returns error if archive can't be opened, or if it's damaged - in this case some files may have been already read.
+pub fn read_archive<F: FnMut(&str, &mut dyn Read) -> bool>(path: &str, function: F) -> Result<(), String> {
+    let file = match File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => return Err(format!("Can't open archive {}: {}", path, e)),
+    };
+    match ArchiveFormat::from_path(path) {
+        ArchiveFormat::Zip => read_zip(file, path, function),
+        ArchiveFormat::TarZst => match zstd::Decoder::with_buffer(file) {
+            Ok(decoder) => read_tar(decoder, path, function),
+            Err(e) => Err(format!("Can't decompress archive {}: {}", path, e)),
+        },
+        _ => read_tar(file, path, function),
+    }
+}
+
+/// Reads every file from tar archive, passing it to provided function, like ```read_archive```.
+fn read_tar<R: Read, F: FnMut(&str, &mut dyn Read) -> bool>(reader: R, path: &str, mut function: F) -> Result<(), String> {
+    let mut archive = Archive::new(reader);
+    let entries = archive.entries().map_err(|e| format!("Can't read archive {}: {}", path, e))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("Archive {} is damaged: {}", path, e))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = match entry.path() {
+            Ok(name) => name.to_string_lossy().to_string(),
+            Err(e) => return Err(format!("Archive {} contains invalid file name: {}", path, e)),
+        };
+        if !function(&name, &mut entry) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Reads every file from zip archive, passing it to provided function, like ```read_archive```. Map embedded in archive is skipped.
+fn read_zip<R: Read + Seek, F: FnMut(&str, &mut dyn Read) -> bool>(reader: R, path: &str, mut function: F) -> Result<(), String> {
+    let mut archive = ZipArchive::new(reader).map_err(|e| format!("Can't read archive {}: {}", path, e))?;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|e| format!("Archive {} is damaged: {}", path, e))?;
+        if entry.is_dir() || entry.name() == S_MAP {
+            continue;
+        }
+        let name = String::from(entry.name());
+        if !function(&name, &mut entry) {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,282 @@
+use crate::backups::map::backup_dir::BackupDir;
+use crate::backups::map::backup_entry::BackupEntry;
+use crate::backups::map::encryption_cipher::EncryptionCipher;
+use crate::backups::helpers::hashing::generate_hash_meow_hash_reader;
+use crate::backups::helpers::compression::open_stored;
+use crate::backups::helpers::encryption::{ENCRYPTED_EXTENSION, obfuscated_name};
+use crate::backups::modes::backup_dedup::object_path;
+use crate::backups::traits::backup_copy::{compress_file, create_parent_folder};
+use std::fs::{self, metadata, File};
+use std::io::{self, BufReader, ErrorKind, Read};
+use std::path::Path;
+use fastcdc::v2020::StreamCDC;
+use uuid::Uuid;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::helpers::compression::hash_entry;
+    use crate::backups::helpers::hashing::generate_hash_meow_hash;
+    use std::fs;
+    use uuid::Uuid;
+    use walkdir::WalkDir;
+
+    /// Returns pseudo-random content, which (unlike repeated text) is split into many chunks.
+    fn random_content(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..len).map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 56) as u8
+        }).collect()
+    }
+
+    #[test]
+    fn test_store_and_read_chunks() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&root).unwrap();
+        let path = |name: &str| root.join(name).to_str().unwrap().to_string();
+        let count_chunks = || WalkDir::new(path("chunks")).into_iter().filter_map(|e| e.ok()).filter(|e| e.path().is_file()).count();
+        let mut content = random_content(6 * 1024 * 1024, 1);
+        fs::write(path("image.bin"), &content).unwrap();
+
+        let mut entry = BackupEntry { input_path: path("image.bin"), output_path: path("chunks"), is_file: true, hash: generate_hash_meow_hash(&path("image.bin")).unwrap(), chunked: true, ..BackupEntry::new() };
+        entry.chunks = store_chunks(&entry, false).unwrap();
+        assert!(entry.chunks.len() > 1);
+        assert_eq!(count_chunks(), entry.chunks.len());
+        assert_eq!(hash_entry(&entry).unwrap(), entry.hash);
+
+        // Changing few bytes in the middle of file should add only one or two chunks
+        content[3 * 1024 * 1024] ^= 0xff;
+        fs::write(path("image.bin"), &content).unwrap();
+        let previous = entry.chunks.len();
+        entry.hash = generate_hash_meow_hash(&path("image.bin")).unwrap();
+        entry.chunks = store_chunks(&entry, false).unwrap();
+        assert!(count_chunks() <= previous + 2);
+        assert_eq!(hash_entry(&entry).unwrap(), entry.hash);
+
+        // Corrupted chunk is detected, and written again during repair
+        let damaged = chunk_path(&entry, &entry.chunks[0]);
+        fs::write(&damaged, "corrupted").unwrap();
+        assert_eq!(verify_chunks(&entry), (vec![], vec![damaged.clone()]));
+        store_chunks(&entry, true).unwrap();
+        assert_eq!(verify_chunks(&entry), (vec![], vec![]));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_store_chunks_concurrently() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&root).unwrap();
+        let path = |name: &str| root.join(name).to_str().unwrap().to_string();
+        fs::write(path("image.bin"), random_content(6 * 1024 * 1024, 2)).unwrap();
+
+        // The same file stored at once to one chunks folder, like copies of the same file from different input folders
+        let entry = BackupEntry { input_path: path("image.bin"), output_path: path("chunks"), is_file: true, hash: generate_hash_meow_hash(&path("image.bin")).unwrap(), chunked: true, ..BackupEntry::new() };
+        let threads: Vec<_> = (0..4).map(|_| {
+            let entry = entry.clone();
+            std::thread::spawn(move || store_chunks(&entry, false).unwrap())
+        }).collect();
+        let results: Vec<_> = threads.into_iter().map(|x| x.join().unwrap()).collect();
+        let entry = BackupEntry { chunks: results[0].clone(), ..entry };
+        assert_eq!(verify_chunks(&entry), (vec![], vec![]));
+        let stored = WalkDir::new(path("chunks")).into_iter().filter_map(|e| e.ok()).filter(|e| e.path().is_file()).count();
+        assert_eq!(stored, entry.chunks.len());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_chunk_path_with_key() {
+        let entry = BackupEntry { output_path: String::from("/backup/.chunks"), is_file: true, chunked: true, encryption: EncryptionCipher::ChaCha20Poly1305, ..BackupEntry::new() };
+        let plain = chunk_path(&entry, "abcdef");
+        assert!(plain.contains("cdef"));
+
+        let keyed = BackupEntry { chunk_key: String::from("key"), ..entry.clone() };
+        let name = chunk_path(&keyed, "abcdef");
+        assert!(!name.contains("cdef"));
+        assert!(name.starts_with("/backup/.chunks"));
+        assert!(name.ends_with(ENCRYPTED_EXTENSION));
+        assert_eq!(name, chunk_path(&keyed, "abcdef"));
+        assert_ne!(name, chunk_path(&BackupEntry { chunk_key: String::from("other"), ..entry }, "abcdef"));
+    }
+}
+
+/// Files of this size or bigger (in bytes) are split into chunks, if chunking is turned on.
+pub const CHUNKING_THRESHOLD: u64 = 16 * 1024 * 1024;
+/// Minimal size of chunk, in bytes.
+const CHUNK_MIN_SIZE: u32 = 256 * 1024;
+/// Average size of chunk, in bytes.
+const CHUNK_AVG_SIZE: u32 = 1024 * 1024;
+/// Maximal size of chunk, in bytes.
+const CHUNK_MAX_SIZE: u32 = 4 * 1024 * 1024;
+
+/// Marks every file from provided dirs that is at least as big as ```CHUNKING_THRESHOLD``` as chunked, and changes its output path to provided chunks folder. Encrypted files get provided key, from which names of their chunks are derived.
+///
+/// Should be used after assigning codec, cipher and obfuscated names, because output path of chunked file is replaced.
+pub fn assign_chunking(dirs: &mut [BackupDir], chunks_folder: &str, name_key: &str) {
+    for entry in dirs.iter_mut().flat_map(|x| x.backup_entries.iter_mut()).filter(|x| x.is_file) {
+        if metadata(&entry.input_path).map(|x| x.len() >= CHUNKING_THRESHOLD).unwrap_or(false) {
+            entry.chunked = true;
+            entry.output_path = String::from(chunks_folder);
+            if entry.encryption != EncryptionCipher::None {
+                entry.chunk_key = String::from(name_key);
+            }
+        }
+    }
+}
+
+/// Returns path of chunk with provided hash of chunked entry - chunk is placed in chunks folder (output path of entry), with extensions of codec and cipher of entry.
+///
+/// If entry has key for names of chunks, chunk is named by HMAC of its hash, so names of chunks don't reveal their content.
+pub fn chunk_path(entry: &BackupEntry, hash: &str) -> String {
+    let extension = match entry.encryption {
+        EncryptionCipher::None => "",
+        _ => ENCRYPTED_EXTENSION,
+    };
+    let name = match entry.chunk_key.is_empty() {
+        true => String::from(hash),
+        false => obfuscated_name(&entry.chunk_key, hash),
+    };
+    format!("{}{}{}", object_path(&entry.output_path, &name), entry.compression.extension(), extension)
+}
+
+/// Returns paths of all files in which entry is stored - paths of its chunks for chunked files, or just its output path for other entries.
+pub fn stored_paths(entry: &BackupEntry) -> Vec<String> {
+    match entry.chunked {
+        true => entry.chunks.iter().map(|x| chunk_path(entry, x)).collect(),
+        false => vec![entry.output_path.clone()],
+    }
+}
+
+/// Splits input file of entry into chunks with content-defined boundaries, and stores every chunk that isn't present in chunks folder yet, compressed and encrypted like whole files.
+///
+/// Boundaries depend only on content, so after small change of big file only chunks around that change are new. If ```repair``` is true, existing chunks are also checked and written again if they're corrupted.
+///
+/// Returns hashes of all chunks of file, in order, or error if file can't be read or any chunk can't be stored.
+pub fn store_chunks(entry: &BackupEntry, repair: bool) -> Result<Vec<String>, String> {
+    let file = match File::open(&entry.input_path) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => return Err(format!("Couldn't copy file {}: can't open source file: {}", &entry.input_path, e)),
+    };
+    println!("Chunking: {}", &entry.input_path);
+    let mut chunks = vec![];
+    let mut written: usize = 0;
+    for chunk in StreamCDC::new(file, CHUNK_MIN_SIZE, CHUNK_AVG_SIZE, CHUNK_MAX_SIZE) {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => return Err(format!("Couldn't split file {} into chunks: {}", &entry.input_path, e)),
+        };
+        let hash = generate_hash_meow_hash_reader(&mut chunk.data.as_slice(), &entry.input_path)?;
+        let stored = BackupEntry { output_path: chunk_path(entry, &hash), hash: hash.clone(), chunked: false, chunks: vec![], ..entry.clone() };
+        let exists = Path::new(&stored.output_path).exists();
+        let corrupted = exists && repair && !is_chunk_valid(&stored.output_path, &hash, entry);
+        if !exists || corrupted {
+            create_parent_folder(&stored.output_path)?;
+            store_chunk(&stored, &chunk.data, corrupted)?;
+            written += 1;
+        }
+        chunks.push(hash);
+    }
+    println!("Stored {} new chunks of {} for {}", written, chunks.len(), &entry.input_path);
+    Ok(chunks)
+}
+
+/// Writes content of chunk to temporary file with unique name, and then moves it to output path of provided entry, replacing existing chunk only if ```replace``` is true.
+///
+/// The same chunk may be stored at once by many threads copying different folders, so every one of them writes its own file, and chunk that already exists (stored by other thread) is left untouched.
+fn store_chunk(stored: &BackupEntry, data: &[u8], replace: bool) -> Result<(), String> {
+    let temp = BackupEntry { output_path: format!("{}.{}", &stored.output_path, Uuid::new_v4()), ..stored.clone() };
+    compress_file(&temp, &mut &data[..])?;
+    let result = match replace {
+        true => fs::rename(&temp.output_path, &stored.output_path),
+        // Hard link fails if chunk already exists, so existing chunk is never replaced
+        false => match fs::hard_link(&temp.output_path, &stored.output_path) {
+            Err(e) if e.kind() != ErrorKind::AlreadyExists && !Path::new(&stored.output_path).exists() => fs::rename(&temp.output_path, &stored.output_path),
+            _ => Ok(()),
+        },
+    };
+    let _ = fs::remove_file(&temp.output_path);
+    result.map_err(|e| format!("Couldn't store chunk {} of file {}: {}", &stored.output_path, &stored.input_path, e))
+}
+
+/// Returns true if chunk stored in provided path can be read, and its content has provided hash.
+fn is_chunk_valid(path: &str, hash: &str, entry: &BackupEntry) -> bool {
+    match open_stored(path, &entry.compression, &entry.encryption) {
+        Ok(mut reader) => generate_hash_meow_hash_reader(&mut reader, path).map(|x| x == hash).unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+/// Checks every chunk of chunked entry against its hash.
+///
+/// Returns tuple of paths of missing chunks and paths of corrupted chunks.
+pub fn verify_chunks(entry: &BackupEntry) -> (Vec<String>, Vec<String>) {
+    let mut missing = vec![];
+    let mut corrupted = vec![];
+    for hash in &entry.chunks {
+        let path = chunk_path(entry, hash);
+        if !Path::new(&path).exists() {
+            missing.push(path);
+        } else if !is_chunk_valid(&path, hash, entry) {
+            corrupted.push(path);
+        }
+    }
+    (missing, corrupted)
+}
+
+/// Copies lists of chunks to chunked entries of provided dirs that don't have them yet, from entries with the same input path and hash in known dirs (for example copied files or previous backup).
+///
+/// Chunked entries that still don't have any chunks are removed, because they can't be restored.
+pub fn fill_chunks(dirs: &mut [BackupDir], known: &[BackupDir]) {
+    for dir in dirs.iter_mut() {
+        for entry in dir.backup_entries.iter_mut().filter(|x| x.chunked && x.chunks.is_empty()) {
+            let found = known.iter()
+                .flat_map(|x| x.backup_entries.iter())
+                .find(|x| x.chunked && !x.chunks.is_empty() && x.input_path == entry.input_path && x.hash == entry.hash && x.compression == entry.compression && x.encryption == entry.encryption);
+            if let Some(found) = found {
+                // Chunks are stored under names derived from key of entry that stored them
+                entry.chunks = found.chunks.clone();
+                entry.chunk_key = found.chunk_key.clone();
+            }
+        }
+        dir.backup_entries.retain(|x| !x.chunked || !x.chunks.is_empty());
+        dir.files = dir.backup_entries.iter().filter(|x| x.is_file).count();
+    }
+}
+
+/// Reader returning content of all chunks of chunked entry one after another, decrypted and decompressed.
+///
+/// Chunks are opened only when previous one is read to the end, so only one file is open at a time.
+pub struct ChunkReader {
+    entry: BackupEntry,
+    next: usize,
+    current: Option<Box<dyn Read>>,
+}
+
+impl ChunkReader {
+    /// Creates reader of all chunks of provided chunked entry.
+    pub fn new(entry: &BackupEntry) -> ChunkReader {
+        ChunkReader { entry: entry.clone(), next: 0, current: None }
+    }
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(current) = &mut self.current {
+                let read = current.read(buf)?;
+                if read > 0 || buf.is_empty() {
+                    return Ok(read);
+                }
+                self.current = None;
+            }
+            match self.entry.chunks.get(self.next) {
+                None => return Ok(0),
+                Some(hash) => {
+                    let path = chunk_path(&self.entry, hash);
+                    self.current = Some(open_stored(&path, &self.entry.compression, &self.entry.encryption).map_err(io::Error::other)?);
+                    self.next += 1;
+                }
+            }
+        }
+    }
+}
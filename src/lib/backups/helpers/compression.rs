@@ -0,0 +1,153 @@
+use crate::backups::map::backup_dir::BackupDir;
+use crate::backups::map::backup_entry::BackupEntry;
+use crate::backups::map::compression_codec::CompressionCodec;
+use crate::backups::map::encryption_cipher::EncryptionCipher;
+use crate::backups::helpers::hashing::generate_hash_meow_hash_reader;
+use crate::backups::helpers::encryption::DecryptReader;
+use crate::backups::helpers::chunking::ChunkReader;
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::path::Path;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::helpers::hashing::generate_hash_meow_hash;
+    use std::fs;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_assign_compression() {
+        let mut dir = BackupDir::new();
+        dir.backup_entries.push(BackupEntry { input_path: String::from("/home/user/a.txt"), output_path: String::from("/backup/a.txt"), is_file: true, ..BackupEntry::new() });
+        dir.backup_entries.push(BackupEntry { input_path: String::from("/home/user/b.JPG"), output_path: String::from("/backup/b.JPG"), is_file: true, ..BackupEntry::new() });
+        dir.backup_entries.push(BackupEntry { input_path: String::from("/home/user/c"), output_path: String::from("/backup/c"), is_file: false, ..BackupEntry::new() });
+        let mut dirs = vec![dir];
+        assign_compression(&mut dirs, &CompressionCodec::Zstd);
+
+        let entries = &dirs[0].backup_entries;
+        assert_eq!(entries[0].compression, CompressionCodec::Zstd);
+        assert_eq!(entries[0].output_path, "/backup/a.txt.zst");
+        assert_eq!(entries[1].compression, CompressionCodec::None);
+        assert_eq!(entries[1].output_path, "/backup/b.JPG");
+        assert_eq!(entries[2].output_path, "/backup/c");
+    }
+
+    #[test]
+    fn test_compress_and_read_entry() {
+        for codec in &[CompressionCodec::Zstd, CompressionCodec::Gzip] {
+            let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+            fs::create_dir_all(&root).unwrap();
+            let path = |name: &str| root.join(name).to_str().unwrap().to_string();
+            fs::write(path("a.txt"), "content ".repeat(100)).unwrap();
+
+            let entry = BackupEntry { input_path: path("a.txt"), output_path: path("a.txt.packed"), is_file: true, hash: generate_hash_meow_hash(&path("a.txt")).unwrap(), compression: codec.clone(), ..BackupEntry::new() };
+            compress(&mut File::open(&entry.input_path).unwrap(), File::create(&entry.output_path).unwrap(), codec).unwrap();
+            assert!(fs::metadata(&entry.output_path).unwrap().len() < 800);
+            assert_eq!(hash_entry(&entry).unwrap(), entry.hash);
+
+            let mut content = String::new();
+            open_entry(&entry).unwrap().read_to_string(&mut content).unwrap();
+            assert_eq!(content, "content ".repeat(100));
+            fs::remove_dir_all(root).unwrap();
+        }
+    }
+}
+
+/// Extensions of files that are already compressed - compressing them again only wastes time, so they're always copied without changes.
+const COMPRESSED_EXTENSIONS: [&str; 30] = ["jpg", "jpeg", "png", "gif", "webp", "heic", "avif", "mp3", "aac", "ogg", "opus", "flac", "m4a", "mp4", "mkv", "avi", "mov", "webm",
+    "zip", "gz", "tgz", "bz2", "xz", "zst", "7z", "rar", "jar", "docx", "xlsx", "pptx"];
+
+/// Returns true if file with provided path should be compressed - false if its extension belongs to already compressed format. Letter case of extension doesn't matter.
+pub fn is_compressible(path: &str) -> bool {
+    match Path::new(path).extension().and_then(|x| x.to_str()) {
+        Some(extension) => !COMPRESSED_EXTENSIONS.contains(&extension.to_lowercase().as_str()),
+        None => true,
+    }
+}
+
+/// Sets codec of every compressible file in provided dirs, and adds extension of codec to its output path.
+///
+/// Should be used after creating output map and before copying. Does nothing if codec is ```None```.
+pub fn assign_compression(dirs: &mut [BackupDir], codec: &CompressionCodec) {
+    if *codec == CompressionCodec::None {
+        return;
+    }
+    for entry in dirs.iter_mut().flat_map(|x| x.backup_entries.iter_mut()) {
+        if entry.is_file && is_compressible(&entry.input_path) {
+            entry.compression = codec.clone();
+            entry.output_path = format!("{}{}", &entry.output_path, codec.extension());
+        }
+    }
+}
+
+/// Compresses all data from reader with provided codec, and writes it to writer, which is returned after flushing.
+///
+/// Returns error if data can't be read, compressed or written.
+pub fn compress<W: Write>(reader: &mut dyn Read, writer: W, codec: &CompressionCodec) -> Result<W, String> {
+    let mut writer = match codec {
+        CompressionCodec::None => {
+            let mut writer = writer;
+            std::io::copy(reader, &mut writer).map_err(|e| e.to_string())?;
+            writer
+        }
+        CompressionCodec::Zstd => {
+            let mut encoder = zstd::Encoder::new(writer, 0).map_err(|e| e.to_string())?;
+            std::io::copy(reader, &mut encoder).map_err(|e| e.to_string())?;
+            encoder.finish().map_err(|e| e.to_string())?
+        }
+        CompressionCodec::Gzip => {
+            let mut encoder = GzEncoder::new(writer, Compression::default());
+            std::io::copy(reader, &mut encoder).map_err(|e| e.to_string())?;
+            encoder.finish().map_err(|e| e.to_string())?
+        }
+    };
+    writer.flush().map_err(|e| e.to_string())?;
+    Ok(writer)
+}
+
+/// Opens file of entry stored in backup, and returns reader with its original, decrypted and decompressed content. Chunked files are read chunk after chunk.
+///
+/// Returns error if file can't be opened, or decryption or decompression can't be started.
+pub fn open_entry(entry: &BackupEntry) -> Result<Box<dyn Read>, String> {
+    match entry.chunked {
+        true => Ok(Box::new(ChunkReader::new(entry))),
+        false => open_stored(&entry.output_path, &entry.compression, &entry.encryption),
+    }
+}
+
+/// Opens file stored in backup with provided codec and cipher, and returns reader with its decrypted and decompressed content.
+///
+/// Returns error if file can't be opened, or decryption or decompression can't be started.
+pub fn open_stored(path: &str, compression: &CompressionCodec, encryption: &EncryptionCipher) -> Result<Box<dyn Read>, String> {
+    let file = match File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => return Err(format!("Couldn't open file {}: {}", path, e)),
+    };
+    let file: Box<dyn Read> = match encryption {
+        EncryptionCipher::None => Box::new(file),
+        _ => match DecryptReader::new(file) {
+            Ok(reader) => Box::new(reader),
+            Err(e) => return Err(format!("Couldn't decrypt file {}: {}", path, e)),
+        },
+    };
+    match compression {
+        CompressionCodec::None => Ok(file),
+        CompressionCodec::Zstd => match zstd::Decoder::new(file) {
+            Ok(decoder) => Ok(Box::new(decoder)),
+            Err(e) => Err(format!("Couldn't decompress file {}: {}", path, e)),
+        },
+        CompressionCodec::Gzip => Ok(Box::new(GzDecoder::new(file))),
+    }
+}
+
+/// Generates hash of original content of file stored in backup, which can be compared with hash saved in map.
+///
+/// Returns error if file can't be opened, read or decompressed.
+pub fn hash_entry(entry: &BackupEntry) -> Result<String, String> {
+    let mut reader = open_entry(entry)?;
+    generate_hash_meow_hash_reader(&mut reader, &entry.output_path)
+}
@@ -2,15 +2,27 @@ use std::path::{MAIN_SEPARATOR, Path};
 use crate::backups::map::backup_map::BackupMap;
 use walkdir::WalkDir;
 use crate::{S_MAP, S_SEPARATOR};
-use std::fs::{File, remove_dir, remove_file};
-use std::io::BufReader;
+use std::fs::{remove_dir, remove_file, File};
+use std::io::{BufRead, BufReader};
 use crate::backups::map::backup_dir::BackupDir;
 use crate::backups::map::backup_entry::BackupEntry;
+use crate::backups::helpers::encryption::{is_encrypted, DecryptReader};
+use crate::backups::helpers::map_io::read_map;
+use crate::backups::helpers::migration::{migrate_map, check_version};
+use crate::backups::helpers::map_checksum::{verify_map_checksum, map_key};
+use crate::backups::helpers::parity::remove_parity;
+use crate::backups::map::map_version::MapVersion;
+use serde::de::DeserializeOwned;
 use std::error::Error;
 
 #[cfg(test)]
 mod tests {
-    use crate::backups::helpers::dirs::{get_last_subdir, find_previous_backups};
+    use crate::backups::helpers::dirs::{get_last_subdir, find_previous_backups, find_previous_backups_strict, load_map};
+    use crate::backups::map::backup_map::BackupMap;
+    use crate::backups::map::backup_mode::BackupMode;
+    use crate::backups::traits::backup_serialize::save_map_to_file;
+    use std::fs;
+    use uuid::Uuid;
 
     #[test]
     fn test_get_last_subdir() {
@@ -24,6 +36,64 @@ mod tests {
         let backups = find_previous_backups(&String::from("/home/szymon/Downloads/test")).unwrap();
         assert_eq!(backups.len(), 0);
     }
+
+    #[test]
+    fn test_find_previous_backups_damaged() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        let path = |name: &str| root.join(name).join(crate::S_MAP).to_str().unwrap().to_string();
+        for name in &["a", "b"] {
+            fs::create_dir_all(root.join(name)).unwrap();
+            save_map_to_file(&mut BackupMap::new(BackupMode::Multiple), &path(name)).unwrap();
+        }
+        let dir = root.to_str().unwrap().to_string();
+        assert_eq!(find_previous_backups_strict(&dir).unwrap().len(), 2);
+
+        // Truncated map is skipped, but not when making new backup
+        let content = fs::read(path("b")).unwrap();
+        fs::write(path("b"), &content[..content.len() / 2]).unwrap();
+        assert_eq!(find_previous_backups(&dir).unwrap().len(), 1);
+        assert!(find_previous_backups_strict(&dir).is_err());
+
+        // Edited map is detected by checksum
+        let content = String::from_utf8(content).unwrap().replace("\"max_backups\": 1", "\"max_backups\": 9");
+        fs::write(path("b"), content).unwrap();
+        assert!(find_previous_backups_strict(&dir).err().unwrap().contains("checksum"));
+
+        // Map of current version without checksum was edited
+        let mut value: serde_json::Value = serde_json::from_str(&String::from_utf8(fs::read(path("a")).unwrap()).unwrap()).unwrap();
+        value.as_object_mut().unwrap().remove("checksum");
+        fs::write(path("a"), value.to_string()).unwrap();
+        assert!(load_map(std::path::Path::new(&path("a"))).err().unwrap().contains("checksum"));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_load_map_of_newer_version() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&root).unwrap();
+        let mut map = BackupMap::new(BackupMode::Multiple);
+        map.format_version = crate::backups::map::backup_map::MAP_FORMAT_VERSION + 1;
+        map.checksum = String::from("sha256:calculated-differently");
+        fs::write(root.join(crate::S_MAP), serde_json::to_string(&map).unwrap()).unwrap();
+        assert!(load_map(&root.join(crate::S_MAP)).err().unwrap().contains("update program"));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_load_map_of_older_version() {
+        // Map saved by version of program from before maps had format version and checksum
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&root).unwrap();
+        let old = r#"{"id":"old","timestamp":1600000000,"backup_mode":"Multiple","max_backups":3,"files":1,"folders":1,"output_folder":"/backup/01-01-2020 10_00_00","input_folders":["/home/user/docs"],"ignore_extensions":[],"ignore_folders":[],"backup_dirs":[{"root_input":"/home/user/docs","root_output":"/backup/01-01-2020 10_00_00/docs","files":1,"folders":1,"backup_entries":[{"input_path":"/home/user/docs","output_path":"/backup/01-01-2020 10_00_00/docs","is_file":false,"hash":""},{"input_path":"/home/user/docs/a.txt","output_path":"/backup/01-01-2020 10_00_00/docs/a.txt","is_file":true,"hash":"1234"}]}]}"#;
+        fs::write(root.join(crate::S_MAP), old).unwrap();
+
+        let map = load_map(&root.join(crate::S_MAP)).unwrap();
+        assert_eq!(map.id, "old");
+        assert_eq!(map.format_version, crate::backups::map::backup_map::MAP_FORMAT_VERSION);
+        assert_eq!(map.backup_dirs[0].backup_entries[1].hash, "1234");
+        assert_eq!(find_previous_backups_strict(&root.to_str().unwrap().to_string()).unwrap().len(), 1);
+        fs::remove_dir_all(root).unwrap();
+    }
 }
 
 /// Returns last section from path (for example "/usr/lib/firefox" returns "firefox", and "/usr/lib/a.txt" returns "a.txt".
@@ -70,25 +140,96 @@ pub fn get_last_subdir(path: &String) -> Result<String, String> {
 /// ```
 pub fn find_previous_backups(dir: &String) -> Result<Vec<BackupMap>, String> {
     let mut previous_maps = vec![];
+    for map in load_all_maps(dir)? {
+        match map {
+            Ok(previous_map) => previous_maps.push(previous_map),
+            Err(e) => println!("{}, it will not count", e),
+        }
+    }
+    Ok(previous_maps)
+}
+
+/// Finds all maps inside folder passed in argument, like ```find_previous_backups```, but doesn't skip maps that can't be loaded.
+///
+/// Should be used before making new backup - skipping damaged map could make program copy all files again, or remove files that are still needed by damaged backup.
+///
+/// Returns vector containing found maps, or error if path isn't directory or any map can't be loaded (for example is truncated or its checksum doesn't match).
+pub fn find_previous_backups_strict(dir: &String) -> Result<Vec<BackupMap>, String> {
+    load_all_maps(dir)?.into_iter().collect::<Result<Vec<BackupMap>, String>>()
+        .map_err(|e| format!("{} - fix or remove it before making new backup", e))
+}
+
+/// Loads every file with name defined by S_MAP const inside folder passed in argument.
+///
+/// Returns result of loading every found map, or error if path isn't directory.
+fn load_all_maps(dir: &String) -> Result<Vec<Result<BackupMap, String>>, String> {
     let path = Path::new(dir);
     if !path.is_dir() {
         return Err(String::from("Trying to find maps in path that isn't directory"));
     }
-    for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
-        if entry.path().ends_with(S_MAP) {
-            match File::open(entry.path()) {
-                Err(e) => println!("Error reading possible map, it will not count: {}", e),
-                Ok(previous_map) => {
-                    let buf_reader = BufReader::new(previous_map);
-                    match serde_json::from_reader(buf_reader) {
-                        Ok(previous_map) => previous_maps.push(previous_map),
-                        Err(e) => println!("Found not valid possible map, it will not count: {}", e)
-                    }
-                }
-            }
-        }
+    Ok(WalkDir::new(dir).into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().ends_with(S_MAP))
+        .map(|e| load_map(e.path()))
+        .collect())
+}
+
+/// Loads one map file from provided path and converts it to BackupMap struct, migrated to current format version. Encrypted maps are decrypted with key derived from passphrase.
+///
+/// Version of map is checked first, because checksum of map saved by newer version of program can't be checked. Then checksum is checked before migration - if ```FLASH_BACKUP_MAP_KEY``` is set, map has to be authenticated with it. Maps saved by older versions of program without checksum are loaded with warning, only if key isn't set.
+///
+/// Returns error if file can't be opened, can't be decrypted, isn't valid map (for example is truncated), its checksum is missing or doesn't match, or was saved by newer version of program.
+pub fn load_map(path: &Path) -> Result<BackupMap, String> {
+    match read_map_file::<BackupMap>(path) {
+        Ok(mut map) => check_version(map.format_version)
+            .and_then(|_| verify_map_checksum(&mut map, map_key().as_deref()))
+            .and_then(|_| migrate_map(map))
+            .map_err(|e| format!("Can't load map {}: {}", path.display(), e)),
+        // Map from newer version of program may not be readable at all, so its version is checked separately to show clear error
+        Err(e) => match read_map_file::<MapVersion>(path).map(|x| check_version(x.format_version)) {
+            Ok(Err(newer)) => Err(format!("Can't load map {}: {}", path.display(), newer)),
+            _ => Err(e),
+        },
+    }
+}
+
+/// Reads one map file from provided path (or only part of it, like its version), without migrating it. Encrypted maps are decrypted with key derived from passphrase.
+///
+/// Returns error if file can't be opened, can't be decrypted or isn't valid map.
+pub fn read_map_file<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let mut file = match File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => return Err(format!("Error reading possible map {}: {}", path.display(), e)),
+    };
+    let encrypted = match file.fill_buf() {
+        Ok(start) => is_encrypted(start),
+        Err(e) => return Err(format!("Error reading possible map {}: {}", path.display(), e)),
+    };
+    match encrypted {
+        true => DecryptReader::new(file)
+            .map_err(|e| format!("Can't decrypt map {}: {}", path.display(), e))
+            .and_then(|reader| read_map(BufReader::new(reader)).map_err(|e| format!("Map {} is damaged or isn't valid map: {}", path.display(), e))),
+        false => read_map(file).map_err(|e| format!("Map {} is damaged or isn't valid map: {}", path.display(), e)),
+    }
+}
+
+/// Finds backup with provided id inside folder passed in argument, using the same search as ```find_previous_backups```.
+///
+/// If id is empty, newest backup (with highest timestamp) is returned.
+///
+/// Returns error if folder can't be searched, no backups are found or there is no backup with provided id.
+pub fn find_backup(dir: &String, id: &str) -> Result<BackupMap, String> {
+    let backups = find_previous_backups(dir)?;
+    if backups.is_empty() {
+        return Err(format!("No backups found in {}", dir));
+    }
+    if id.is_empty() {
+        return Ok(backups.into_iter().max_by_key(|x| x.timestamp).unwrap());
+    }
+    match backups.into_iter().find(|x| x.id == id) {
+        Some(map) => Ok(map),
+        None => Err(format!("No backup with id {} found in {}", id, dir))
     }
-    Ok(previous_maps)
 }
 
 /// Deletes folder and all its content, recursively, until nothing is left in folder.
@@ -110,7 +251,10 @@ pub fn delete_folder_with_content(path: &String, map_folder: &BackupDir) -> Resu
     for item in &folder_content {
         if item.is_file {
             match remove_file(&item.output_path) {
-                Ok(_) => entries += 1,
+                Ok(_) => {
+                    remove_parity(&item.output_path);
+                    entries += 1
+                }
                 Err(e) => {
                     println!("Error while removing file {}: {} - skipping...", &item.output_path, e);
                     continue;
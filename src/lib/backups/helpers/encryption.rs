@@ -0,0 +1,459 @@
+use crate::backups::map::backup_dir::BackupDir;
+use crate::backups::map::backup_map::BackupMap;
+use crate::backups::map::encryption_cipher::EncryptionCipher;
+use crate::S_PASSPHRASE;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, CHACHA20_POLY1305};
+use ring::{hmac, pbkdf2};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::io::{self, Read, Write};
+use std::num::NonZeroU32;
+use std::sync::Mutex;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::map::backup_entry::BackupEntry;
+    use crate::backups::map::backup_mode::BackupMode;
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        set_passphrase("test passphrase");
+        for cipher in &[EncryptionCipher::ChaCha20Poly1305, EncryptionCipher::Aes256Gcm] {
+            for len in &[0, 1, CHUNK_SIZE, CHUNK_SIZE + 1, 3 * CHUNK_SIZE + 7] {
+                let data: Vec<u8> = (0..*len).map(|x| (x % 251) as u8).collect();
+                let encrypted = encrypt_bytes(&data, cipher).unwrap();
+                assert!(is_encrypted(&encrypted));
+                assert_eq!(decrypt_bytes(&encrypted).unwrap(), data);
+
+                // Every changed byte and every missing chunk must be detected
+                let mut tampered = encrypted.clone();
+                let last = tampered.len() - 1;
+                tampered[last] ^= 1;
+                assert!(decrypt_bytes(&tampered).is_err());
+                if *len > CHUNK_SIZE {
+                    assert!(decrypt_bytes(&encrypted[..HEADER_SIZE + CHUNK_SIZE + TAG_SIZE]).is_err());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_header_authenticated() {
+        set_passphrase("test passphrase");
+        let encrypted = encrypt_bytes(b"data", &EncryptionCipher::ChaCha20Poly1305).unwrap();
+        // Changed nonce prefix doesn't change key, but header is authenticated
+        let mut tampered = encrypted.clone();
+        tampered[HEADER_SIZE - 1] ^= 1;
+        assert!(decrypt_bytes(&tampered).is_err());
+
+        // Number of iterations is checked before key is derived
+        let mut tampered = encrypted;
+        tampered[7..11].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(DecryptReader::new(&tampered[..]).err().unwrap().contains("iterations"));
+    }
+
+    #[test]
+    fn test_decrypt_legacy() {
+        set_passphrase("test passphrase");
+        let salt = [7; SALT_SIZE];
+        let nonce_prefix = [3; NONCE_PREFIX_SIZE];
+        let mut encrypted = LEGACY_MAGIC.to_vec();
+        encrypted.push(EncryptionCipher::Aes256Gcm.id());
+        encrypted.extend_from_slice(&PBKDF2_ITERATIONS.to_be_bytes());
+        encrypted.extend_from_slice(&salt);
+        encrypted.extend_from_slice(&nonce_prefix);
+        let key = create_key(&EncryptionCipher::Aes256Gcm, &derive_key(&salt, PBKDF2_ITERATIONS).unwrap()).unwrap();
+        let mut data = b"legacy data".to_vec();
+        key.seal_in_place_append_tag(chunk_nonce(&nonce_prefix, 0, true), Aad::empty(), &mut data).unwrap();
+        encrypted.extend_from_slice(&data);
+        assert!(is_encrypted(&encrypted));
+        assert_eq!(decrypt_bytes(&encrypted).unwrap(), b"legacy data");
+    }
+
+    #[test]
+    fn test_obfuscated_name() {
+        let key = generate_name_key().unwrap();
+        assert_eq!(key.len(), 64);
+        let name = obfuscated_name(&key, "/home/user/Clients/salaries.xlsx");
+        assert_eq!(name.len(), 32);
+        assert_eq!(name, obfuscated_name(&key, "/home/user/Clients/salaries.xlsx"));
+        assert_ne!(name, obfuscated_name(&key, "/home/user/Clients/other.xlsx"));
+        assert_ne!(name, obfuscated_name(&generate_name_key().unwrap(), "/home/user/Clients/salaries.xlsx"));
+    }
+
+    #[test]
+    fn test_assign_name_key() {
+        let mut map = BackupMap::new(BackupMode::Dedup);
+        assign_name_key(&mut map, &[]);
+        assert!(map.name_key.is_empty());
+
+        map.encryption = EncryptionCipher::ChaCha20Poly1305;
+        let old = BackupMap { name_key: String::from("old"), timestamp: 1, ..BackupMap::new(BackupMode::Dedup) };
+        let newest = BackupMap { name_key: String::from("newest"), timestamp: 2, ..BackupMap::new(BackupMode::Dedup) };
+        assign_name_key(&mut map, &[newest, old, BackupMap { timestamp: 3, ..BackupMap::new(BackupMode::Dedup) }]);
+        assert_eq!(map.name_key, "newest");
+        map.name_key = String::new();
+        assign_name_key(&mut map, &[]);
+        assert_eq!(map.name_key.len(), 64);
+    }
+
+    #[test]
+    fn test_assign_encryption() {
+        let mut dir = BackupDir::new();
+        dir.backup_entries.push(BackupEntry { output_path: String::from("/backup/a.txt.zst"), is_file: true, ..BackupEntry::new() });
+        dir.backup_entries.push(BackupEntry { output_path: String::from("/backup/folder"), is_file: false, ..BackupEntry::new() });
+        let mut dirs = vec![dir];
+        assign_encryption(&mut dirs, &EncryptionCipher::Aes256Gcm);
+        assert_eq!(dirs[0].backup_entries[0].encryption, EncryptionCipher::Aes256Gcm);
+        assert_eq!(dirs[0].backup_entries[0].output_path, "/backup/a.txt.zst.enc");
+        assert_eq!(dirs[0].backup_entries[1].encryption, EncryptionCipher::None);
+    }
+}
+
+/// First bytes of every encrypted file - whole header is authenticated with every chunk.
+const MAGIC: &[u8; 6] = b"FBENC2";
+/// First bytes of files encrypted by older versions of program, whose header isn't authenticated.
+const LEGACY_MAGIC: &[u8; 6] = b"FBENC1";
+/// Size of header of encrypted file - magic bytes, cipher id, number of iterations, salt and nonce prefix.
+const HEADER_SIZE: usize = 6 + 1 + 4 + SALT_SIZE + NONCE_PREFIX_SIZE;
+const SALT_SIZE: usize = 16;
+const NONCE_PREFIX_SIZE: usize = 7;
+const TAG_SIZE: usize = 16;
+/// Size of one encrypted part of file, without tag.
+const CHUNK_SIZE: usize = 64 * 1024;
+/// Number of PBKDF2 iterations used to derive key from passphrase for new files.
+pub const PBKDF2_ITERATIONS: u32 = 100_000;
+/// Maximal number of PBKDF2 iterations accepted from header of encrypted file, so damaged or crafted file can't make key derivation run for hours.
+const MAX_PBKDF2_ITERATIONS: u32 = 100 * PBKDF2_ITERATIONS;
+/// Extension added to name of every encrypted file.
+pub const ENCRYPTED_EXTENSION: &str = ".enc";
+
+/// Passphrase provided by user, asked only once per run.
+static PASSPHRASE: Mutex<Option<String>> = Mutex::new(None);
+/// Key derived from passphrase, with its salt and number of iterations.
+type DerivedKey = ([u8; SALT_SIZE], u32, [u8; 32]);
+
+/// Keys already derived from passphrase - derivation is slow on purpose, so it's done once per salt.
+static KEYS: Mutex<Vec<DerivedKey>> = Mutex::new(Vec::new());
+/// Salt used for every file encrypted during this run.
+static SALT: Mutex<Option<[u8; SALT_SIZE]>> = Mutex::new(None);
+
+/// Sets passphrase used to encrypt and decrypt backups during this run, so user isn't asked about it.
+pub fn set_passphrase(passphrase: &str) {
+    *PASSPHRASE.lock().unwrap() = Some(String::from(passphrase));
+}
+
+/// Returns passphrase used during this run - taken from ```FLASH_BACKUP_PASSPHRASE``` environment variable, or asked from user if it isn't set. Passphrase written by user isn't shown in terminal.
+///
+/// If confirm is true, user has to write passphrase twice, to avoid creating backup that can't be decrypted because of typo.
+///
+/// Returns error if passphrase is empty or can't be read.
+pub fn passphrase(confirm: bool) -> Result<String, String> {
+    let mut cached = PASSPHRASE.lock().unwrap();
+    if let Some(passphrase) = cached.as_ref() {
+        return Ok(passphrase.clone());
+    }
+    let passphrase = match std::env::var(S_PASSPHRASE) {
+        Ok(passphrase) => passphrase,
+        Err(_) => {
+            let passphrase = read_line("Write passphrase of encrypted backups:")?;
+            if confirm && read_line("Write passphrase again:")? != passphrase {
+                return Err(String::from("Passphrases don't match"));
+            }
+            passphrase
+        }
+    };
+    if passphrase.is_empty() {
+        return Err(String::from("Passphrase can't be empty"));
+    }
+    *cached = Some(passphrase.clone());
+    Ok(passphrase)
+}
+
+/// Prints message and returns one line written by user, without new line character and without showing it in terminal.
+///
+/// If program isn't run in terminal, line is read from standard input, so passphrase can be passed by script.
+fn read_line(message: &str) -> Result<String, String> {
+    if let Ok(line) = rpassword::prompt_password(format!("{}\n", message)) {
+        return Ok(line);
+    }
+    let mut line = String::new();
+    match std::io::stdin().read_line(&mut line) {
+        Ok(_) => Ok(String::from(line.trim_end_matches(&['\r', '\n'][..]))),
+        Err(e) => Err(format!("Can't read passphrase: {}", e)),
+    }
+}
+
+/// Returns key derived from passphrase with provided salt and number of iterations of PBKDF2-HMAC-SHA256.
+fn derive_key(salt: &[u8; SALT_SIZE], iterations: u32) -> Result<[u8; 32], String> {
+    if let Some((_, _, key)) = KEYS.lock().unwrap().iter().find(|(s, i, _)| s == salt && *i == iterations) {
+        return Ok(*key);
+    }
+    if iterations > MAX_PBKDF2_ITERATIONS {
+        return Err(format!("Encrypted file requires {} iterations of key derivation, more than allowed {} - file is damaged", iterations, MAX_PBKDF2_ITERATIONS));
+    }
+    let iterations_non_zero = NonZeroU32::new(iterations).ok_or_else(|| String::from("Invalid number of iterations in encrypted file"))?;
+    let mut key = [0; 32];
+    pbkdf2::derive(pbkdf2::PBKDF2_HMAC_SHA256, iterations_non_zero, salt, passphrase(false)?.as_bytes(), &mut key);
+    KEYS.lock().unwrap().push((*salt, iterations, key));
+    Ok(key)
+}
+
+/// Returns salt used for files encrypted during this run, generating it on first use.
+fn salt() -> Result<[u8; SALT_SIZE], String> {
+    let mut salt = SALT.lock().unwrap();
+    if salt.is_none() {
+        *salt = Some(random_bytes()?);
+    }
+    Ok(salt.unwrap())
+}
+
+/// Returns array filled with secure random bytes.
+fn random_bytes<const N: usize>() -> Result<[u8; N], String> {
+    let mut bytes = [0; N];
+    SystemRandom::new().fill(&mut bytes).map_err(|_| String::from("Can't generate random data"))?;
+    Ok(bytes)
+}
+
+/// Creates key of provided cipher from derived key bytes.
+fn create_key(cipher: &EncryptionCipher, key: &[u8; 32]) -> Result<LessSafeKey, String> {
+    let algorithm = match cipher {
+        EncryptionCipher::ChaCha20Poly1305 => &CHACHA20_POLY1305,
+        EncryptionCipher::Aes256Gcm => &AES_256_GCM,
+        EncryptionCipher::None => return Err(String::from("Cipher not chosen")),
+    };
+    UnboundKey::new(algorithm, key).map(LessSafeKey::new).map_err(|_| String::from("Can't create encryption key"))
+}
+
+/// Returns nonce of one chunk - nonce prefix of file, number of chunk and flag set only for last chunk, so chunks can't be reordered, removed or added.
+fn chunk_nonce(prefix: &[u8; NONCE_PREFIX_SIZE], counter: u32, last: bool) -> Nonce {
+    let mut nonce = [0; 12];
+    nonce[..NONCE_PREFIX_SIZE].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_SIZE..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    Nonce::assume_unique_for_key(nonce)
+}
+
+/// Returns true if provided data starts with header of encrypted file.
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC) || data.starts_with(LEGACY_MAGIC)
+}
+
+/// Sets cipher of every file in provided dirs, and adds ```.enc``` extension to its output path.
+///
+/// Should be used after assigning compression, right before copying. Does nothing if cipher is ```None```.
+pub fn assign_encryption(dirs: &mut [BackupDir], cipher: &EncryptionCipher) {
+    if *cipher == EncryptionCipher::None {
+        return;
+    }
+    for entry in dirs.iter_mut().flat_map(|x| x.backup_entries.iter_mut()).filter(|x| x.is_file) {
+        entry.encryption = cipher.clone();
+        entry.output_path = format!("{}{}", &entry.output_path, ENCRYPTED_EXTENSION);
+    }
+}
+
+/// Generates new random key used to create obfuscated names, as hex string.
+pub fn generate_name_key() -> Result<String, String> {
+    Ok(hex::encode(random_bytes::<32>()?))
+}
+
+/// Sets key from which names of files stored in encrypted backup are derived, if map doesn't have one yet - key of newest of provided previous maps is reused, so objects and chunks shared with previous backups keep their names. New key is generated if no previous map has one.
+///
+/// Does nothing if map isn't encrypted. Function may panic if key can't be generated.
+pub fn assign_name_key(map: &mut BackupMap, previous_maps: &[BackupMap]) {
+    if map.encryption == EncryptionCipher::None || !map.name_key.is_empty() {
+        return;
+    }
+    match previous_maps.iter().filter(|x| !x.name_key.is_empty()).max_by_key(|x| x.timestamp) {
+        Some(previous) => map.name_key = previous.name_key.clone(),
+        None => match generate_name_key() {
+            Ok(key) => map.name_key = key,
+            Err(e) => panic!("Can't generate key to hide names of files: {}. Program will stop", e),
+        },
+    }
+}
+
+/// Returns opaque name of file or folder with provided path - HMAC-SHA256 of path with provided key, as 32 hex characters.
+///
+/// The same path always gets the same name with the same key, but without key name can't be linked to path.
+pub fn obfuscated_name(key: &str, path: &str) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key.as_bytes());
+    let mut name = hex::encode(hmac::sign(&key, path.as_bytes()).as_ref());
+    name.truncate(32);
+    name
+}
+
+/// Writer encrypting all written data with key derived from passphrase, in chunks of 64 KiB, each with its own authentication tag.
+///
+/// ```finish``` must be called after writing all data, otherwise file is incomplete and can't be decrypted.
+pub struct EncryptWriter<W: Write> {
+    inner: W,
+    key: LessSafeKey,
+    header: Vec<u8>,
+    nonce_prefix: [u8; NONCE_PREFIX_SIZE],
+    counter: u32,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> EncryptWriter<W> {
+    /// Creates writer encrypting data with provided cipher, and writes header of encrypted file to inner writer.
+    ///
+    /// Returns error if key can't be derived, or header can't be written.
+    pub fn new(mut inner: W, cipher: &EncryptionCipher) -> Result<EncryptWriter<W>, String> {
+        let salt = salt()?;
+        let key = create_key(cipher, &derive_key(&salt, PBKDF2_ITERATIONS)?)?;
+        let nonce_prefix = random_bytes()?;
+
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        header.extend_from_slice(MAGIC);
+        header.push(cipher.id());
+        header.extend_from_slice(&PBKDF2_ITERATIONS.to_be_bytes());
+        header.extend_from_slice(&salt);
+        header.extend_from_slice(&nonce_prefix);
+        inner.write_all(&header).map_err(|e| format!("Can't write header of encrypted file: {}", e))?;
+        Ok(EncryptWriter { inner, key, header, nonce_prefix, counter: 0, buffer: Vec::with_capacity(CHUNK_SIZE + TAG_SIZE) })
+    }
+
+    /// Encrypts buffered data as one chunk and writes it to inner writer.
+    fn seal_chunk(&mut self, last: bool) -> io::Result<()> {
+        let nonce = chunk_nonce(&self.nonce_prefix, self.counter, last);
+        self.key.seal_in_place_append_tag(nonce, Aad::from(&self.header), &mut self.buffer).map_err(|_| io::Error::other("can't encrypt data"))?;
+        self.inner.write_all(&self.buffer)?;
+        self.buffer.clear();
+        self.counter = self.counter.checked_add(1).ok_or_else(|| io::Error::other("file is too big to encrypt"))?;
+        Ok(())
+    }
+
+    /// Encrypts remaining data as last chunk, flushes inner writer and returns it.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.seal_chunk(true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for EncryptWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut written = 0;
+        while written < buf.len() {
+            // Full chunk is sealed only when more data comes, because last chunk is marked differently
+            if self.buffer.len() == CHUNK_SIZE {
+                self.seal_chunk(false)?;
+            }
+            let len = (CHUNK_SIZE - self.buffer.len()).min(buf.len() - written);
+            self.buffer.extend_from_slice(&buf[written..written + len]);
+            written += len;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reader decrypting data written by ```EncryptWriter```. Returns error of kind ```InvalidData``` if any chunk was modified, removed or added.
+pub struct DecryptReader<R: Read> {
+    inner: R,
+    key: LessSafeKey,
+    /// Header authenticated with every chunk, empty for files encrypted by older versions of program.
+    aad: Vec<u8>,
+    nonce_prefix: [u8; NONCE_PREFIX_SIZE],
+    counter: u32,
+    chunk: Vec<u8>,
+    position: usize,
+    next: Vec<u8>,
+    finished: bool,
+}
+
+impl<R: Read> DecryptReader<R> {
+    /// Creates reader decrypting data from inner reader, reading header of encrypted file first.
+    ///
+    /// Returns error if data isn't encrypted file, uses unknown cipher, or key can't be derived.
+    pub fn new(mut inner: R) -> Result<DecryptReader<R>, String> {
+        let mut header = [0; HEADER_SIZE];
+        inner.read_exact(&mut header).map_err(|e| format!("Can't read header of encrypted file: {}", e))?;
+        if !is_encrypted(&header) {
+            return Err(String::from("File isn't encrypted by Flash Backup"));
+        }
+        let cipher = EncryptionCipher::from_id(header[6]).ok_or_else(|| String::from("Encrypted file uses unknown cipher"))?;
+        let mut iterations = [0; 4];
+        iterations.copy_from_slice(&header[7..11]);
+        let mut salt = [0; SALT_SIZE];
+        salt.copy_from_slice(&header[11..11 + SALT_SIZE]);
+        let mut nonce_prefix = [0; NONCE_PREFIX_SIZE];
+        nonce_prefix.copy_from_slice(&header[11 + SALT_SIZE..]);
+
+        let key = create_key(&cipher, &derive_key(&salt, u32::from_be_bytes(iterations))?)?;
+        let aad = match header.starts_with(MAGIC) {
+            true => header.to_vec(),
+            false => vec![],
+        };
+        Ok(DecryptReader { inner, key, aad, nonce_prefix, counter: 0, chunk: vec![], position: 0, next: vec![], finished: false })
+    }
+
+    /// Reads and decrypts next chunk. Chunk is last if no data is left after it.
+    fn open_chunk(&mut self) -> io::Result<()> {
+        let mut data = std::mem::take(&mut self.next);
+        let wanted = CHUNK_SIZE + TAG_SIZE + 1;
+        let mut buffer = [0; 8192];
+        while data.len() < wanted {
+            let read = self.inner.read(&mut buffer[..(wanted - data.len()).min(8192)])?;
+            if read == 0 {
+                break;
+            }
+            data.extend_from_slice(&buffer[..read]);
+        }
+        let last = data.len() < wanted;
+        if !last {
+            self.next = data.split_off(CHUNK_SIZE + TAG_SIZE);
+        }
+
+        let nonce = chunk_nonce(&self.nonce_prefix, self.counter, last);
+        let len = self.key.open_in_place(nonce, Aad::from(&self.aad), &mut data)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "encrypted data is damaged or was modified"))?
+            .len();
+        data.truncate(len);
+        self.chunk = data;
+        self.position = 0;
+        self.counter = self.counter.wrapping_add(1);
+        self.finished = last;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.position < self.chunk.len() {
+                let len = (self.chunk.len() - self.position).min(buf.len());
+                buf[..len].copy_from_slice(&self.chunk[self.position..self.position + len]);
+                self.position += len;
+                return Ok(len);
+            }
+            if self.finished {
+                return Ok(0);
+            }
+            self.open_chunk()?;
+        }
+    }
+}
+
+/// Encrypts provided data with provided cipher, returning whole encrypted file.
+pub fn encrypt_bytes(data: &[u8], cipher: &EncryptionCipher) -> Result<Vec<u8>, String> {
+    let mut writer = EncryptWriter::new(vec![], cipher)?;
+    writer.write_all(data).map_err(|e| format!("Can't encrypt data: {}", e))?;
+    writer.finish().map_err(|e| format!("Can't encrypt data: {}", e))
+}
+
+/// Decrypts whole encrypted file.
+///
+/// Returns error if data isn't encrypted, passphrase is wrong or data was modified.
+pub fn decrypt_bytes(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut reader = DecryptReader::new(data)?;
+    let mut decrypted = vec![];
+    match reader.read_to_end(&mut decrypted) {
+        Ok(_) => Ok(decrypted),
+        Err(_) => Err(String::from("wrong passphrase, or data is damaged or was modified")),
+    }
+}
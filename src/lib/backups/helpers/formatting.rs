@@ -0,0 +1,38 @@
+use chrono::{Local, TimeZone};
+
+#[cfg(test)]
+mod tests {
+    use crate::backups::helpers::formatting::format_size;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024 * 1024), "5.0 GB");
+    }
+}
+
+/// Converts timestamp saved in map to human-readable local date, in format "dd-mm-YYYY HH:MM:SS".
+///
+/// Returns "unknown date" if timestamp can't be converted.
+pub fn format_timestamp(timestamp: usize) -> String {
+    match Local.timestamp_opt(timestamp as i64, 0).single() {
+        Some(date) => date.format("%d-%m-%Y %H:%M:%S").to_string(),
+        None => String::from("unknown date"),
+    }
+}
+
+/// Converts size in bytes to human-readable string, for example "1.5 MB".
+pub fn format_size(bytes: u64) -> String {
+    let units = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} {}", bytes, units[0]),
+        _ => format!("{:.1} {}", size, units[unit]),
+    }
+}
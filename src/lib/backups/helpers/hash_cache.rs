@@ -0,0 +1,151 @@
+use crate::backups::map::backup_dir::BackupDir;
+use crate::backups::traits::backup_input::{KnownHash, modified_time};
+use crate::{S_CACHE_DIR, S_SEPARATOR};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{BufReader, BufWriter, Write};
+use fs2::FileExt;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::map::backup_entry::BackupEntry;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_update_and_load_hash_cache() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&root).unwrap();
+        let path = |name: &str| root.join(name).to_str().unwrap().to_string();
+        fs::write(path("a.txt"), "content").unwrap();
+        let metadata = fs::metadata(path("a.txt")).unwrap();
+        let entry = BackupEntry { input_path: path("a.txt"), is_file: true, hash: String::from("hash"), original_size: 7, modified: modified_time(&metadata), ..BackupEntry::new() };
+        let changed = BackupEntry { input_path: path("b.txt"), is_file: true, hash: String::from("old"), original_size: 1, modified: 1, ..BackupEntry::new() };
+        let dir = BackupDir { backup_entries: vec![entry, changed], ..BackupDir::new() };
+
+        let cache = path("cache");
+        assert!(load_hash_cache(&cache).is_empty());
+        assert_eq!(update_hash_cache(&cache, &[dir]).unwrap(), 1);
+        let known = load_hash_cache(&cache);
+        assert_eq!(known.len(), 1);
+        assert_eq!(known[&path("a.txt")].hash, "hash");
+        assert_eq!(known[&path("a.txt")].inode, inode(&metadata));
+        fs::remove_dir_all(root).unwrap();
+    }
+}
+
+/// Name of file with cached hashes, inside cache folder.
+const CACHE_FILE: &str = "hashes.cbor";
+/// Name of lock file, which is locked by every run reading or writing cache, inside cache folder.
+const LOCK_FILE: &str = "hashes.lock";
+
+/// Hash of source file saved in cache, with size, time of modification and inode of file when it was hashed.
+#[derive(Serialize, Deserialize)]
+struct CachedHash {
+    size: u64,
+    modified: u64,
+    inode: u64,
+    hash: String,
+}
+
+/// Returns folder with hash cache - from ```FLASH_BACKUP_CACHE_DIR``` environment variable if it's set, or ```flash_backup``` folder in user's cache folder.
+///
+/// Returns None if user's cache folder can't be found.
+pub fn cache_folder() -> Option<String> {
+    if let Ok(folder) = std::env::var(S_CACHE_DIR) {
+        return Some(folder);
+    }
+    dirs::cache_dir().and_then(|x| x.join("flash_backup").to_str().map(String::from))
+}
+
+/// Returns inode of file, which changes when file is replaced by other file (for example restored or moved), even with the same size and time of modification. Always 0 on systems without inodes.
+#[cfg(unix)]
+pub fn inode(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+/// Returns inode of file, which changes when file is replaced by other file (for example restored or moved), even with the same size and time of modification. Always 0 on systems without inodes.
+#[cfg(not(unix))]
+pub fn inode(_metadata: &Metadata) -> u64 {
+    0
+}
+
+/// Opens lock file of cache in provided folder, creating folder if it doesn't exist.
+fn open_lock(folder: &str) -> Result<File, String> {
+    fs::create_dir_all(folder).map_err(|e| format!("Can't create cache folder {}: {}", folder, e))?;
+    let path = format!("{}{}{}", folder, S_SEPARATOR, LOCK_FILE);
+    OpenOptions::new().create(true).truncate(false).write(true).open(&path).map_err(|e| format!("Can't open lock file of cache {}: {}", path, e))
+}
+
+/// Reads cache file, returns empty cache if it doesn't exist or is damaged. Cache should be locked before.
+fn read_cache(folder: &str) -> HashMap<String, CachedHash> {
+    match File::open(format!("{}{}{}", folder, S_SEPARATOR, CACHE_FILE)) {
+        Ok(file) => ciborium::de::from_reader(BufReader::new(file)).unwrap_or_default(),
+        Err(_) => HashMap::new(),
+    }
+}
+
+/// Loads hashes of source files from cache in provided folder, as map of input path and known hash. Cache is locked while it's read, so it's never read while other run saves it.
+///
+/// Returns empty map if cache doesn't exist or can't be read - it only makes files hashed again.
+pub fn load_hash_cache(folder: &str) -> HashMap<String, KnownHash> {
+    let lock = match open_lock(folder) {
+        Ok(lock) => lock,
+        Err(e) => {
+            println!("{}, every file will be hashed", e);
+            return HashMap::new();
+        }
+    };
+    if let Err(e) = lock.lock_shared() {
+        println!("Can't lock cache {}: {}, every file will be hashed", folder, e);
+        return HashMap::new();
+    }
+    let cache = read_cache(folder);
+    let _ = lock.unlock();
+    cache.into_iter().map(|(path, x)| (path, KnownHash { size: x.size, modified: x.modified, inode: x.inode, hash: x.hash })).collect()
+}
+
+/// Saves hashes of all files from provided dirs to cache in provided folder, replacing cached hashes of the same files. Files changed since they were hashed are skipped.
+///
+/// Cache is locked for whole update and read again before it's changed, so concurrent runs don't lose each other's hashes. New cache is written to temporary file, which replaces cache only after it's completely saved.
+///
+/// Returns number of saved hashes, or error if cache can't be locked or saved.
+pub fn update_hash_cache(folder: &str, dirs: &[BackupDir]) -> Result<usize, String> {
+    let lock = open_lock(folder)?;
+    lock.lock_exclusive().map_err(|e| format!("Can't lock cache {}: {}", folder, e))?;
+    let mut cache = read_cache(folder);
+    let mut saved: usize = 0;
+    for entry in dirs.iter().flat_map(|x| x.backup_entries.iter()).filter(|x| x.is_file && x.modified != 0 && !x.hash.is_empty()) {
+        // Inode isn't saved in map, so it's read now - file must still be the same as when it was hashed
+        match fs::metadata(&entry.input_path) {
+            Ok(metadata) if metadata.len() == entry.original_size && modified_time(&metadata) == entry.modified => {
+                cache.insert(entry.input_path.clone(), CachedHash { size: entry.original_size, modified: entry.modified, inode: inode(&metadata), hash: entry.hash.clone() });
+                saved += 1;
+            }
+            _ => {
+                cache.remove(&entry.input_path);
+            }
+        }
+    }
+
+    let path = format!("{}{}{}", folder, S_SEPARATOR, CACHE_FILE);
+    let partial_path = format!("{}.{}.part", &path, std::process::id());
+    let result = File::create(&partial_path)
+        .map_err(|e| e.to_string())
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            ciborium::ser::into_writer(&cache, &mut writer).map_err(|e| e.to_string())?;
+            writer.flush().map_err(|e| e.to_string())
+        })
+        .and_then(|_| fs::rename(&partial_path, &path).map_err(|e| e.to_string()));
+    let _ = lock.unlock();
+    match result {
+        Ok(_) => Ok(saved),
+        Err(e) => {
+            let _ = fs::remove_file(&partial_path);
+            Err(format!("Can't save cache {}: {}", path, e))
+        }
+    }
+}
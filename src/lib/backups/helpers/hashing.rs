@@ -23,28 +23,7 @@ pub fn generate_hash_meow_hash(path: &str) -> Result<String, String> {
     }
 
     match File::open(path) {
-        Ok(file) => {
-            let mut reader = BufReader::new(file);
-            let mut meow = MeowHasher::new();
-            let mut buffer = [0; 1024];
-
-            loop {
-                match reader.read(&mut buffer) {
-                    Ok(u) => {
-                        if u == 0 {
-                            break;
-                        }
-                        meow.input(&buffer[..u]);
-                    }
-                    Err(e) => {
-                        let message = format!("Couldn't hash file {}: {}", path, e);
-                        return Err(message);
-                    }
-                }
-            }
-            let result = meow.result();
-            Ok(hex::encode(result.as_ref()))
-        }
+        Ok(file) => generate_hash_meow_hash_reader(&mut BufReader::new(file), path),
         Err(e) => {
             let message = format!("Couldn't open file {} to generate its hash: {}", path, e);
             Err(message)
@@ -52,6 +31,31 @@ pub fn generate_hash_meow_hash(path: &str) -> Result<String, String> {
     }
 }
 
+/// Generates meow hash of all data read from provided reader, and returns it as a string - the same as ```generate_hash_meow_hash``` returns for file with this data.
+///
+/// Name is used only in error message. Returns error if data can't be read.
+pub fn generate_hash_meow_hash_reader(reader: &mut dyn Read, name: &str) -> Result<String, String> {
+    let mut meow = MeowHasher::new();
+    let mut buffer = [0; 1024];
+
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(u) => {
+                if u == 0 {
+                    break;
+                }
+                meow.input(&buffer[..u]);
+            }
+            Err(e) => {
+                let message = format!("Couldn't hash file {}: {}", name, e);
+                return Err(message);
+            }
+        }
+    }
+    let result = meow.result();
+    Ok(hex::encode(result.as_ref()))
+}
+
 /// Function generate hash of a file and return it as a string. Uses SHA-256, standard and widely used non-cryptographic hasing method.
 ///
 /// Returns error if file to which the path leads doesn't exist or is empty, exists but can't be opened, or if an error occurs during hashing.
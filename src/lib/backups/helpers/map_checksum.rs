@@ -0,0 +1,136 @@
+use crate::backups::map::backup_map::BackupMap;
+use crate::S_MAP_KEY;
+use ring::{digest, hmac};
+use std::io::{self, Write};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::map::backup_mode::BackupMode;
+    use crate::backups::map::backup_dir::BackupDir;
+
+    #[test]
+    fn test_map_checksum() {
+        for key in &[None, Some("secret")] {
+            let mut map = BackupMap::new(BackupMode::Cloud);
+            map.backup_dirs = vec![BackupDir::new()];
+            map.checksum = map_checksum(&map, *key).unwrap();
+            assert!(verify_map_checksum(&mut map, *key).is_ok());
+            assert!(!map.checksum.is_empty());
+
+            map.max_backups = 5;
+            assert!(verify_map_checksum(&mut map, *key).is_err());
+        }
+
+        // Map authenticated with key can't be loaded without key, or with different key
+        let mut map = BackupMap::new(BackupMode::Cloud);
+        map.checksum = map_checksum(&map, Some("secret")).unwrap();
+        assert!(verify_map_checksum(&mut map, None).is_err());
+        assert!(verify_map_checksum(&mut map, Some("other")).is_err());
+
+        // Maps saved before checksums existed don't have them, they're accepted only without key
+        let mut map = BackupMap::new(BackupMode::Cloud);
+        assert!(verify_map_checksum(&mut map, None).is_err());
+        map.format_version = FIRST_VERSION_WITH_CHECKSUM - 1;
+        assert!(verify_map_checksum(&mut map, None).is_ok());
+        assert!(verify_map_checksum(&mut map, Some("secret")).is_err());
+    }
+
+    #[test]
+    fn test_default_fields_not_serialized() {
+        // Fields with default values are skipped, so checksum depends only on fields that are used
+        let map = BackupMap::new(BackupMode::Multiple);
+        let fields: Vec<String> = serde_json::to_value(&map).unwrap().as_object().unwrap().keys().cloned().collect();
+        let expected = ["backup_dirs", "backup_mode", "checksum", "files", "folders", "format_version", "id", "ignore_extensions", "ignore_folders", "input_folders", "max_backups", "output_folder", "timestamp"];
+        assert_eq!(fields, expected);
+    }
+}
+
+/// First format version of map in which every map has checksum - older maps are accepted without it.
+pub const FIRST_VERSION_WITH_CHECKSUM: u32 = 2;
+/// Prefix of checksum created without key.
+const SHA256_PREFIX: &str = "sha256:";
+/// Prefix of checksum created with key.
+const HMAC_PREFIX: &str = "hmac-sha256:";
+
+/// Writer calculating SHA-256 or HMAC-SHA256 of all written data, without storing it.
+enum ChecksumWriter {
+    Sha256(digest::Context),
+    HmacSha256(hmac::Context),
+}
+
+impl Write for ChecksumWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            ChecksumWriter::Sha256(context) => context.update(buf),
+            ChecksumWriter::HmacSha256(context) => context.update(buf),
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Returns key used to authenticate maps, taken from ```FLASH_BACKUP_MAP_KEY``` environment variable, or None if it isn't set.
+pub fn map_key() -> Option<String> {
+    std::env::var(S_MAP_KEY).ok().filter(|x| !x.is_empty())
+}
+
+/// Returns checksum of map - SHA-256, or HMAC-SHA256 if key is provided, of map serialized to CBOR, with name of algorithm as prefix.
+///
+/// Checksum doesn't depend on format in which map is saved. Checksum field of provided map must be empty.
+///
+/// Returns error if map can't be serialized.
+pub fn map_checksum(map: &BackupMap, key: Option<&str>) -> Result<String, String> {
+    let mut writer = match key {
+        None => ChecksumWriter::Sha256(digest::Context::new(&digest::SHA256)),
+        Some(key) => ChecksumWriter::HmacSha256(hmac::Context::with_key(&hmac::Key::new(hmac::HMAC_SHA256, key.as_bytes()))),
+    };
+    ciborium::into_writer(map, &mut writer).map_err(|e| format!("Can't calculate checksum of map: {}", e))?;
+    Ok(match writer {
+        ChecksumWriter::Sha256(context) => format!("{}{}", SHA256_PREFIX, hex::encode(context.finish().as_ref())),
+        ChecksumWriter::HmacSha256(context) => format!("{}{}", HMAC_PREFIX, hex::encode(context.sign().as_ref())),
+    })
+}
+
+/// Saves checksum of map in map, using key from ```FLASH_BACKUP_MAP_KEY``` environment variable, if it's set. Should be used straight before saving map.
+///
+/// Returns error if map can't be serialized.
+pub fn seal_map(map: &mut BackupMap) -> Result<(), String> {
+    map.checksum.clear();
+    map.checksum = map_checksum(map, map_key().as_deref())?;
+    Ok(())
+}
+
+/// Checks if checksum saved in map matches its content. If key is provided, map has to be authenticated with the same key.
+///
+/// Map without checksum is accepted with warning only if key isn't set and map reports version saved before checksums existed, so backups made by older versions of program still work - ```migrate``` adds checksum to them.
+/// Version reported by map itself can't be trusted, so when key is set, map without valid HMAC is never accepted.
+///
+/// Returns error if checksum is missing or doesn't match, which means that map was edited or damaged.
+pub fn verify_map_checksum(map: &mut BackupMap, key: Option<&str>) -> Result<(), String> {
+    if map.checksum.is_empty() {
+        return match (key, map.format_version < FIRST_VERSION_WITH_CHECKSUM) {
+            (Some(_), _) => Err(String::from("map isn't authenticated with key, but key is set - it could be replaced by someone who doesn't know key")),
+            (None, true) => {
+                println!("WARNING: map of backup {} was saved by older version of program and doesn't have checksum, so its damage can't be detected - run migrate to add it", &map.id);
+                Ok(())
+            }
+            (None, false) => Err(String::from("map doesn't have checksum, but it was saved by version of program with checksums - map was edited or damaged")),
+        };
+    }
+    match (map.checksum.starts_with(HMAC_PREFIX), key.is_some()) {
+        (true, false) => return Err(format!("map is authenticated with key, set {} environment variable to load it", S_MAP_KEY)),
+        (false, true) => return Err(String::from("map isn't authenticated with key, but key is set - it could be replaced by someone who doesn't know key")),
+        _ => {}
+    }
+    let expected = std::mem::take(&mut map.checksum);
+    let actual = map_checksum(map, key);
+    map.checksum = expected;
+    match actual? == map.checksum {
+        true => Ok(()),
+        false => Err(String::from("checksum doesn't match content of map - map was edited or damaged, or key is wrong")),
+    }
+}
@@ -0,0 +1,83 @@
+use crate::backups::map::backup_map::BackupMap;
+use crate::backups::map::map_format::MapFormat;
+use serde::de::DeserializeOwned;
+use std::io::{BufRead, BufReader, Write};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::map::backup_mode::BackupMode;
+    use crate::backups::map::backup_dir::BackupDir;
+    use crate::backups::map::backup_entry::BackupEntry;
+
+    #[test]
+    fn test_write_and_read_map() {
+        let mut dir = BackupDir::new();
+        dir.root_input = String::from("/home/user");
+        dir.backup_entries.push(BackupEntry { input_path: String::from("/home/user/a.txt"), output_path: String::from("/backup/a.txt"), is_file: true, hash: String::from("12345"), ..BackupEntry::new() });
+        let mut map = BackupMap::new(BackupMode::Multiple);
+        map.id = String::from("id");
+        map.backup_dirs = vec![dir];
+
+        for format in &[MapFormat::Json, MapFormat::Cbor, MapFormat::CborZst] {
+            map.map_format = format.clone();
+            let content = write_map(&map, vec![]).unwrap();
+            assert_eq!(content.first() == Some(&b'{'), *format == MapFormat::Json);
+            let loaded: BackupMap = read_map(content.as_slice()).unwrap();
+            assert_eq!(loaded.id, "id");
+            assert_eq!(loaded.map_format, *format);
+            assert_eq!(loaded.backup_dirs[0].backup_entries[0].hash, "12345");
+        }
+
+        // Maps saved before binary format existed don't have all fields
+        let old = br#"{"id":"old","timestamp":1,"backup_mode":"Multiple","max_backups":1,"files":0,"folders":0,"output_folder":"","input_folders":[],"ignore_extensions":[],"ignore_folders":[],"backup_dirs":[]}"#;
+        let loaded: BackupMap = read_map(&old[..]).unwrap();
+        assert_eq!(loaded.id, "old");
+        assert_eq!(loaded.map_format, MapFormat::Json);
+    }
+}
+
+/// First bytes of every zstd frame.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Serializes map in its format directly to writer, without creating whole serialized map in memory, and returns writer.
+///
+/// Returns error if map can't be serialized or written.
+pub fn write_map<W: Write>(map: &BackupMap, mut writer: W) -> Result<W, String> {
+    match map.map_format {
+        MapFormat::Json => serde_json::to_writer_pretty(&mut writer, map).map_err(|e| format!("Can't convert map to JSON: {}", e))?,
+        MapFormat::Cbor => ciborium::into_writer(map, &mut writer).map_err(|e| format!("Can't convert map to CBOR: {}", e))?,
+        MapFormat::CborZst => {
+            let mut encoder = zstd::Encoder::new(writer, 0).map_err(|e| format!("Can't compress map: {}", e))?;
+            ciborium::into_writer(map, &mut encoder).map_err(|e| format!("Can't convert map to CBOR: {}", e))?;
+            writer = encoder.finish().map_err(|e| format!("Can't compress map: {}", e))?;
+        }
+    }
+    writer.flush().map_err(|e| format!("Can't write map: {}", e))?;
+    Ok(writer)
+}
+
+/// Deserializes map (or part of it, like its version) from reader, without reading whole file to memory first.
+///
+/// Format is recognized by first bytes of content - zstd-compressed maps start with zstd magic number, JSON maps start with ```{```, every other map is treated as CBOR.
+///
+/// Returns error if content isn't valid map in any format.
+pub fn read_map<T: DeserializeOwned, R: BufRead>(mut reader: R) -> Result<T, String> {
+    let start = reader.fill_buf().map_err(|e| format!("Can't read map: {}", e))?;
+    match start.starts_with(&ZSTD_MAGIC) {
+        true => {
+            let decoder = zstd::Decoder::with_buffer(reader).map_err(|e| format!("Can't decompress map: {}", e))?;
+            read_uncompressed_map(BufReader::new(decoder))
+        }
+        false => read_uncompressed_map(reader),
+    }
+}
+
+/// Deserializes map from reader with uncompressed content - JSON if it starts with ```{```, CBOR otherwise.
+fn read_uncompressed_map<T: DeserializeOwned, R: BufRead>(mut reader: R) -> Result<T, String> {
+    let start = reader.fill_buf().map_err(|e| format!("Can't read map: {}", e))?;
+    match start.first() {
+        Some(b'{') => serde_json::from_reader(reader).map_err(|e| e.to_string()),
+        _ => ciborium::from_reader(reader).map_err(|e| e.to_string()),
+    }
+}
@@ -0,0 +1,91 @@
+use crate::backups::map::backup_map::{BackupMap, MAP_FORMAT_VERSION};
+use crate::backups::map::archive_format::ArchiveFormat;
+use crate::backups::map::compression_codec::CompressionCodec;
+use crate::backups::map::encryption_cipher::EncryptionCipher;
+use std::fs::metadata;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::map::backup_mode::BackupMode;
+    use crate::backups::map::backup_dir::BackupDir;
+    use crate::backups::map::backup_entry::BackupEntry;
+    use std::fs;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_migrate_map() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.txt"), "12345").unwrap();
+
+        let mut dir = BackupDir::new();
+        dir.backup_entries.push(BackupEntry { input_path: String::from("/home/user/a.txt"), output_path: root.join("a.txt").to_str().unwrap().to_string(), is_file: true, ..BackupEntry::new() });
+        dir.backup_entries.push(BackupEntry { input_path: String::from("/home/user/b"), output_path: String::from("/backup/b"), is_file: false, ..BackupEntry::new() });
+        let mut map = BackupMap::new(BackupMode::Multiple);
+        map.format_version = 0;
+        map.backup_dirs = vec![dir];
+
+        let map = migrate_map(map).unwrap();
+        assert_eq!(map.format_version, MAP_FORMAT_VERSION);
+        assert_eq!((map.files, map.folders), (1, 1));
+        assert_eq!((map.backup_dirs[0].files, map.backup_dirs[0].folders), (1, 1));
+        assert_eq!(map.backup_dirs[0].backup_entries[0].original_size, 5);
+        assert_eq!(map.backup_dirs[0].backup_entries[0].stored_size, 5);
+
+        let mut newer = BackupMap::new(BackupMode::Multiple);
+        newer.format_version = MAP_FORMAT_VERSION + 1;
+        assert!(migrate_map(newer).is_err());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
+
+/// Returns error if map with provided format version was saved by newer version of program, and can't be used by this one.
+pub fn check_version(format_version: u32) -> Result<(), String> {
+    match format_version > MAP_FORMAT_VERSION {
+        true => Err(format!("map has format version {}, but this version of program supports maps up to version {} - update program to use this backup", format_version, MAP_FORMAT_VERSION)),
+        false => Ok(()),
+    }
+}
+
+/// Upgrades map to current format version, one version after another. Maps already in current version are returned without changes.
+///
+/// Returns error if map was saved by newer version of program, or there's no migration from its version.
+pub fn migrate_map(mut map: BackupMap) -> Result<BackupMap, String> {
+    check_version(map.format_version)?;
+    while map.format_version < MAP_FORMAT_VERSION {
+        map = match map.format_version {
+            0 => migrate_from_unversioned(map),
+            // Version 2 only adds checksum, which is created when map is saved again
+            1 => map,
+            version => return Err(format!("there's no migration of maps from format version {}", version)),
+        };
+        map.format_version += 1;
+    }
+    Ok(map)
+}
+
+/// Upgrades map saved before maps had versions to version 1.
+///
+/// Numbers of files and folders are counted again, because some older maps don't have them. Files without sizes get them from files stored in backup - only for files stored without compression, encryption and chunking, because only for them stored size is equal to original size.
+fn migrate_from_unversioned(mut map: BackupMap) -> BackupMap {
+    for dir in &mut map.backup_dirs {
+        dir.files = dir.backup_entries.iter().filter(|x| x.is_file).count();
+        dir.folders = dir.backup_entries.iter().filter(|x| !x.is_file).count();
+        if map.archive != ArchiveFormat::Folder {
+            continue;
+        }
+        for entry in dir.backup_entries.iter_mut().filter(|x| x.is_file && x.original_size == 0 && x.stored_size == 0) {
+            if entry.compression != CompressionCodec::None || entry.encryption != EncryptionCipher::None || entry.chunked {
+                continue;
+            }
+            if let Ok(metadata) = metadata(&entry.output_path) {
+                entry.original_size = metadata.len();
+                entry.stored_size = metadata.len();
+            }
+        }
+    }
+    map.files = map.backup_dirs.iter().map(|x| x.files).sum();
+    map.folders = map.backup_dirs.iter().map(|x| x.folders).sum();
+    map
+}
@@ -1,3 +1,15 @@
 pub mod hashing;
 pub mod multithreading;
-pub mod dirs;
\ No newline at end of file
+pub mod dirs;
+pub mod formatting;
+pub mod archive;
+pub mod compression;
+pub mod encryption;
+pub mod chunking;
+pub mod map_io;
+pub mod migration;
+pub mod map_checksum;
+pub mod parity;
+pub mod volumes;
+pub mod pool;
+pub mod hash_cache;
\ No newline at end of file
@@ -0,0 +1,338 @@
+use crate::backups::map::backup_dir::BackupDir;
+use crate::backups::map::backup_entry::BackupEntry;
+use crate::backups::helpers::chunking::{stored_paths, verify_chunks};
+use crate::backups::helpers::compression::hash_entry;
+use reed_solomon_erasure::galois_8::ReedSolomon;
+use ring::digest;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::helpers::hashing::generate_hash_meow_hash;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_fill_parity_skips_corrupted() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join("file.txt").to_str().unwrap().to_string();
+        fs::write(&path, "content of file ".repeat(100)).unwrap();
+        let entry = BackupEntry { output_path: path.clone(), is_file: true, hash: String::from("12345"), parity: 10, ..BackupEntry::new() };
+        let dir = BackupDir { backup_entries: vec![entry.clone()], ..BackupDir::new() };
+        fill_parity(&[dir]);
+        assert!(!Path::new(&parity_path(&path)).exists());
+
+        let entry = BackupEntry { hash: generate_hash_meow_hash(&path).unwrap(), ..entry };
+        let dir = BackupDir { backup_entries: vec![entry], ..BackupDir::new() };
+        fill_parity(&[dir]);
+        assert!(Path::new(&parity_path(&path)).exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_create_and_repair_parity() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join("file.bin").to_str().unwrap().to_string();
+        let content: Vec<u8> = (0..300_000u32).map(|x| (x.wrapping_mul(2654435761) >> 13) as u8).collect();
+        fs::write(&path, &content).unwrap();
+        create_parity(&path, 10).unwrap();
+        assert!(fs::metadata(parity_path(&path)).unwrap().len() < 40_000);
+        assert_eq!(repair_from_parity(&path).unwrap(), 0);
+
+        // Few damaged places and truncated end of file are repaired
+        let mut damaged = content.clone();
+        damaged[10] ^= 1;
+        damaged[150_000] ^= 0xff;
+        damaged.truncate(299_000);
+        fs::write(&path, &damaged).unwrap();
+        assert_eq!(repair_from_parity(&path).unwrap(), 3);
+        assert_eq!(fs::read(&path).unwrap(), content);
+
+        // Too many damaged blocks can't be repaired
+        fs::write(&path, vec![0u8; content.len()]).unwrap();
+        assert!(repair_from_parity(&path).is_err());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
+
+/// Extension added to path of stored file to get path of its parity file.
+pub const PARITY_EXTENSION: &str = ".par";
+/// Bytes at the beginning of every parity file.
+const PARITY_MAGIC: &[u8; 6] = b"FBPAR1";
+/// Maximal number of data blocks in one stripe - every stripe has its own parity blocks, so damage spread across file is still repairable.
+const STRIPE_DATA_BLOCKS: u64 = 64;
+/// Minimal size of block, in bytes.
+const MIN_BLOCK_SIZE: u64 = 64;
+/// Maximal size of block, in bytes.
+const MAX_BLOCK_SIZE: u64 = 64 * 1024;
+/// Length of hash of every block saved in parity file, in bytes.
+const BLOCK_HASH_LEN: usize = 16;
+/// Length of header of parity file, in bytes - magic, size of file, size of block, redundancy and hash of these values.
+const HEADER_LEN: usize = 19 + BLOCK_HASH_LEN;
+
+/// Layout of parity file of one stored file.
+///
+/// File is divided into blocks, and blocks are grouped into stripes of up to ```STRIPE_DATA_BLOCKS``` blocks. Parity file contains header, then for every stripe hashes of all its data and parity blocks, followed by its parity blocks.
+/// Damaged blocks are found by their hashes, and every stripe can be repaired if no more of its blocks are damaged than it has parity blocks.
+struct ParityHeader {
+    file_size: u64,
+    block_size: u64,
+    percent: u8,
+}
+
+impl ParityHeader {
+    /// Creates layout for file with provided size and redundancy - small files are divided into smaller blocks, so their parity is also small.
+    fn new(file_size: u64, percent: u8) -> ParityHeader {
+        let block_size = file_size.div_ceil(STRIPE_DATA_BLOCKS).clamp(MIN_BLOCK_SIZE, MAX_BLOCK_SIZE);
+        ParityHeader { file_size, block_size, percent: percent.clamp(1, 100) }
+    }
+
+    /// Returns number of stripes of file.
+    fn stripes(&self) -> u64 {
+        let blocks = self.file_size.div_ceil(self.block_size);
+        blocks.div_ceil(STRIPE_DATA_BLOCKS)
+    }
+
+    /// Returns number of data blocks and number of parity blocks of stripe with provided index.
+    fn stripe_blocks(&self, stripe: u64) -> (usize, usize) {
+        let stripe_size = STRIPE_DATA_BLOCKS * self.block_size;
+        let remaining = self.file_size - stripe * stripe_size;
+        let data = remaining.min(stripe_size).div_ceil(self.block_size) as usize;
+        let parity = (data * self.percent as usize).div_ceil(100).max(1);
+        (data, parity)
+    }
+
+    /// Returns position of block with provided index of stripe in file, and number of its bytes that belong to file.
+    fn block_range(&self, stripe: u64, block: usize) -> (u64, usize) {
+        let start = (stripe * STRIPE_DATA_BLOCKS + block as u64) * self.block_size;
+        (start, (self.file_size - start).min(self.block_size) as usize)
+    }
+
+    /// Returns header as bytes saved at the beginning of parity file.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = PARITY_MAGIC.to_vec();
+        bytes.extend_from_slice(&self.file_size.to_le_bytes());
+        bytes.extend_from_slice(&(self.block_size as u32).to_le_bytes());
+        bytes.push(self.percent);
+        let hash = block_hash(&bytes);
+        bytes.extend_from_slice(&hash);
+        bytes
+    }
+
+    /// Reads header from the beginning of parity file.
+    ///
+    /// Returns error if header can't be read, isn't header of parity file or is damaged.
+    fn read(reader: &mut dyn Read) -> Result<ParityHeader, String> {
+        let mut bytes = [0u8; HEADER_LEN];
+        reader.read_exact(&mut bytes).map_err(|e| format!("can't read parity file: {}", e))?;
+        let (values, hash) = bytes.split_at(HEADER_LEN - BLOCK_HASH_LEN);
+        if &values[..PARITY_MAGIC.len()] != PARITY_MAGIC || block_hash(values) != hash {
+            return Err(String::from("parity file is damaged"));
+        }
+        let mut file_size = [0u8; 8];
+        file_size.copy_from_slice(&values[6..14]);
+        let mut block_size = [0u8; 4];
+        block_size.copy_from_slice(&values[14..18]);
+        Ok(ParityHeader { file_size: u64::from_le_bytes(file_size), block_size: u32::from_le_bytes(block_size) as u64, percent: values[18] })
+    }
+}
+
+/// Returns hash of block saved in parity file - first bytes of its SHA-256.
+fn block_hash(block: &[u8]) -> Vec<u8> {
+    digest::digest(&digest::SHA256, block).as_ref()[..BLOCK_HASH_LEN].to_vec()
+}
+
+/// Reads into buffer as many bytes as possible, until buffer is full or reader has no more data. Rest of buffer is filled with zeros.
+fn read_block(reader: &mut dyn Read, buffer: &mut [u8]) -> std::io::Result<()> {
+    let mut read = 0;
+    while read < buffer.len() {
+        match reader.read(&mut buffer[read..])? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    buffer[read..].iter_mut().for_each(|x| *x = 0);
+    Ok(())
+}
+
+/// Returns path of parity file of file stored in provided path.
+pub fn parity_path(path: &str) -> String {
+    format!("{}{}", path, PARITY_EXTENSION)
+}
+
+/// Sets redundancy of parity (in percent) of every file in provided dirs.
+///
+/// Should be used after creating output map and before copying. Does nothing if percent is 0.
+pub fn assign_parity(dirs: &mut [BackupDir], percent: u8) {
+    if percent == 0 {
+        return;
+    }
+    for entry in dirs.iter_mut().flat_map(|x| x.backup_entries.iter_mut()).filter(|x| x.is_file) {
+        entry.parity = percent;
+    }
+}
+
+/// Creates parity file next to file stored in provided path, with Reed-Solomon parity blocks making provided percent of file size.
+///
+/// Parity file is written to temporary file first, so existing parity file is replaced only by complete one.
+///
+/// Returns error if file can't be read, or parity file can't be created.
+pub fn create_parity(path: &str, percent: u8) -> Result<(), String> {
+    let partial_path = format!("{}.part", parity_path(path));
+    let result = write_parity(path, &partial_path, percent).and_then(|_| fs::rename(&partial_path, parity_path(path)).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&partial_path);
+        return Err(format!("Couldn't create parity of {}: {}", path, e));
+    }
+    Ok(())
+}
+
+/// Writes parity of file stored in provided path to parity file with provided path.
+fn write_parity(path: &str, parity_path: &str, percent: u8) -> Result<(), String> {
+    let file_size = fs::metadata(path).map_err(|e| e.to_string())?.len();
+    let header = ParityHeader::new(file_size, percent);
+    let mut reader = BufReader::new(File::open(path).map_err(|e| e.to_string())?);
+    let mut writer = BufWriter::new(File::create(parity_path).map_err(|e| e.to_string())?);
+    writer.write_all(&header.to_bytes()).map_err(|e| e.to_string())?;
+    for stripe in 0..header.stripes() {
+        let (data, parity) = header.stripe_blocks(stripe);
+        let mut blocks = vec![vec![0u8; header.block_size as usize]; data + parity];
+        for (i, block) in blocks.iter_mut().take(data).enumerate() {
+            let (_, len) = header.block_range(stripe, i);
+            read_block(&mut (&mut reader).take(len as u64), block).map_err(|e| e.to_string())?;
+        }
+        let codec = ReedSolomon::new(data, parity).map_err(|e| format!("{:?}", e))?;
+        codec.encode(&mut blocks).map_err(|e| format!("{:?}", e))?;
+        for block in &blocks {
+            writer.write_all(&block_hash(block)).map_err(|e| e.to_string())?;
+        }
+        for block in &blocks[data..] {
+            writer.write_all(block).map_err(|e| e.to_string())?;
+        }
+    }
+    writer.flush().map_err(|e| e.to_string())
+}
+
+/// Repairs file stored in provided path in place, using its parity file - damaged blocks are found by hashes saved in parity file and rebuilt from other blocks of their stripe. File is also cut or extended to its original size.
+///
+/// If parity file itself was damaged, it's created again after repair.
+///
+/// Returns number of repaired blocks, or error if file or parity file can't be opened, or any stripe has more damaged blocks than parity blocks.
+pub fn repair_from_parity(path: &str) -> Result<usize, String> {
+    let mut parity = match File::open(parity_path(path)) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => return Err(format!("Can't open parity file of {}: {}", path, e)),
+    };
+    let mut file = match OpenOptions::new().read(true).write(true).open(path) {
+        Ok(file) => file,
+        Err(e) => return Err(format!("Can't repair {} from parity: {}", path, e)),
+    };
+    let result = repair_stripes(&mut file, &mut parity);
+    let (repaired, parity_damaged, percent) = result.map_err(|e| format!("Can't repair {} from parity: {}", path, e))?;
+    if parity_damaged {
+        create_parity(path, percent)?;
+    }
+    Ok(repaired)
+}
+
+/// Checks every stripe of file against hashes from parity file and rebuilds its damaged blocks.
+///
+/// Returns number of repaired blocks, true if any block of parity file was damaged, and redundancy of parity file.
+fn repair_stripes(file: &mut File, parity: &mut BufReader<File>) -> Result<(usize, bool, u8), String> {
+    let header = ParityHeader::read(parity)?;
+    let mut repaired = 0;
+    let mut parity_damaged = false;
+    for stripe in 0..header.stripes() {
+        let (data, parity_blocks) = header.stripe_blocks(stripe);
+        let mut blocks = vec![vec![0u8; header.block_size as usize]; data + parity_blocks];
+        for (i, block) in blocks.iter_mut().take(data).enumerate() {
+            let (start, len) = header.block_range(stripe, i);
+            file.seek(SeekFrom::Start(start)).map_err(|e| e.to_string())?;
+            read_block(&mut Read::by_ref(file).take(len as u64), block).map_err(|e| e.to_string())?;
+        }
+        let mut hashes = vec![0u8; (data + parity_blocks) * BLOCK_HASH_LEN];
+        parity.read_exact(&mut hashes).map_err(|_| String::from("parity file is incomplete"))?;
+        for block in blocks.iter_mut().skip(data) {
+            parity.read_exact(block).map_err(|_| String::from("parity file is incomplete"))?;
+        }
+
+        let mut shards: Vec<Option<Vec<u8>>> = blocks.into_iter().zip(hashes.chunks(BLOCK_HASH_LEN))
+            .map(|(block, hash)| if block_hash(&block) == hash { Some(block) } else { None })
+            .collect();
+        let damaged: Vec<usize> = (0..data).filter(|i| shards[*i].is_none()).collect();
+        parity_damaged |= shards[data..].iter().any(|x| x.is_none());
+        if damaged.is_empty() {
+            continue;
+        }
+        let missing = shards.iter().filter(|x| x.is_none()).count();
+        if missing > parity_blocks {
+            return Err(format!("{} blocks of part {} are damaged, but only {} can be repaired", missing, stripe + 1, parity_blocks));
+        }
+        let codec = ReedSolomon::new(data, parity_blocks).map_err(|e| format!("{:?}", e))?;
+        codec.reconstruct_data(&mut shards).map_err(|e| format!("{:?}", e))?;
+        for i in damaged {
+            let (start, len) = header.block_range(stripe, i);
+            let block = shards[i].as_ref().unwrap();
+            file.seek(SeekFrom::Start(start)).and_then(|_| file.write_all(&block[..len])).map_err(|e| e.to_string())?;
+            repaired += 1;
+        }
+    }
+    file.set_len(header.file_size).and_then(|_| file.sync_all()).map_err(|e| e.to_string())?;
+    Ok((repaired, parity_damaged, header.percent))
+}
+
+/// Creates parity files of all files in which entry is stored, if entry has parity - parity of files copied again is replaced.
+///
+/// Chunks never change their content, so their parity is created only if it doesn't exist yet.
+///
+/// Returns error if any parity file can't be created.
+pub fn store_parity(entry: &BackupEntry) -> Result<(), String> {
+    if entry.parity == 0 {
+        return Ok(());
+    }
+    for path in stored_paths(entry) {
+        if !entry.chunked || !Path::new(&parity_path(&path)).exists() {
+            create_parity(&path, entry.parity)?;
+        }
+    }
+    Ok(())
+}
+
+/// Creates parity files that don't exist yet for files of entries with parity in provided dirs - for example files linked to previous backup, objects stored by previous backups or files copied before parity was turned on.
+///
+/// Stored files are checked against hashes saved in map first - parity of damaged file would only let it be "repaired" to its damaged content, so it isn't created.
+///
+/// Every error is printed to user, and doesn't stop function execution.
+pub fn fill_parity(dirs: &[BackupDir]) {
+    for entry in dirs.iter().flat_map(|x| x.backup_entries.iter()).filter(|x| x.is_file && x.parity > 0) {
+        let without_parity: Vec<String> = stored_paths(entry).into_iter().filter(|x| Path::new(x).exists() && !Path::new(&parity_path(x)).exists()).collect();
+        if without_parity.is_empty() {
+            continue;
+        }
+        let corrupted = match entry.chunked {
+            true => verify_chunks(entry).1,
+            false => match hash_entry(entry) {
+                Ok(hash) if hash == entry.hash => vec![],
+                _ => vec![entry.output_path.clone()],
+            },
+        };
+        for path in without_parity {
+            if corrupted.contains(&path) {
+                println!("File {} doesn't match hash saved in map, its parity file won't be created", &path);
+                continue;
+            }
+            if let Err(e) = create_parity(&path, entry.parity) {
+                println!("{}", e);
+            }
+        }
+    }
+}
+
+/// Removes parity file of file stored in provided path, if it exists.
+pub fn remove_parity(path: &str) {
+    let _ = fs::remove_file(parity_path(path));
+}
@@ -0,0 +1,103 @@
+use crate::backups::helpers::hash_cache::cache_folder;
+use crate::{S_POOL_MARKER, S_SEPARATOR, S_STATUS};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use chrono::Utc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_select_drive() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        let path = |name: &str| root.join(name).to_str().unwrap().to_string();
+        for name in &["a", "b", "c"] {
+            fs::create_dir_all(root.join(name)).unwrap();
+        }
+        fs::write(root.join("a").join(S_POOL_MARKER), "Drive A\n").unwrap();
+        fs::write(root.join("b").join(S_POOL_MARKER), "").unwrap();
+        let pool = vec![path("a"), path("b"), path("c"), path("missing")];
+
+        assert!(is_connected(&path("a")));
+        assert!(!is_connected(&path("c")));
+        assert_eq!(drive_label(&path("a")), "Drive A");
+        assert_eq!(drive_label(&path("b")), path("b"));
+
+        // Drive updated longer ago is chosen
+        let mut state = HashMap::new();
+        state.insert(path("a"), 200);
+        state.insert(path("b"), 100);
+        assert_eq!(select_drive(&pool, &state).unwrap(), path("b"));
+        state.insert(path("b"), 300);
+        assert_eq!(select_drive(&pool, &state).unwrap(), path("a"));
+        assert!(select_drive(&pool[2..], &state).is_err());
+
+        let state_path = path("cache/state.json");
+        record_backup(&state_path, &path("c")).unwrap();
+        assert!(load_state(&state_path).get(&path("c")).unwrap() > &0);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
+
+/// Returns true if drive with provided output folder is connected - its output folder contains marker file.
+///
+/// Marker file distinguishes connected drive from empty mount point, so backup is never made on disk that only contains mount point.
+pub fn is_connected(folder: &str) -> bool {
+    Path::new(folder).join(S_POOL_MARKER).is_file()
+}
+
+/// Returns name of drive written in its marker file, or its output folder if marker file is empty or can't be read.
+pub fn drive_label(folder: &str) -> String {
+    match fs::read_to_string(format!("{}{}{}", folder, S_SEPARATOR, S_POOL_MARKER)) {
+        Ok(label) if !label.trim().is_empty() => String::from(label.trim()),
+        _ => String::from(folder),
+    }
+}
+
+/// Chooses output folder of drive from pool that is currently connected. If many drives are connected, the one updated longest ago is chosen, so drives are rotated.
+///
+/// Returns error if no drive from pool is connected.
+pub fn select_drive(pool: &[String], state: &HashMap<String, usize>) -> Result<String, String> {
+    match pool.iter().filter(|x| is_connected(x)).min_by_key(|x| state.get(*x).cloned().unwrap_or(0)) {
+        Some(folder) => Ok(folder.clone()),
+        None => Err(format!("None of {} drives in pool is connected - connect one of them, or create {} file in its output folder", pool.len(), S_POOL_MARKER)),
+    }
+}
+
+/// Returns path to status file in cache folder, so the same file is used no matter from which folder program is started.
+///
+/// Returns error if cache folder can't be found.
+pub fn status_path() -> Result<String, String> {
+    match cache_folder() {
+        Some(folder) => Ok(format!("{}{}{}", folder, S_SEPARATOR, S_STATUS)),
+        None => Err(String::from("Can't find cache folder to keep status file in")),
+    }
+}
+
+/// Loads times of last backups in every drive from pool from status file in cache folder. Returns empty map if it can't be read.
+pub fn load_status() -> HashMap<String, usize> {
+    status_path().map(|path| load_state(&path)).unwrap_or_default()
+}
+
+/// Loads times of last backups in every output folder from status file, as map of output folder and timestamp.
+///
+/// Returns empty map if file doesn't exist or can't be read.
+pub fn load_state(path: &str) -> HashMap<String, usize> {
+    fs::read_to_string(path).ok().and_then(|text| serde_json::from_str(&text).ok()).unwrap_or_default()
+}
+
+/// Saves current time as time of last backup in provided output folder to status file, keeping times of other output folders.
+///
+/// Returns error if file can't be saved.
+pub fn record_backup(path: &str, folder: &str) -> Result<(), String> {
+    let mut state = load_state(path);
+    state.insert(String::from(folder), Utc::now().timestamp() as usize);
+    let text = serde_json::to_string_pretty(&state).map_err(|e| e.to_string())?;
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Can't create folder for status file {}: {}", path, e))?;
+    }
+    fs::write(path, text).map_err(|e| format!("Can't save status file {}: {}", path, e))
+}
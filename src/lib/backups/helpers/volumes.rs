@@ -0,0 +1,65 @@
+use crate::backups::map::backup_map::BackupMap;
+use std::io;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::map::backup_mode::BackupMode;
+
+    #[test]
+    fn test_next_missing_volume() {
+        let part = |volume: usize, last_volume: bool| BackupMap { volume, last_volume, ..BackupMap::new(BackupMode::Multiple) };
+        assert_eq!(next_missing_volume(&[part(1, false)]), Some(2));
+        assert_eq!(next_missing_volume(&[part(2, false), part(1, false)]), Some(3));
+        assert_eq!(next_missing_volume(&[part(3, true), part(1, false)]), Some(2));
+        assert_eq!(next_missing_volume(&[part(1, false), part(3, true), part(2, false)]), None);
+        assert_eq!(next_missing_volume(&[part(0, false)]), None);
+    }
+}
+
+/// Space left free on every volume, in bytes, so it doesn't get completely full - map and folders need some space too.
+pub const VOLUME_RESERVE: u64 = 16 * 1024 * 1024;
+
+/// Returns true if volume containing provided folder has enough free space to store provided amount of bytes, keeping ```VOLUME_RESERVE``` free.
+///
+/// Returns error if free space of volume can't be checked.
+pub fn has_space_for(folder: &str, size: u64) -> Result<bool, String> {
+    match fs2::available_space(folder) {
+        Ok(available) => Ok(available >= size.saturating_add(VOLUME_RESERVE)),
+        Err(e) => Err(format!("Can't check free space of volume with folder {}: {}", folder, e)),
+    }
+}
+
+/// Prints provided message and asks user for path to folder on next volume (for example mount point of other drive).
+///
+/// Returns empty string if user writes nothing, or if input can't be read.
+pub fn ask_for_volume(message: &str) -> String {
+    let mut path = String::new();
+    println!("{}", message);
+    println!("Write path to folder on next volume (leave empty to stop):");
+    match io::stdin().read_line(&mut path) {
+        Ok(_) => String::from(path.trim()),
+        Err(_) => {
+            println!("Can't read path to next volume");
+            String::new()
+        }
+    }
+}
+
+/// Returns number of first volume of spanned backup that is still missing, based on already restored parts of backup.
+///
+/// Returns None if backup isn't spanned, or every volume from first to last one has been already restored.
+pub fn next_missing_volume(restored: &[BackupMap]) -> Option<usize> {
+    if restored.iter().any(|x| x.volume == 0) {
+        return None;
+    }
+    let last = restored.iter().find(|x| x.last_volume).map(|x| x.volume);
+    let mut volume = 1;
+    while restored.iter().any(|x| x.volume == volume) {
+        if Some(volume) == last {
+            return None;
+        }
+        volume += 1;
+    }
+    Some(volume)
+}
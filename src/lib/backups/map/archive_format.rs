@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Enum to store all possible ways of storing files of one backup.
+#[derive(Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Default)]
+pub enum ArchiveFormat {
+    /// Every file is copied separately, to the same folder structure as in input folder.
+    #[default]
+    Folder,
+    /// All files are stored in one tar archive.
+    Tar,
+    /// All files are stored in one tar archive, compressed with zstd.
+    TarZst,
+    /// All files are stored in one zip archive, which can be opened without additional tools on every operating system.
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Returns extension of archive file, including dot, or empty string for folder.
+    pub fn extension(&self) -> &str {
+        match self {
+            ArchiveFormat::Folder => "",
+            ArchiveFormat::Tar => ".tar",
+            ArchiveFormat::TarZst => ".tar.zst",
+            ArchiveFormat::Zip => ".zip",
+        }
+    }
+
+    /// Returns format of archive with provided path, based on its extension.
+    pub fn from_path(path: &str) -> ArchiveFormat {
+        if path.ends_with(ArchiveFormat::Zip.extension()) {
+            ArchiveFormat::Zip
+        } else if path.ends_with(ArchiveFormat::TarZst.extension()) {
+            ArchiveFormat::TarZst
+        } else if path.ends_with(ArchiveFormat::Tar.extension()) {
+            ArchiveFormat::Tar
+        } else {
+            ArchiveFormat::Folder
+        }
+    }
+}
+
+impl fmt::Display for ArchiveFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArchiveFormat::Folder => write!(f, "folder"),
+            ArchiveFormat::Tar => write!(f, "tar"),
+            ArchiveFormat::TarZst => write!(f, "tar.zst"),
+            ArchiveFormat::Zip => write!(f, "zip"),
+        }
+    }
+}
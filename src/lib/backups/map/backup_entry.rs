@@ -1,3 +1,6 @@
+use crate::backups::map::compression_codec::CompressionCodec;
+use crate::backups::map::encryption_cipher::EncryptionCipher;
+use crate::backups::map::is_default::is_default;
 use serde::{Deserialize, Serialize};
 
 /// Contains data of one entry (file or folder).
@@ -9,12 +12,40 @@ pub struct BackupEntry {
     pub input_path: String,
     pub output_path: String,
     pub is_file: bool,
+    /// Hash of original file - for compressed files, hash of its decompressed content.
     pub hash: String,
+    /// Codec with which file in backup is compressed.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub compression: CompressionCodec,
+    /// Size of original file, in bytes.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub original_size: u64,
+    /// Size of file stored in backup, in bytes - for compressed files, size after compression.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub stored_size: u64,
+    /// Cipher with which file in backup is encrypted (after compression).
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub encryption: EncryptionCipher,
+    /// If true, file is split into chunks stored in chunks folder (output path), instead of being stored as one file.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub chunked: bool,
+    /// Hashes of all chunks of chunked file, in order.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub chunks: Vec<String>,
+    /// Key from which names of chunks of encrypted chunked file are derived, so names don't reveal hashes of chunks. Empty if chunks are named by their hashes.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub chunk_key: String,
+    /// Redundancy of parity file of file stored in backup, in percent of its size, or 0 if file has no parity.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub parity: u8,
+    /// Time of last modification of original file when it was hashed, in nanoseconds since UNIX epoch, or 0 if unknown - with size, used to reuse hash of unchanged file in next backup.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub modified: u64,
 }
 
 impl BackupEntry {
     /// Creates new instance of BackupDir, with all values empty or equal to false.
     pub fn new() -> BackupEntry {
-        BackupEntry { input_path: String::new(), output_path: String::new(), is_file: false, hash: String::new() }
+        BackupEntry { input_path: String::new(), output_path: String::new(), is_file: false, hash: String::new(), compression: CompressionCodec::None, original_size: 0, stored_size: 0, encryption: EncryptionCipher::None, chunked: false, chunks: vec![], chunk_key: String::new(), parity: 0, modified: 0 }
     }
 }
\ No newline at end of file
@@ -1,9 +1,17 @@
 use crate::backups::map::backup_mode::BackupMode;
 use crate::backups::map::backup_dir::BackupDir;
+use crate::backups::map::archive_format::ArchiveFormat;
+use crate::backups::map::compression_codec::CompressionCodec;
+use crate::backups::map::encryption_cipher::EncryptionCipher;
+use crate::backups::map::map_format::MapFormat;
+use crate::backups::map::is_default::is_default;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::Utc;
 
+/// Current version of format of map - it should be increased, and migration from previous version should be added, every time fields of map are changed in a way that breaks older maps.
+pub const MAP_FORMAT_VERSION: u32 = 2;
+
 /// Contains data of whole filemap and basically all program functions rely on this struct.
 #[derive(Serialize, Deserialize)]
 #[derive(Clone)]
@@ -18,7 +26,73 @@ pub struct BackupMap {
     pub input_folders: Vec<String>,
     pub ignore_extensions: Vec<String>,
     pub ignore_folders: Vec<String>,
-    pub backup_dirs: Vec<BackupDir>
+    pub backup_dirs: Vec<BackupDir>,
+    /// Timestamp of last verification done by ```verify``` command, 0 if backup was never verified.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub last_verified: usize,
+    /// If true, unchanged files are hardlinked to newest previous backup, instead of copied (only for Multiple mode).
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub hardlinks: bool,
+    /// Id of previous backup in chain, empty if this backup is full copy (only for Incremental mode).
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub parent_id: String,
+    /// Input paths of files and folders deleted since previous backup in chain (only for Incremental mode).
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub deleted: Vec<String>,
+    /// Format in which files of backup are stored - if it isn't folder, output paths of files are paths inside archive placed in output folder (only for Multiple mode).
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub archive: ArchiveFormat,
+    /// Codec used to compress files of backup - every entry has its own codec, because already compressed files are copied without changes (not used for backups stored in archive).
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub compression: CompressionCodec,
+    /// Cipher used to encrypt files and map of backup, with key derived from passphrase (not used for backups stored in archive).
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub encryption: EncryptionCipher,
+    /// If true, files and folders of backup have opaque names, and their real paths are saved only in encrypted map.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub obfuscate_names: bool,
+    /// Secret key used to create opaque names of files and folders, empty if names aren't obfuscated.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub name_key: String,
+    /// If true, big files are split into chunks with content-defined boundaries, stored in chunks folder shared by all backups, so only changed chunks are copied (only for Multiple and Cloud modes).
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub chunking: bool,
+    /// Format in which map is saved to file.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub map_format: MapFormat,
+    /// Version of format of map, 0 for maps saved before maps had versions. Older maps are migrated to current version while loading.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub format_version: u32,
+    /// Redundancy of parity files created for every stored file (or archive), in percent of its size, or 0 if parity isn't created.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub parity: u8,
+    /// If true, backup continues on next volume (output drive) when current one is full (only for Multiple mode, not used for backups stored in archive).
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub spanning: bool,
+    /// Folders on next volumes, used in order when previous volume is full - if there's no more of them, user is asked for path of next volume.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub volumes: Vec<String>,
+    /// Number of volume containing this part of spanned backup, starting from 1, or 0 if backup isn't spanned. Every part has the same id.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub volume: usize,
+    /// True if this part of spanned backup is stored on the last volume.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub last_volume: bool,
+    /// Other output folders, to which the same backup is copied in the same run - every one of them gets its own backup with its own map, so saved maps don't contain them.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub mirrors: Vec<String>,
+    /// If true, every file is hashed, even if it has the same size and time of modification as in previous backup. Only option of current run, never saved.
+    #[serde(skip)]
+    pub paranoid: bool,
+    /// True if output folder was chosen from pool of drives, so time of backup is saved in status file. Only option of current run, never saved.
+    #[serde(skip)]
+    pub pooled: bool,
+    /// If true, hashes of source files are saved in cache in user's cache folder, and files with unchanged size, time of modification and inode aren't hashed again, even without previous backup.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub hash_cache: bool,
+    /// Checksum of map (SHA-256, or HMAC-SHA256 if key is set), calculated with this field empty, checked every time map is loaded.
+    #[serde(default)]
+    pub checksum: String,
 }
 
 impl BackupMap {
@@ -26,15 +100,17 @@ impl BackupMap {
     ///
     /// Requires bakcup mode as an argument.
     pub fn new(mode: BackupMode) -> BackupMap {
-        BackupMap { id: String::new(), timestamp: 0, backup_mode: mode, max_backups: 1, files: 0, folders: 0, output_folder: String::new(), input_folders: vec![], ignore_extensions: vec![], ignore_folders: vec![], backup_dirs: Vec::new() }
+        BackupMap { id: String::new(), timestamp: 0, backup_mode: mode, max_backups: 1, files: 0, folders: 0, output_folder: String::new(), input_folders: vec![], ignore_extensions: vec![], ignore_folders: vec![], backup_dirs: Vec::new(), last_verified: 0, hardlinks: false, parent_id: String::new(), deleted: vec![], archive: ArchiveFormat::Folder, compression: CompressionCodec::None, encryption: EncryptionCipher::None, obfuscate_names: false, name_key: String::new(), chunking: false, map_format: MapFormat::Json, format_version: MAP_FORMAT_VERSION, parity: 0, spanning: false, volumes: vec![], volume: 0, last_volume: false, mirrors: vec![], paranoid: false, pooled: false, hash_cache: false, checksum: String::new() }
     }
 
     /// Generates metadata of BackupMap, with random id, timestamp of time of execution and current number of files and folders.
     ///
-    /// This function doesn't fill BackupMode.
+    /// This function doesn't fill BackupMode. Id and timestamp are kept if id is already set - every part of spanned backup has the same id.
     pub fn generate_metadata(&mut self) {
-        self.id = Uuid::new_v4().to_string();
-        self.timestamp = Utc::now().timestamp() as usize;
+        if self.id.is_empty() {
+            self.id = Uuid::new_v4().to_string();
+            self.timestamp = Utc::now().timestamp() as usize;
+        }
         self.files = self.backup_dirs.iter().map(|x| x.files).sum();
         self.folders = self.backup_dirs.iter().map(|x| x.folders).sum();
     }
@@ -1,9 +1,25 @@
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 /// Enum to store all possible backup modes.
 #[derive(Serialize, Deserialize)]
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum BackupMode {
     Multiple,
     Cloud,
+    /// Content of files is stored once in objects folder, named by hash, and every backup is only a map pointing to objects.
+    Dedup,
+    /// First backup is full copy, every next one contains only files changed since previous backup, and points to it with parent id.
+    Incremental,
+}
+
+impl fmt::Display for BackupMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BackupMode::Multiple => write!(f, "multiple"),
+            BackupMode::Cloud => write!(f, "cloud"),
+            BackupMode::Dedup => write!(f, "dedup"),
+            BackupMode::Incremental => write!(f, "incremental"),
+        }
+    }
 }
\ No newline at end of file
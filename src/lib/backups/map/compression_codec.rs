@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Enum to store all possible ways of compressing single file in backup.
+#[derive(Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Default)]
+pub enum CompressionCodec {
+    /// File is copied without any changes.
+    #[default]
+    None,
+    /// File is compressed with zstd - fast, with good compression ratio.
+    Zstd,
+    /// File is compressed with gzip - slower, but can be opened by almost every tool.
+    Gzip,
+}
+
+impl CompressionCodec {
+    /// Returns extension added to name of compressed file, including dot, or empty string if file isn't compressed.
+    pub fn extension(&self) -> &str {
+        match self {
+            CompressionCodec::None => "",
+            CompressionCodec::Zstd => ".zst",
+            CompressionCodec::Gzip => ".gz",
+        }
+    }
+}
+
+impl fmt::Display for CompressionCodec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompressionCodec::None => write!(f, "none"),
+            CompressionCodec::Zstd => write!(f, "zstd"),
+            CompressionCodec::Gzip => write!(f, "gzip"),
+        }
+    }
+}
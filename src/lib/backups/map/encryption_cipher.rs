@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Enum to store all possible ways of encrypting files and maps of backup.
+#[derive(Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Default)]
+pub enum EncryptionCipher {
+    /// Backup isn't encrypted.
+    #[default]
+    None,
+    /// Files are encrypted with ChaCha20-Poly1305 - fast on every processor.
+    ChaCha20Poly1305,
+    /// Files are encrypted with AES-256-GCM - fastest on processors with AES instructions.
+    Aes256Gcm,
+}
+
+impl EncryptionCipher {
+    /// Returns number saved in header of encrypted file, identifying cipher, or 0 if backup isn't encrypted.
+    pub fn id(&self) -> u8 {
+        match self {
+            EncryptionCipher::None => 0,
+            EncryptionCipher::ChaCha20Poly1305 => 1,
+            EncryptionCipher::Aes256Gcm => 2,
+        }
+    }
+
+    /// Returns cipher with provided number from header of encrypted file, or None if number is unknown.
+    pub fn from_id(id: u8) -> Option<EncryptionCipher> {
+        match id {
+            0 => Some(EncryptionCipher::None),
+            1 => Some(EncryptionCipher::ChaCha20Poly1305),
+            2 => Some(EncryptionCipher::Aes256Gcm),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for EncryptionCipher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncryptionCipher::None => write!(f, "none"),
+            EncryptionCipher::ChaCha20Poly1305 => write!(f, "chacha20-poly1305"),
+            EncryptionCipher::Aes256Gcm => write!(f, "aes-256-gcm"),
+        }
+    }
+}
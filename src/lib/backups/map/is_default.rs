@@ -0,0 +1,6 @@
+/// Returns true if value is equal to default value of its type.
+///
+/// Every field added to map structs should be skipped while serializing if it has default value (```#[serde(default, skip_serializing_if = "is_default")]```) - otherwise checksums of maps saved before that field existed wouldn't match anymore.
+pub fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Enum to store all possible formats of map files - format is recognized while loading map, so maps in different formats can be mixed in one output folder.
+#[derive(Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Default)]
+pub enum MapFormat {
+    /// Map is saved as pretty-printed JSON, which can be read and edited by hand.
+    #[default]
+    Json,
+    /// Map is saved as binary CBOR - smaller and faster to read and write than JSON.
+    Cbor,
+    /// Map is saved as binary CBOR, compressed with zstd - the smallest format, best for backups with millions of files.
+    CborZst,
+}
+
+impl fmt::Display for MapFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MapFormat::Json => write!(f, "json"),
+            MapFormat::Cbor => write!(f, "cbor"),
+            MapFormat::CborZst => write!(f, "cbor.zst"),
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use serde::Deserialize;
+
+/// Contains only format version of map - every other field is skipped while deserializing, so version can be read even from map whose fields can't be understood by this version of program.
+#[derive(Deserialize)]
+pub struct MapVersion {
+    /// Version of format of map, 0 for maps saved before maps had versions.
+    #[serde(default)]
+    pub format_version: u32,
+}
@@ -1,4 +1,10 @@
 pub mod backup_entry;
 pub mod backup_dir;
 pub mod backup_map;
-pub mod backup_mode;
\ No newline at end of file
+pub mod backup_mode;
+pub mod archive_format;
+pub mod compression_codec;
+pub mod encryption_cipher;
+pub mod map_format;
+pub mod map_version;
+pub mod is_default;
\ No newline at end of file
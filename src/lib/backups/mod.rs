@@ -1,4 +1,5 @@
 pub mod traits;
 pub mod map;
 pub mod modes;
-pub mod helpers;
\ No newline at end of file
+pub mod helpers;
+pub mod actions;
\ No newline at end of file
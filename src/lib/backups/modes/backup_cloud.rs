@@ -11,11 +11,59 @@ use std::borrow::{Borrow, BorrowMut};
 use crate::backups::helpers::multithreading::arc_to_inner;
 use std::fs::{remove_file, create_dir_all};
 use crate::backups::traits::backup_output::BackupOutput;
-use crate::backups::helpers::dirs::{get_last_subdir, find_previous_backups, delete_folder_with_content};
-use crate::S_SEPARATOR;
+use crate::backups::helpers::dirs::{get_last_subdir, find_previous_backups_strict, delete_folder_with_content};
+use crate::backups::helpers::compression::assign_compression;
+use crate::backups::helpers::encryption::{assign_encryption, assign_name_key};
+use crate::backups::helpers::chunking::{assign_chunking, fill_chunks};
+use crate::backups::helpers::parity::{assign_parity, remove_parity};
+use crate::backups::modes::backup_dedup::collect_garbage;
+use crate::{S_SEPARATOR, S_CHUNKS};
 use crate::backups::traits::backup::Backup;
 use std::path::Path;
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::backups::map::backup_entry::BackupEntry;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_backup_to_empty_folder() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(root.join("source")).unwrap();
+        fs::create_dir_all(root.join("backup")).unwrap();
+        fs::write(root.join("source").join("a.txt"), "a").unwrap();
+        let path = |name: &str| root.join(name).to_str().unwrap().to_string();
+
+        let mut map = BackupMap::new(BackupMode::Cloud);
+        map.input_folders = vec![path("source")];
+        map.output_folder = path("backup");
+        map.max_backups = 1;
+        BackupCloud::new(map).backup().unwrap();
+        assert_eq!(fs::read_to_string(root.join("backup").join("source").join("a.txt")).unwrap(), "a");
+        assert_eq!(find_previous_backups_strict(&path("backup")).unwrap().len(), 1);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_delete_missing_skips_chunked() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(root.join(".chunks")).unwrap();
+        fs::write(root.join(".chunks").join("chunk"), "chunk").unwrap();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        let path = |name: &str| root.join(name).to_str().unwrap().to_string();
+        let chunked = BackupEntry { input_path: path("big.bin"), output_path: path(".chunks"), is_file: true, hash: String::from("big"), chunked: true, ..BackupEntry::new() };
+        let removed = BackupEntry { input_path: path("a.txt"), output_path: path("a.txt"), is_file: true, hash: String::from("a"), ..BackupEntry::new() };
+        let mut previous = BackupDir { backup_entries: vec![chunked, removed], ..BackupDir::new() };
+
+        delete_missing_one_folder(&BackupDir::new(), &mut previous).unwrap();
+        assert!(!root.join("a.txt").exists());
+        assert!(root.join(".chunks").join("chunk").exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
+
 pub struct BackupCloud {
     pub map: BackupMap,
     pub previous_map: BackupMap,
@@ -39,6 +87,11 @@ impl BackupCloud {
         backup_cloud
     }
 
+    /// Returns path of folder with chunks of big files, placed in output folder.
+    fn chunks_folder(&self) -> String {
+        format!("{}{}{}", &self.map.output_folder, S_SEPARATOR, S_CHUNKS)
+    }
+
     /// Searches for new or modified entries in folders and puts them in ```copy_dirs``` fields.
     ///
     /// Works in multi threads (max amount of them is equal to computer's thread count).
@@ -188,7 +241,7 @@ impl BackupCloud {
 /// Compares folders content with its equivalent in backup and returns ```BackupDir``` of those entries that doesn't have equivalent in backup.
 ///
 ///
-/// New or modified file is file with different hash then it's equivalent in backup.
+/// New or modified file is file with different hash then it's equivalent in backup. File compressed with different codec, encrypted with different cipher or stored under different name than now is also copied again.
 fn generate_entries_to_copy_one_folder(folder: &BackupDir, previous_folder: &BackupDir) -> BackupDir {
     let mut counter: usize = 0;
     let mut copy_folder = BackupDir::new();
@@ -200,7 +253,7 @@ fn generate_entries_to_copy_one_folder(folder: &BackupDir, previous_folder: &Bac
             // Checking if folder contains the same file
             let mut found_matching_hash = false;
             for previous_entry in &previous_folder.backup_entries {
-                if previous_entry.hash == entry.hash {
+                if previous_entry.hash == entry.hash && previous_entry.compression == entry.compression && previous_entry.encryption == entry.encryption && previous_entry.output_path == entry.output_path {
                     found_matching_hash = true;
                     break;
                 }
@@ -228,7 +281,7 @@ fn generate_entries_to_copy_one_folder(folder: &BackupDir, previous_folder: &Bac
 
 /// Deletes from folder all files and folders that doesn't exist in latest version of input folders, deletes them also from map.
 ///
-/// The function acknowledges that the file does not exist in current version of folder if no file in current version of folder has the same hash.
+/// The function acknowledges that the file does not exist in current version of folder if no file in current version of folder has the same hash, codec, cipher and output path.
 ///
 /// Function returns error if folder can't be deleted recursively.
 fn delete_missing_one_folder(folder: &BackupDir, previous_folder: &mut BackupDir) -> Result<(), String> {
@@ -237,16 +290,21 @@ fn delete_missing_one_folder(folder: &BackupDir, previous_folder: &mut BackupDir
     for previous_entry in &previous_folder.backup_entries {
         let mut found = false;
         for entry in &folder.backup_entries {
-            if previous_entry.hash == entry.hash {
+            if previous_entry.hash == entry.hash && previous_entry.compression == entry.compression && previous_entry.encryption == entry.encryption && previous_entry.output_path == entry.output_path {
                 found = true;
                 break;
             }
         }
         if !found {
+            // Output path of chunked file is chunks folder shared by all files - chunks that aren't used anymore are removed by garbage collection
+            if previous_entry.chunked {
+                continue;
+            }
             if previous_entry.is_file {
                 if let Err(_) = remove_file(&previous_entry.output_path) {
                     continue;
                 }
+                remove_parity(&previous_entry.output_path);
                 deleted += 1;
             } else {
                 match delete_folder_with_content(&previous_entry.output_path, &folder) {
@@ -319,7 +377,7 @@ impl Backup for BackupCloud {
         }
         match Path::new(&self.map.output_folder).exists() {
             true => {
-                match find_previous_backups(&self.map.output_folder) {
+                match find_previous_backups_strict(&self.map.output_folder) {
                     Err(e) => {
                         let message = format!("Couldn't find previous backups: {}. Program will stop", e);
                         panic!(message);
@@ -328,7 +386,10 @@ impl Backup for BackupCloud {
                         if backups.len() > 1 {
                             panic!(String::from("Found too many backups, program will stop"));
                         }
-                        self.previous_map = backups[0].clone();
+                        // Existing output folder may be still empty, for example drive from pool with only marker file
+                        if let Some(previous_map) = backups.into_iter().next() {
+                            self.previous_map = previous_map;
+                        }
                     }
                 }
             },
@@ -340,11 +401,26 @@ impl Backup for BackupCloud {
         }
 
         // Filling map with data - find better way without moving so much data
-        self.map.backup_dirs = BackupCloud::create_input_maps(&self.map.input_folders);
+        // Input maps may be already created, when the same backup is copied to many output folders
+        if self.map.backup_dirs.is_empty() {
+            self.map.backup_dirs = BackupCloud::create_input_maps_with_hashes(&self.map, &[&self.previous_map]);
+        }
         let map_copy = self.map.clone();
         self.map.backup_dirs = BackupCloud::ignore_files_and_folders_parrarel(map_copy.backup_dirs, &map_copy.ignore_extensions, &map_copy.ignore_folders);
         let map_copy = self.map.clone();
         self.map = BackupCloud::create_output_map(map_copy);
+        assign_compression(&mut self.map.backup_dirs, &self.map.compression);
+        assign_encryption(&mut self.map.backup_dirs, &self.map.encryption);
+        assign_parity(&mut self.map.backup_dirs, self.map.parity);
+        // Unchanged files and chunks have to keep their names, so key of previous backup is reused
+        assign_name_key(&mut self.map, std::slice::from_ref(&self.previous_map));
+        if self.map.obfuscate_names {
+            BackupCloud::obfuscate_output_map(&mut self.map);
+        }
+        if self.map.chunking {
+            let chunks_folder = self.chunks_folder();
+            assign_chunking(&mut self.map.backup_dirs, &chunks_folder, &self.map.name_key);
+        }
 
         // Filling copy_dirs and deleting redundant files
         if let Err(e) = self.generate_entries_to_copy_all() {
@@ -365,12 +441,22 @@ impl Backup for BackupCloud {
             false => {
                 // Deleting non-confirmed entries from map
                 self.copy_dirs = BackupCloud::copy_all(self.copy_dirs.clone());
+                let known: Vec<BackupDir> = self.copy_dirs.iter().chain(self.previous_map.backup_dirs.iter()).cloned().collect();
+                fill_chunks(&mut self.map.backup_dirs, &known);
                 let copied = self.map.backup_dirs.clone();
                 self.map.backup_dirs = BackupCloud::delete_non_existing(copied);
 
-                // Serializing map
-                if let Err(e) = BackupCloud::serialize_to_json(&mut self.map) {
-                    println!("Map couldn't be saved to file, this backup won't be considered next time: {}", e);
+                // Serializing map and removing chunks no longer used by it
+                match BackupCloud::serialize_to_json(&mut self.map) {
+                    Err(e) => println!("Map couldn't be saved to file, this backup won't be considered next time: {}", e),
+                    Ok(_) => {
+                        if Path::new(&self.chunks_folder()).is_dir() {
+                            match collect_garbage(&self.chunks_folder(), std::slice::from_ref(&self.map)) {
+                                Ok(removed) => println!("Removed {} chunks no longer used by backup", removed),
+                                Err(e) => println!("Couldn't remove unused chunks: {}", e),
+                            }
+                        }
+                    }
                 }
                 // Verifying files
                 if let Err(e) = BackupCloud::verify_all(&self.map) {
@@ -0,0 +1,334 @@
+use crate::backups::traits::backup::Backup;
+use crate::backups::traits::backup_copy::BackupCopy;
+use crate::backups::traits::backup_ignore::BackupIgnore;
+use crate::backups::traits::backup_input::BackupInput;
+use crate::backups::traits::backup_serialize::{BackupSerialize, verify_one_folder};
+use crate::backups::traits::backup_output::BackupOutput;
+use crate::backups::map::backup_map::BackupMap;
+use crate::backups::map::backup_mode::BackupMode;
+use crate::backups::map::backup_dir::BackupDir;
+use crate::backups::map::compression_codec::CompressionCodec;
+use crate::backups::map::encryption_cipher::EncryptionCipher;
+use crate::backups::helpers::encryption::{assign_encryption, assign_name_key, obfuscated_name};
+use crate::backups::helpers::dirs::{get_last_subdir, find_previous_backups_strict};
+use crate::backups::helpers::compression::assign_compression;
+use crate::backups::helpers::chunking::stored_paths;
+use crate::backups::helpers::parity::{assign_parity, parity_path};
+use crate::{S_SEPARATOR, S_OBJECTS};
+use std::collections::HashSet;
+use std::fs::{create_dir_all, remove_dir_all, remove_file, remove_dir, metadata};
+use std::path::Path;
+use chrono::Local;
+use walkdir::WalkDir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::map::backup_entry::BackupEntry;
+    use std::fs;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_object_path() {
+        assert_eq!(object_path("/backup/.objects", "abcdef"), format!("/backup/.objects{}ab{}cdef", S_SEPARATOR, S_SEPARATOR));
+        assert_eq!(object_path("/backup/.objects", &"a".repeat(256)), format!("/backup/.objects{}aa{}{}", S_SEPARATOR, S_SEPARATOR, "a".repeat(64)));
+    }
+
+    #[test]
+    fn test_collect_garbage() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        let objects = root.join(S_OBJECTS);
+        let objects = objects.to_str().unwrap();
+        let used = object_path(objects, "aaaa");
+        let unused = object_path(objects, "bbbb");
+        fs::create_dir_all(Path::new(&used).parent().unwrap()).unwrap();
+        fs::create_dir_all(Path::new(&unused).parent().unwrap()).unwrap();
+        fs::write(&used, "a").unwrap();
+        fs::write(&unused, "b").unwrap();
+
+        let mut dir = BackupDir::new();
+        dir.backup_entries.push(BackupEntry { input_path: String::from("/home/user/a.txt"), output_path: used.clone(), is_file: true, hash: String::from("aaaa"), ..BackupEntry::new() });
+        let mut map = BackupMap::new(BackupMode::Dedup);
+        map.backup_dirs = vec![dir];
+
+        assert_eq!(collect_garbage(objects, &[map]).unwrap(), 1);
+        assert!(Path::new(&used).exists());
+        assert!(!Path::new(&unused).exists());
+        assert!(!Path::new(&unused).parent().unwrap().exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_backup() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(root.join("source").join("copy")).unwrap();
+        fs::write(root.join("source").join("a.txt"), "same").unwrap();
+        fs::write(root.join("source").join("copy").join("a.txt"), "same").unwrap();
+        let mut map = BackupMap::new(BackupMode::Dedup);
+        map.input_folders = vec![root.join("source").to_str().unwrap().to_string()];
+        map.output_folder = root.join("backup").to_str().unwrap().to_string();
+        map.max_backups = 1;
+
+        BackupDedup::new(map.clone()).backup().unwrap();
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        BackupDedup::new(map).backup().unwrap();
+        let objects: Vec<_> = WalkDir::new(root.join("backup").join(S_OBJECTS)).into_iter().filter_map(|e| e.ok()).filter(|e| e.path().is_file()).collect();
+        assert_eq!(objects.len(), 1);
+        assert_eq!(find_previous_backups_strict(&root.join("backup").to_str().unwrap().to_string()).unwrap().len(), 1);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_backup_same_content_different_compression() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(root.join("source")).unwrap();
+        fs::write(root.join("source").join("a.txt"), "same").unwrap();
+        fs::write(root.join("source").join("a.jpg"), "same").unwrap();
+        let mut map = BackupMap::new(BackupMode::Dedup);
+        map.input_folders = vec![root.join("source").to_str().unwrap().to_string()];
+        map.output_folder = root.join("backup").to_str().unwrap().to_string();
+        map.max_backups = 1;
+        map.compression = CompressionCodec::Zstd;
+
+        BackupDedup::new(map).backup().unwrap();
+        let objects: Vec<_> = WalkDir::new(root.join("backup").join(S_OBJECTS)).into_iter().filter_map(|e| e.ok()).filter(|e| e.path().is_file()).collect();
+        assert_eq!(objects.len(), 2);
+        let maps = find_previous_backups_strict(&root.join("backup").to_str().unwrap().to_string()).unwrap();
+        let files: Vec<_> = maps[0].backup_dirs.iter().flat_map(|x| x.backup_entries.iter()).filter(|x| x.is_file).collect();
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().all(|x| Path::new(&x.output_path).exists()));
+        fs::remove_dir_all(root).unwrap();
+    }
+}
+
+/// Backup mode that stores content of files in object folder, with every file named by its hash.
+///
+/// Every backup is just a map pointing to objects, so identical files (from different input folders, or from different backups) are stored only once.
+pub struct BackupDedup {
+    pub map: BackupMap,
+    previous_maps: Vec<BackupMap>,
+    objects_folder: String,
+}
+
+impl BackupDedup {
+    /// Creates new BackupDedup struct, requires already created BackupMap with filled basic informations like input folders and output folder.
+    ///
+    /// May panic if data have not been filled, or if map's mode isn't dedup.
+    pub fn new(map: BackupMap) -> BackupDedup {
+        if map.output_folder.is_empty() || map.input_folders.is_empty() {
+            panic!("Not all needed data filled. Program will stop");
+        }
+        match map.backup_mode {
+            BackupMode::Dedup => {}
+            _ => panic!("Mode of created map isn't dedup mode, but dedup mode is trying to be executed. Program will stop")
+        }
+        let objects_folder = format!("{}{}{}", &map.output_folder, S_SEPARATOR, S_OBJECTS);
+        BackupDedup { map, previous_maps: vec![], objects_folder }
+    }
+
+    /// Finds previous backups, deletes oldest ones until their amount is less than maximum, removes objects that are no longer used, then creates folder for map of new backup.
+    ///
+    /// Returns error if output folder can't be created or searched.
+    fn create_new_backup_folder(&mut self) -> Result<(), String> {
+        if let Err(e) = create_dir_all(&self.objects_folder) {
+            return Err(format!("Can't create folder for objects {}: {}", &self.objects_folder, e));
+        }
+        self.previous_maps = find_previous_backups_strict(&self.map.output_folder)?.into_iter().filter(|x| x.backup_mode == BackupMode::Dedup).collect();
+        println!("Found {} previous backups", self.previous_maps.len());
+
+        // Deleting backups until it's less than maximum amount, then deleting their objects
+        let mut deleted: usize = 0;
+        self.previous_maps.sort_by_key(|x| x.timestamp);
+        while !self.previous_maps.is_empty() && self.previous_maps.len() >= self.map.max_backups {
+            let oldest = self.previous_maps.remove(0);
+            println!("Max amount of backups reached, deleting oldest one: {}", &oldest.output_folder);
+            if let Err(e) = remove_dir_all(&oldest.output_folder) {
+                return Err(format!("Error removing oldest backup {}: {}", &oldest.output_folder, e));
+            }
+            deleted += 1;
+        }
+        if deleted > 0 {
+            match collect_garbage(&self.objects_folder, &self.previous_maps) {
+                Ok(removed) => println!("Removed {} objects no longer used by any backup", removed),
+                Err(e) => println!("Couldn't remove unused objects: {}", e),
+            }
+        }
+
+        // Creating new backup folder
+        let date_string = Local::now().format("%d-%m-%Y %H_%M_%S").to_string();
+        let new_backup_path = format!("{}{}{}", self.map.output_folder, S_SEPARATOR, date_string);
+        if let Err(e) = create_dir_all(&new_backup_path) {
+            return Err(format!("Folder {} for new backup can't be created: {}", &new_backup_path, e));
+        }
+        self.map.output_folder = new_backup_path;
+        Ok(())
+    }
+}
+
+/// Returns path of object with provided hash - objects are divided into subfolders named by first two characters of hash, to avoid too many files in one folder.
+///
+/// Name of object is made of next 64 characters of hash, because full hash is longer than maximum length of file name on most file systems, and there must be space left for extension of compressed objects.
+pub fn object_path(objects_folder: &str, hash: &str) -> String {
+    let split = hash.char_indices().nth(2).map(|(i, _)| i).unwrap_or(hash.len());
+    let (prefix, rest) = hash.split_at(split);
+    let rest: String = rest.chars().take(64).collect();
+    format!("{}{}{}{}{}", objects_folder, S_SEPARATOR, prefix, S_SEPARATOR, rest)
+}
+
+/// Returns dirs with only those files whose objects don't exist yet - every object is present only once, even if many files have the same hash.
+///
+/// Objects are identified by their output path, not only hash, as files with the same content may be compressed differently depending on their extension.
+///
+/// Object is considered existing if its size is the same as size of source file, so objects left incomplete by interrupted backup are copied again.
+/// Compressed and encrypted objects are renamed to their final path only after compression is finished, so it's enough that they exist.
+pub fn generate_objects_to_copy(dirs: &[BackupDir], objects_folder: &str) -> Vec<BackupDir> {
+    let mut seen = HashSet::new();
+    let mut to_copy = vec![];
+    for dir in dirs {
+        let mut copy_dir = BackupDir { root_input: dir.root_input.clone(), root_output: String::from(objects_folder), files: 0, folders: 0, backup_entries: vec![] };
+        for entry in dir.backup_entries.iter().filter(|x| x.is_file) {
+            if !seen.insert(entry.output_path.clone()) {
+                continue;
+            }
+            let stored_size = metadata(&entry.output_path).map(|x| x.len()).ok();
+            let source_size = metadata(&entry.input_path).map(|x| x.len()).ok();
+            if stored_size.is_none() || (entry.compression == CompressionCodec::None && entry.encryption == EncryptionCipher::None && stored_size != source_size) {
+                copy_dir.backup_entries.push(entry.clone());
+            }
+        }
+        copy_dir.files = copy_dir.backup_entries.len();
+        if !copy_dir.backup_entries.is_empty() {
+            to_copy.push(copy_dir);
+        }
+    }
+    to_copy
+}
+
+/// Removes every object (or chunk, if provided folder is chunks folder) that isn't used by any of provided maps, with its parity file, then removes empty subfolders of objects folder.
+///
+/// Returns number of removed objects, or error if objects folder doesn't exist.
+pub fn collect_garbage(objects_folder: &str, maps: &[BackupMap]) -> Result<usize, String> {
+    if !Path::new(objects_folder).is_dir() {
+        return Err(format!("Objects folder {} doesn't exist", objects_folder));
+    }
+    let used: HashSet<String> = maps.iter()
+        .flat_map(|x| x.backup_dirs.iter())
+        .flat_map(|x| x.backup_entries.iter())
+        .filter(|x| x.is_file)
+        .flat_map(stored_paths)
+        .flat_map(|x| vec![parity_path(&x), x])
+        .collect();
+
+    let mut removed: usize = 0;
+    for entry in WalkDir::new(objects_folder).contents_first(true).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.is_file() {
+            if !used.contains(path.to_str().unwrap_or("")) {
+                match remove_file(path) {
+                    Ok(_) => removed += 1,
+                    Err(e) => println!("Can't remove unused object {}: {}", path.display(), e),
+                }
+            }
+        } else if path != Path::new(objects_folder) {
+            // Fails for non-empty folders, which is expected
+            let _ = remove_dir(path);
+        }
+    }
+    Ok(removed)
+}
+
+impl BackupCopy for BackupDedup {}
+impl BackupIgnore for BackupDedup {}
+impl BackupInput for BackupDedup {}
+impl BackupSerialize for BackupDedup {}
+
+impl BackupOutput for BackupDedup {
+    /// Creates output map - output path of every file is path of object with its hash, in objects folder placed next to folder of backup.
+    ///
+    /// Objects of encrypted backups are named by HMAC of hash with key saved in map instead, so their names don't reveal content of files.
+    ///
+    /// Folders don't have objects, so their output paths are only informational, based on root output folder.
+    ///
+    /// May panic if root output folder is empty, prints to user any other possible, application non-breaking error.
+    fn create_output_map(mut map: BackupMap) -> BackupMap {
+        if map.output_folder.is_empty() {
+            panic!("Root output folder isn't set up. Program will stop");
+        }
+        let objects_folder = match Path::new(&map.output_folder).parent().and_then(|x| x.to_str()) {
+            Some(parent) => format!("{}{}{}", parent, S_SEPARATOR, S_OBJECTS),
+            None => panic!("Folder of backup doesn't have parent folder for objects. Program will stop"),
+        };
+
+        for dir in &mut map.backup_dirs {
+            match get_last_subdir(&dir.root_input) {
+                Err(e) => println!("Can't create output folder to backup {}: {}, skipping...", &dir.root_input, e),
+                Ok(last_subdir) => {
+                    dir.root_output = format!("{}{}{}", &map.output_folder, S_SEPARATOR, last_subdir);
+                    for entry in &mut dir.backup_entries {
+                        entry.output_path = match entry.is_file {
+                            true if map.encryption != EncryptionCipher::None => object_path(&objects_folder, &obfuscated_name(&map.name_key, &entry.hash)),
+                            true => object_path(&objects_folder, &entry.hash),
+                            false => entry.input_path.replacen(&dir.root_input, &dir.root_output, 1),
+                        };
+                    }
+                }
+            }
+        }
+        map
+    }
+}
+
+impl Backup for BackupDedup {
+    /// Main function of BackupDedup, that runs all corresponding functions.
+    ///
+    /// First it creates folder for new backup, deleting oldest backups and unused objects before that. Then it creates input maps, ignores provided files and folders and creates output maps.
+    /// Then every file whose object doesn't exist yet is copied to objects folder and verified, and map is serialized to folder of new backup.
+    ///
+    /// Function may panic if required variables are empty, or if functions in traits panic. Every non-panic error is printed to user.
+    fn backup(&mut self) -> Result<(), String> {
+        if self.map.output_folder.is_empty() || self.map.input_folders.is_empty() || self.map.max_backups == 0 {
+            panic!("Trying to backup in dedup mode, but basic metadata is not filled. Program will stop");
+        }
+        if let Err(e) = self.create_new_backup_folder() {
+            panic!("Couldn't create new backup folder: {}. Program will stop", e);
+        }
+
+        // Input maps may be already created, when the same backup is copied to many output folders
+        if self.map.backup_dirs.is_empty() {
+            self.map.backup_dirs = BackupDedup::create_input_maps_with_hashes(&self.map, &self.previous_maps.iter().collect::<Vec<_>>());
+        }
+        let map_copy = self.map.clone();
+        self.map.backup_dirs = BackupDedup::ignore_files_and_folders_parrarel(map_copy.backup_dirs, &map_copy.ignore_extensions, &map_copy.ignore_folders);
+        // Objects are shared by all backups, so they keep their names only with the same key
+        assign_name_key(&mut self.map, &self.previous_maps);
+        let map_copy = self.map.clone();
+        self.map = BackupDedup::create_output_map(map_copy);
+        assign_compression(&mut self.map.backup_dirs, &self.map.compression);
+        assign_encryption(&mut self.map.backup_dirs, &self.map.encryption);
+        assign_parity(&mut self.map.backup_dirs, self.map.parity);
+
+        // Copying only new objects
+        let to_copy = generate_objects_to_copy(&self.map.backup_dirs, &self.objects_folder);
+        let expected: HashSet<String> = to_copy.iter().flat_map(|x| x.backup_entries.iter()).map(|x| x.output_path.clone()).collect();
+        println!("{} new objects to copy", expected.len());
+        let copied = BackupDedup::copy_all(to_copy);
+        for dir in &copied {
+            if let Err(e) = verify_one_folder(dir) {
+                println!("Error while verifying integrity of copied objects: {}", e);
+            }
+        }
+
+        // Removing from map files whose objects weren't copied
+        let copied: HashSet<&str> = copied.iter().flat_map(|x| x.backup_entries.iter()).map(|x| x.output_path.as_str()).collect();
+        for dir in &mut self.map.backup_dirs {
+            dir.backup_entries.retain(|x| !x.is_file || ((!expected.contains(&x.output_path) || copied.contains(x.output_path.as_str())) && Path::new(&x.output_path).exists()));
+            dir.files = dir.backup_entries.iter().filter(|x| x.is_file).count();
+            dir.folders = dir.backup_entries.iter().filter(|x| !x.is_file).count();
+        }
+
+        if let Err(e) = BackupDedup::serialize_to_json(&mut self.map) {
+            println!("Map couldn't be saved to file, this backup won't be considered next time: {}", e);
+        }
+        Ok(())
+    }
+}
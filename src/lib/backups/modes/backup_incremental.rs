@@ -0,0 +1,480 @@
+use crate::backups::traits::backup::Backup;
+use crate::backups::traits::backup_copy::{BackupCopy, create_folder, create_parent_folder};
+use crate::backups::traits::backup_ignore::BackupIgnore;
+use crate::backups::traits::backup_input::BackupInput;
+use crate::backups::traits::backup_serialize::{BackupSerialize, save_map_to_json};
+use crate::backups::traits::backup_output::BackupOutput;
+use crate::backups::map::backup_map::BackupMap;
+use crate::backups::map::backup_mode::BackupMode;
+use crate::backups::map::backup_dir::BackupDir;
+use crate::backups::map::backup_entry::BackupEntry;
+use crate::backups::helpers::dirs::{get_last_subdir, find_previous_backups_strict};
+use crate::backups::helpers::compression::assign_compression;
+use crate::backups::helpers::encryption::{assign_encryption, assign_name_key};
+use crate::backups::helpers::parity::{assign_parity, parity_path};
+use crate::S_SEPARATOR;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use chrono::Local;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backups::map::encryption_cipher::EncryptionCipher;
+    use crate::backups::map::map_format::MapFormat;
+    use uuid::Uuid;
+
+    fn entry(path: &str, output: &str, hash: &str) -> BackupEntry {
+        BackupEntry { input_path: format!("/home/user/{}", path), output_path: String::from(output), is_file: true, hash: String::from(hash), ..BackupEntry::new() }
+    }
+
+    fn map_with_entries(id: &str, parent_id: &str, entries: Vec<BackupEntry>, deleted: &[&str]) -> BackupMap {
+        let mut dir = BackupDir::new();
+        dir.root_input = String::from("/home/user");
+        dir.backup_entries = entries;
+        let mut map = BackupMap::new(BackupMode::Incremental);
+        map.id = String::from(id);
+        map.parent_id = String::from(parent_id);
+        map.input_folders = vec![String::from("/home/user")];
+        map.deleted = deleted.iter().map(|x| format!("/home/user/{}", x)).collect();
+        map.backup_dirs = vec![dir];
+        map
+    }
+
+    #[test]
+    fn test_resolve_chain() {
+        let full = map_with_entries("1", "", vec![entry("a.txt", "/1/a.txt", "a"), entry("b.txt", "/1/b.txt", "b")], &[]);
+        let first = map_with_entries("2", "1", vec![entry("a.txt", "/2/a.txt", "a2")], &[]);
+        let second = map_with_entries("3", "2", vec![entry("c.txt", "/3/c.txt", "c")], &["b.txt"]);
+        let maps = vec![full, first, second.clone()];
+
+        let resolved = resolve_chain(&second, &maps).unwrap();
+        let outputs: Vec<&str> = resolved.backup_dirs[0].backup_entries.iter().map(|x| x.output_path.as_str()).collect();
+        assert_eq!(outputs, vec!["/2/a.txt", "/3/c.txt"]);
+        assert_eq!(resolved.id, "3");
+        assert_eq!(resolved.files, 2);
+
+        // Settings are taken from resolved backup, not from full one
+        let encrypted = BackupMap { encryption: EncryptionCipher::ChaCha20Poly1305, map_format: MapFormat::Cbor, name_key: String::from("key"), parity: 10, ..second.clone() };
+        let resolved = resolve_chain(&encrypted, &maps).unwrap();
+        assert_eq!(resolved.encryption, EncryptionCipher::ChaCha20Poly1305);
+        assert_eq!(resolved.map_format, MapFormat::Cbor);
+        assert_eq!(resolved.name_key, "key");
+        assert_eq!(resolved.parity, 10);
+        assert!(resolve_chain(&second, &maps[1..]).is_err());
+    }
+
+    #[test]
+    fn test_generate_increment() {
+        let previous = map_with_entries("1", "", vec![entry("same.txt", "", "1"), entry("modified.txt", "", "2"), entry("removed.txt", "", "3")], &[]);
+        let current = map_with_entries("2", "", vec![entry("same.txt", "", "1"), entry("modified.txt", "", "4"), entry("added.txt", "", "5")], &[]);
+        let (changed, deleted) = generate_increment(&current.backup_dirs, &previous);
+        let changed: Vec<&str> = changed[0].backup_entries.iter().map(|x| x.input_path.as_str()).collect();
+        assert_eq!(changed, vec!["/home/user/modified.txt", "/home/user/added.txt"]);
+        assert_eq!(deleted, vec!["/home/user/removed.txt"]);
+    }
+
+    #[test]
+    fn test_consolidate_oldest_keeps_oldest_on_error() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        let path = |name: &str| root.join(name).to_str().unwrap().to_string();
+        fs::create_dir_all(root.join("1")).unwrap();
+        fs::create_dir_all(root.join("2")).unwrap();
+        fs::write(path("1/a.txt"), "a").unwrap();
+        let mut oldest = map_with_entries("1", "", vec![entry("a.txt", &path("1/a.txt"), "a"), entry("b.txt", &path("1/b.txt"), "b")], &[]);
+        oldest.output_folder = path("1");
+        oldest.timestamp = 1;
+        let mut child = map_with_entries("2", "1", vec![], &[]);
+        child.output_folder = path("2");
+        child.timestamp = 2;
+
+        // File b.txt is missing, so backup can't be folded and oldest one is kept
+        let mut maps = vec![oldest.clone(), child.clone()];
+        assert!(consolidate_oldest(&mut maps).is_err());
+        assert_eq!(maps.len(), 2);
+        assert!(root.join("1").join("a.txt").exists());
+        assert!(!root.join("2").join("a.txt").exists());
+
+        fs::write(path("1/b.txt"), "b").unwrap();
+        consolidate_oldest(&mut maps).unwrap();
+        assert_eq!(maps.len(), 1);
+        assert!(!root.join("1").exists());
+        assert_eq!(fs::read_to_string(path("2/a.txt")).unwrap(), "a");
+        assert!(crate::backups::helpers::dirs::load_map(&root.join("2").join(crate::S_MAP)).unwrap().parent_id.is_empty());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_backup() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        let source = root.join("source");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("a.txt"), "first").unwrap();
+        fs::write(source.join("b.txt"), "unchanged").unwrap();
+        let mut map = BackupMap::new(BackupMode::Incremental);
+        map.input_folders = vec![source.to_str().unwrap().to_string()];
+        map.output_folder = root.join("backup").to_str().unwrap().to_string();
+        map.max_backups = 2;
+
+        for content in &["second", "third"] {
+            BackupIncremental::new(map.clone()).backup().unwrap();
+            std::thread::sleep(std::time::Duration::from_secs(1));
+            fs::write(source.join("a.txt"), content).unwrap();
+        }
+        BackupIncremental::new(map.clone()).backup().unwrap();
+
+        let mut maps = find_previous_backups_strict(&map.output_folder).unwrap();
+        maps.sort_by_key(|x| x.timestamp);
+        assert_eq!(maps.len(), 2);
+        assert!(maps[0].parent_id.is_empty());
+        assert_eq!(maps[1].parent_id, maps[0].id);
+        assert_eq!(maps[1].files, 1);
+        let resolved = resolve_chain(&maps[1], &maps).unwrap();
+        for entry in resolved.backup_dirs[0].backup_entries.iter().filter(|x| x.is_file) {
+            assert_eq!(fs::read(&entry.output_path).unwrap(), fs::read(&entry.input_path).unwrap());
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+}
+
+/// Backup mode where first backup is full copy, and every next one contains only files changed since previous backup, with id of previous backup as parent.
+///
+/// Complete content of backup is created by resolving chain of its parents, from full backup to chosen one.
+pub struct BackupIncremental {
+    pub map: BackupMap,
+    previous_maps: Vec<BackupMap>,
+}
+
+impl BackupIncremental {
+    /// Creates new BackupIncremental struct, requires already created BackupMap with filled basic informations like input folders and output folder.
+    ///
+    /// May panic if data have not been filled, or if map's mode isn't incremental.
+    pub fn new(map: BackupMap) -> BackupIncremental {
+        if map.output_folder.is_empty() || map.input_folders.is_empty() {
+            panic!("Not all needed data filled. Program will stop");
+        }
+        match map.backup_mode {
+            BackupMode::Incremental => {}
+            _ => panic!("Mode of created map isn't incremental mode, but incremental mode is trying to be executed. Program will stop")
+        }
+        BackupIncremental { map, previous_maps: vec![] }
+    }
+
+    /// Finds previous backups, folds oldest ones into their successors until their amount is less than maximum, then creates folder for new backup.
+    ///
+    /// Returns error if output folder can't be created or searched, or if oldest backup can't be folded.
+    fn create_new_backup_folder(&mut self) -> Result<(), String> {
+        if let Err(e) = fs::create_dir_all(&self.map.output_folder) {
+            return Err(format!("Can't create output folder {}: {}", &self.map.output_folder, e));
+        }
+        self.previous_maps = find_previous_backups_strict(&self.map.output_folder)?.into_iter().filter(|x| x.backup_mode == BackupMode::Incremental).collect();
+        println!("Found {} previous backups", self.previous_maps.len());
+
+        self.previous_maps.sort_by_key(|x| x.timestamp);
+        while !self.previous_maps.is_empty() && self.previous_maps.len() >= self.map.max_backups {
+            println!("Max amount of backups reached, removing oldest one: {}", &self.previous_maps[0].output_folder);
+            consolidate_oldest(&mut self.previous_maps)?;
+        }
+
+        let date_string = Local::now().format("%d-%m-%Y %H_%M_%S").to_string();
+        let new_backup_path = format!("{}{}{}", self.map.output_folder, S_SEPARATOR, date_string);
+        if let Err(e) = fs::create_dir_all(&new_backup_path) {
+            return Err(format!("Folder {} for new backup can't be created: {}", &new_backup_path, e));
+        }
+        self.map.output_folder = new_backup_path;
+        Ok(())
+    }
+}
+
+/// Returns map with complete content of backup, created by applying every backup in chain, from full backup to provided one.
+///
+/// Returned map has metadata and settings (like cipher, codec or format of map) of provided backup, and every entry points to the newest backup in chain that contains it. Folders that aren't input folders of provided backup are skipped.
+///
+/// Returns error if any backup in chain can't be found in provided maps.
+pub fn resolve_chain(map: &BackupMap, maps: &[BackupMap]) -> Result<BackupMap, String> {
+    let mut chain = vec![map];
+    while !chain[chain.len() - 1].parent_id.is_empty() {
+        let parent_id = &chain[chain.len() - 1].parent_id;
+        match maps.iter().find(|x| &x.id == parent_id) {
+            Some(_) if chain.len() > maps.len() => return Err(format!("Chain of backup {} contains a loop", &map.id)),
+            Some(parent) => chain.push(parent),
+            None => return Err(format!("Backup {} needed to restore backup {} is missing", parent_id, &map.id)),
+        }
+    }
+
+    let mut dirs = chain.pop().unwrap().backup_dirs.clone();
+    while let Some(increment) = chain.pop() {
+        apply_increment(&mut dirs, increment);
+    }
+    let mut state = BackupMap { backup_dirs: dirs, ..map.clone() };
+    state.backup_dirs.retain(|x| map.input_folders.contains(&x.root_input));
+    for dir in &mut state.backup_dirs {
+        dir.files = dir.backup_entries.iter().filter(|x| x.is_file).count();
+        dir.folders = dir.backup_entries.iter().filter(|x| !x.is_file).count();
+    }
+    state.files = state.backup_dirs.iter().map(|x| x.files).sum();
+    state.folders = state.backup_dirs.iter().map(|x| x.folders).sum();
+    state.parent_id = String::new();
+    state.deleted = vec![];
+    Ok(state)
+}
+
+/// Returns complete content of backup for maps of Incremental mode, maps of other modes are returned unchanged.
+///
+/// Returns error if chain of backup can't be resolved.
+pub fn resolve_map(map: BackupMap, maps: &[BackupMap]) -> Result<BackupMap, String> {
+    match map.backup_mode {
+        BackupMode::Incremental => resolve_chain(&map, maps),
+        _ => Ok(map),
+    }
+}
+
+/// Applies one backup from chain to dirs with content of previous backups - deleted entries are removed, new or modified entries replace existing ones.
+fn apply_increment(dirs: &mut Vec<BackupDir>, increment: &BackupMap) {
+    let deleted: HashSet<&str> = increment.deleted.iter().map(|x| x.as_str()).collect();
+    for dir in dirs.iter_mut() {
+        dir.backup_entries.retain(|x| !deleted.contains(x.input_path.as_str()));
+    }
+
+    for increment_dir in &increment.backup_dirs {
+        match dirs.iter_mut().find(|x| x.root_input == increment_dir.root_input) {
+            None => dirs.push(increment_dir.clone()),
+            Some(dir) => {
+                let mut positions: HashMap<String, usize> = dir.backup_entries.iter().enumerate().map(|(i, x)| (x.input_path.clone(), i)).collect();
+                for entry in &increment_dir.backup_entries {
+                    match positions.get(&entry.input_path) {
+                        Some(position) => dir.backup_entries[*position] = entry.clone(),
+                        None => {
+                            positions.insert(entry.input_path.clone(), dir.backup_entries.len());
+                            dir.backup_entries.push(entry.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Searches for entries changed since previous backup - file is changed if previous backup doesn't contain file with the same path and hash, folder is changed if it's new.
+///
+/// Returns tuple of dirs with only changed entries, and input paths of entries present in previous backup, but not in current folders.
+pub fn generate_increment(dirs: &[BackupDir], previous: &BackupMap) -> (Vec<BackupDir>, Vec<String>) {
+    println!("Looking for new or modified files and folders...");
+    let mut changed = vec![];
+    for dir in dirs {
+        let previous_entries: HashMap<&str, &BackupEntry> = previous.backup_dirs.iter()
+            .filter(|x| x.root_input == dir.root_input)
+            .flat_map(|x| x.backup_entries.iter())
+            .map(|x| (x.input_path.as_str(), x))
+            .collect();
+        let mut changed_dir = BackupDir { root_input: dir.root_input.clone(), root_output: dir.root_output.clone(), files: 0, folders: 0, backup_entries: vec![] };
+        for entry in &dir.backup_entries {
+            match previous_entries.get(entry.input_path.as_str()) {
+                Some(previous_entry) if previous_entry.is_file == entry.is_file && previous_entry.hash == entry.hash => {}
+                _ => changed_dir.backup_entries.push(entry.clone()),
+            }
+        }
+        changed_dir.files = changed_dir.backup_entries.iter().filter(|x| x.is_file).count();
+        changed_dir.folders = changed_dir.backup_entries.iter().filter(|x| !x.is_file).count();
+        match changed_dir.backup_entries.is_empty() {
+            true => println!("No new files found in {}", &dir.root_input),
+            false => {
+                println!("{} new or modified files found in {}", changed_dir.files, &dir.root_input);
+                changed.push(changed_dir);
+            }
+        }
+    }
+
+    let current: HashSet<&str> = dirs.iter().flat_map(|x| x.backup_entries.iter()).map(|x| x.input_path.as_str()).collect();
+    let deleted: Vec<String> = previous.backup_dirs.iter()
+        .flat_map(|x| x.backup_entries.iter())
+        .filter(|x| !current.contains(x.input_path.as_str()))
+        .map(|x| x.input_path.clone())
+        .collect();
+    if !deleted.is_empty() {
+        println!("{} files and folders deleted since previous backup", deleted.len());
+    }
+    (changed, deleted)
+}
+
+/// Removes oldest of provided backups, sorted from oldest to newest, without breaking chain.
+///
+/// If other backup has oldest one as parent, content of both is folded into new full backup - files still used are linked (or copied) to folder of next backup, and its map is saved without parent.
+/// Folder of oldest backup is deleted only after every file is placed in folder of next backup and its map is saved, so interrupted or failed folding never loses files needed by chain.
+///
+/// Returns error if next backup can't be resolved, any file can't be placed in folder of next backup, its map can't be saved or oldest backup can't be deleted. Oldest backup is kept in every case except the last one.
+pub fn consolidate_oldest(maps: &mut Vec<BackupMap>) -> Result<(), String> {
+    let oldest = maps.remove(0);
+    if let Some(child_index) = maps.iter().position(|x| x.parent_id == oldest.id) {
+        println!("Folding backup {} into next backup {}...", &oldest.id, &maps[child_index].id);
+        let folded = match fold_into_child(&oldest, &maps[child_index]) {
+            Ok(folded) => folded,
+            Err(e) => {
+                maps.insert(0, oldest);
+                return Err(e);
+            }
+        };
+        maps[child_index] = folded;
+    }
+
+    if let Err(e) = fs::remove_dir_all(&oldest.output_folder) {
+        return Err(format!("Error removing oldest backup {}: {}", &oldest.output_folder, e));
+    }
+    Ok(())
+}
+
+/// Places every file of oldest backup still used by its child in folder of child, and saves map of child as full backup. Files of oldest backup are left untouched.
+///
+/// If any file can't be placed, files already placed are removed and map of child isn't changed.
+///
+/// Returns folded map of child, or error if chain can't be resolved, any file can't be placed or map can't be saved.
+fn fold_into_child(oldest: &BackupMap, child: &BackupMap) -> Result<BackupMap, String> {
+    let mut folded = resolve_chain(child, std::slice::from_ref(oldest))?;
+    let oldest_prefix = format!("{}{}", &oldest.output_folder, S_SEPARATOR);
+    let child_prefix = format!("{}{}", &folded.output_folder, S_SEPARATOR);
+    let mut placed = vec![];
+    let mut errors = vec![];
+    for dir in &mut folded.backup_dirs {
+        if dir.root_output.starts_with(&oldest_prefix) {
+            dir.root_output = dir.root_output.replacen(&oldest_prefix, &child_prefix, 1);
+        }
+        for entry in dir.backup_entries.iter_mut().filter(|x| x.output_path.starts_with(&oldest_prefix)) {
+            let new_path = entry.output_path.replacen(&oldest_prefix, &child_prefix, 1);
+            match place_entry(entry, &new_path) {
+                Ok(_) => {
+                    if entry.is_file {
+                        placed.push(new_path.clone());
+                    }
+                    entry.output_path = new_path;
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+    }
+    if errors.is_empty() {
+        if let Err(e) = save_map_to_json(&mut folded) {
+            errors.push(e);
+        }
+    }
+    if !errors.is_empty() {
+        for path in &placed {
+            let _ = fs::remove_file(path);
+            let _ = fs::remove_file(parity_path(path));
+        }
+        return Err(format!("Can't fold backup {} into next backup, it will be kept: {}", &oldest.id, errors.join(", ")));
+    }
+    Ok(folded)
+}
+
+/// Places file of entry (with its parity file, if it exists) at new path as hard link, or copy if it can't be linked (for example between disks), or creates folder at new path. File at old path is left untouched.
+///
+/// Returns error if folder can't be created or file can't be linked or copied.
+fn place_entry(entry: &BackupEntry, new_path: &str) -> Result<(), String> {
+    let new_path = String::from(new_path);
+    if !entry.is_file {
+        return create_folder(&new_path);
+    }
+    create_parent_folder(&new_path)?;
+    if fs::hard_link(&entry.output_path, &new_path).is_err() {
+        if let Err(e) = fs::copy(&entry.output_path, &new_path) {
+            return Err(format!("Can't copy file {} to {}: {}", &entry.output_path, &new_path, e));
+        }
+    }
+    if Path::new(&parity_path(&entry.output_path)).exists() && fs::hard_link(parity_path(&entry.output_path), parity_path(&new_path)).is_err() {
+        if let Err(e) = fs::copy(parity_path(&entry.output_path), parity_path(&new_path)) {
+            println!("Can't copy parity of {}: {}, it will be created again", &entry.output_path, e);
+        }
+    }
+    Ok(())
+}
+
+impl BackupCopy for BackupIncremental {}
+impl BackupIgnore for BackupIncremental {}
+impl BackupInput for BackupIncremental {}
+impl BackupSerialize for BackupIncremental {}
+
+impl BackupOutput for BackupIncremental {
+    /// Creates output map - for each entry, output folder is changed to one based on root output folder, created in backup() method.
+    ///
+    /// May panic if root output folder is empty, prints to user any other possible, application non-breaking error.
+    fn create_output_map(mut map: BackupMap) -> BackupMap {
+        if map.output_folder.is_empty() {
+            panic!("Root output folder isn't set up. Program will stop");
+        }
+        for dir in &mut map.backup_dirs {
+            match get_last_subdir(&dir.root_input) {
+                Err(e) => println!("Can't create output folder to backup {}: {}, skipping...", &dir.root_input, e),
+                Ok(last_subdir) => {
+                    dir.root_output = format!("{}{}{}", &map.output_folder, S_SEPARATOR, last_subdir);
+                    for entry in &mut dir.backup_entries {
+                        entry.output_path = entry.input_path.replacen(&dir.root_input, &dir.root_output, 1);
+                    }
+                }
+            }
+        }
+        map
+    }
+}
+
+impl Backup for BackupIncremental {
+    /// Main function of BackupIncremental, that runs all corresponding functions.
+    ///
+    /// First it creates folder for new backup, folding oldest backups before that. Then it creates input maps, ignores provided files and folders and creates output maps.
+    /// If previous backup exists, only entries changed since it are kept in map, with deleted entries and id of previous backup as parent. Otherwise full backup is created.
+    /// Then entries are copied, map is serialized and files are verified.
+    ///
+    /// Function may panic if required variables are empty, or if functions in traits panic. Every non-panic error is printed to user.
+    fn backup(&mut self) -> Result<(), String> {
+        if self.map.output_folder.is_empty() || self.map.input_folders.is_empty() || self.map.max_backups == 0 {
+            panic!("Trying to backup in incremental mode, but basic metadata is not filled. Program will stop");
+        }
+        if let Err(e) = self.create_new_backup_folder() {
+            panic!("Couldn't create new backup folder: {}. Program will stop", e);
+        }
+
+        // Input maps may be already created, when the same backup is copied to many output folders
+        if self.map.backup_dirs.is_empty() {
+            self.map.backup_dirs = BackupIncremental::create_input_maps_with_hashes(&self.map, &self.previous_maps.iter().collect::<Vec<_>>());
+        }
+        let map_copy = self.map.clone();
+        self.map.backup_dirs = BackupIncremental::ignore_files_and_folders_parrarel(map_copy.backup_dirs, &map_copy.ignore_extensions, &map_copy.ignore_folders);
+        let map_copy = self.map.clone();
+        self.map = BackupIncremental::create_output_map(map_copy);
+        assign_compression(&mut self.map.backup_dirs, &self.map.compression);
+        assign_encryption(&mut self.map.backup_dirs, &self.map.encryption);
+        assign_parity(&mut self.map.backup_dirs, self.map.parity);
+        // Files keep their names across whole chain only with the same key
+        assign_name_key(&mut self.map, &self.previous_maps);
+        if self.map.obfuscate_names {
+            BackupIncremental::obfuscate_output_map(&mut self.map);
+        }
+
+        // Keeping only entries changed since previous backup
+        match self.previous_maps.last() {
+            None => println!("No previous backup found, creating full backup"),
+            Some(parent) => match resolve_chain(parent, &self.previous_maps) {
+                Err(e) => println!("Can't read chain of previous backups: {}, creating full backup", e),
+                Ok(previous_state) => {
+                    let (changed, deleted) = generate_increment(&self.map.backup_dirs, &previous_state);
+                    self.map.backup_dirs = changed;
+                    self.map.deleted = deleted;
+                    self.map.parent_id = parent.id.clone();
+                }
+            }
+        }
+
+        let to_copy = self.map.backup_dirs.clone().into_iter().filter(|x| !x.backup_entries.is_empty()).collect();
+        let copied = BackupIncremental::copy_all(to_copy);
+        self.map.backup_dirs = BackupIncremental::delete_non_existing(copied);
+        if let Err(e) = BackupIncremental::serialize_to_json(&mut self.map) {
+            println!("Map couldn't be saved to file, this backup won't be considered next time: {}", e);
+        }
+        if !self.map.backup_dirs.is_empty() {
+            if let Err(e) = BackupIncremental::verify_all(&self.map) {
+                println!("Error while verifying integrity of copied files: {}", e);
+            }
+        }
+        Ok(())
+    }
+}
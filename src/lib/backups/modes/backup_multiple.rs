@@ -5,12 +5,26 @@ use crate::backups::traits::backup_input::BackupInput;
 use crate::backups::traits::backup_serialize::BackupSerialize;
 use crate::backups::traits::backup_output::BackupOutput;
 use crate::backups::map::backup_map::BackupMap;
-use crate::{S_SEPARATOR};
+use crate::{S_SEPARATOR, S_CHUNKS};
 use std::fs::{create_dir_all};
 use chrono::Local;
 use crate::backups::map::backup_mode::BackupMode;
 use std::path::Path;
-use crate::backups::helpers::dirs::{get_last_subdir, find_previous_backups};
+use crate::backups::helpers::dirs::{get_last_subdir, find_previous_backups, find_previous_backups_strict};
+use crate::backups::map::backup_dir::BackupDir;
+use crate::backups::map::backup_entry::BackupEntry;
+use crate::backups::map::archive_format::ArchiveFormat;
+use crate::backups::helpers::archive::{write_archive, embed_map_in_zip, archive_path};
+use crate::backups::helpers::compression::assign_compression;
+use crate::backups::helpers::encryption::{assign_encryption, assign_name_key};
+use crate::backups::helpers::chunking::assign_chunking;
+use crate::backups::helpers::parity::{assign_parity, create_parity, parity_path};
+use crate::backups::modes::backup_dedup::collect_garbage;
+use crate::backups::map::encryption_cipher::EncryptionCipher;
+use crate::backups::traits::backup_copy::{create_parent_folder, copy_entry};
+use crate::backups::helpers::volumes::{has_space_for, ask_for_volume};
+use std::collections::{HashMap, HashSet};
+use std::fs;
 
 #[cfg(test)]
 mod tests {
@@ -18,6 +32,103 @@ mod tests {
     use crate::backups::map::backup_mode::BackupMode;
     use crate::backups::modes::backup_multiple::BackupMultiple;
     use crate::backups::traits::backup::Backup;
+    use crate::backups::modes::backup_multiple::{link_unchanged_files, merge_linked_dirs, count_backups};
+    use crate::backups::traits::backup_serialize::save_map_to_json;
+    use crate::backups::map::backup_dir::BackupDir;
+    use crate::backups::map::backup_entry::BackupEntry;
+    use crate::backups::map::archive_format::ArchiveFormat;
+    use crate::backups::map::encryption_cipher::EncryptionCipher;
+    use crate::backups::map::compression_codec::CompressionCodec;
+    use std::fs;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_link_unchanged_files() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(root.join("old")).unwrap();
+        fs::write(root.join("old").join("same.txt"), "same").unwrap();
+        fs::write(root.join("old").join("changed.txt"), "old").unwrap();
+        let path = |name: &str| root.join(name).to_str().unwrap().to_string();
+
+        let mut previous_dir = BackupDir::new();
+        previous_dir.root_input = String::from("/home/user");
+        previous_dir.backup_entries.push(BackupEntry { input_path: String::from("/home/user/same.txt"), output_path: path("old/same.txt"), is_file: true, hash: String::from("1"), ..BackupEntry::new() });
+        previous_dir.backup_entries.push(BackupEntry { input_path: String::from("/home/user/changed.txt"), output_path: path("old/changed.txt"), is_file: true, hash: String::from("2"), ..BackupEntry::new() });
+        let mut previous_map = BackupMap::new(BackupMode::Multiple);
+        previous_map.backup_dirs = vec![previous_dir];
+
+        let mut dir = BackupDir::new();
+        dir.root_input = String::from("/home/user");
+        dir.backup_entries.push(BackupEntry { input_path: String::from("/home/user/same.txt"), output_path: path("new/same.txt"), is_file: true, hash: String::from("1"), ..BackupEntry::new() });
+        dir.backup_entries.push(BackupEntry { input_path: String::from("/home/user/changed.txt"), output_path: path("new/changed.txt"), is_file: true, hash: String::from("3"), ..BackupEntry::new() });
+
+        // Files can't be linked to backup stored in archive, or stored with different codec
+        let archived = BackupMap { archive: ArchiveFormat::Tar, ..previous_map.clone() };
+        let (to_copy, linked) = link_unchanged_files(vec![dir.clone()], &archived);
+        assert_eq!(to_copy[0].backup_entries.len(), 2);
+        assert!(linked.is_empty());
+        let mut compressed = dir.clone();
+        compressed.backup_entries[0].compression = CompressionCodec::Zstd;
+        let (to_copy, _) = link_unchanged_files(vec![compressed], &previous_map);
+        assert_eq!(to_copy[0].backup_entries.len(), 2);
+
+        let (to_copy, linked) = link_unchanged_files(vec![dir], &previous_map);
+        assert_eq!(to_copy[0].backup_entries.len(), 1);
+        assert_eq!(linked[0].backup_entries.len(), 1);
+        assert_eq!(fs::read_to_string(path("new/same.txt")).unwrap(), "same");
+
+        let merged = merge_linked_dirs(to_copy, linked);
+        assert_eq!(merged[0].backup_entries.len(), 2);
+        assert_eq!(merged[0].files, 2);
+
+        // Deleting previous backup can't remove linked file
+        fs::remove_dir_all(root.join("old")).unwrap();
+        assert_eq!(fs::read_to_string(path("new/same.txt")).unwrap(), "same");
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_spanned_retention() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        let path = |name: &str| root.join(name).to_str().unwrap().to_string();
+        let part = |id: &str, volume: usize, folder: &str| {
+            fs::create_dir_all(folder).unwrap();
+            let mut map = BackupMap { id: String::from(id), timestamp: 1, spanning: true, volume, last_volume: volume == 2, output_folder: String::from(folder), ..BackupMap::new(BackupMode::Multiple) };
+            save_map_to_json(&mut map).unwrap();
+            map
+        };
+        let first = part("old", 1, &path("v1/old"));
+        let second = part("old", 2, &path("v2/old"));
+        let other = BackupMap { id: String::from("other"), ..BackupMap::new(BackupMode::Multiple) };
+        assert_eq!(count_backups(&[first, second, other.clone(), other]), 3);
+
+        // Backup made on first volume removes all parts of the oldest one, also from second volume
+        let mut map = BackupMap::new(BackupMode::Multiple);
+        map.input_folders = vec![path("source")];
+        map.output_folder = path("v1");
+        map.volumes = vec![path("v2")];
+        map.spanning = true;
+        map.max_backups = 1;
+        let mut backup_multiple = BackupMultiple::new(map);
+        backup_multiple.create_new_backup_folder().unwrap();
+        assert!(!root.join("v1").join("old").exists());
+        assert!(!root.join("v2").join("old").exists());
+        assert!(backup_multiple.map.output_folder.starts_with(&path("v1")));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_encrypted_archive_rejected() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        let mut map = BackupMap::new(BackupMode::Multiple);
+        map.input_folders = vec![root.join("source").to_str().unwrap().to_string()];
+        map.output_folder = root.join("backup").to_str().unwrap().to_string();
+        map.max_backups = 1;
+        map.archive = ArchiveFormat::Tar;
+        map.encryption = EncryptionCipher::ChaCha20Poly1305;
+        assert!(BackupMultiple::new(map).backup().is_err());
+        assert!(!root.join("backup").exists());
+    }
 
     #[test]
     fn test_backup() {
@@ -38,6 +149,8 @@ mod tests {
 pub struct BackupMultiple {
     pub map: BackupMap,
     previous_maps: Vec<BackupMap>,
+    /// Root output folders of all known volumes, searched for other parts of spanned backup when it's deleted.
+    volume_roots: Vec<String>,
 }
 
 impl BackupMultiple {
@@ -52,7 +165,8 @@ impl BackupMultiple {
             BackupMode::Multiple => {}
             _ => panic!("Mode of created map isn't multiple mode, but multiple mode is trying to be executed. Program will stop")
         }
-        let backup_multiple = BackupMultiple { map, previous_maps: vec![] };
+        let volume_roots = std::iter::once(map.output_folder.clone()).chain(map.volumes.iter().cloned()).collect();
+        let backup_multiple = BackupMultiple { map, previous_maps: vec![], volume_roots };
         backup_multiple
     }
 
@@ -66,7 +180,7 @@ impl BackupMultiple {
         match Path::new(&self.map.output_folder).exists() {
             true => {
                 // Finding previous backups
-                match find_previous_backups(&self.map.output_folder) {
+                match find_previous_backups_strict(&self.map.output_folder) {
                     Ok(previous_backups) => {
                         self.previous_maps = previous_backups;
                         println!("Found {} previous backups", self.previous_maps.len());
@@ -81,7 +195,7 @@ impl BackupMultiple {
                     false => {
                         // Deleting backups until it's less than maximum amount
                         loop {
-                            if self.previous_maps.len() >= self.map.max_backups {
+                            if count_backups(&self.previous_maps) >= self.map.max_backups {
                                 if let Err(e) = self.delete_oldest_folder() {
                                     let message = format!("Error while removing oldest folder, program will stop: {}", e);
                                     panic!(message);
@@ -115,6 +229,8 @@ impl BackupMultiple {
     }
 
     /// Use after "find_previous_backups", helper to create_new_backup_folder
+    ///
+    /// All parts of spanned backup are deleted together - from this output folder and from every other known volume that is connected.
     fn delete_oldest_folder(&mut self) -> Result<(), String> {
         // Checking if value exists
         if self.previous_maps.is_empty() {
@@ -124,24 +240,52 @@ impl BackupMultiple {
         // Finding oldest backup
         let mut oldest_map_timestamp: usize = std::usize::MAX;
         let mut oldest_map_output_path = String::new();
+        let mut oldest_map_id = String::new();
+        let mut oldest_map_spanned = false;
 
         for previous_map in &self.previous_maps {
             if previous_map.timestamp < oldest_map_timestamp {
                 oldest_map_timestamp = previous_map.timestamp;
                 oldest_map_output_path = previous_map.output_folder.clone();
+                oldest_map_id = previous_map.id.clone();
+                oldest_map_spanned = previous_map.volume > 0;
+            }
+        }
+
+        // Other parts of spanned backup can't be restored without this one, so they are deleted too
+        if oldest_map_spanned {
+            let mut other_parts = HashSet::new();
+            for root in std::iter::once(&self.map.output_folder).chain(self.volume_roots.iter()).filter(|x| Path::new(x).is_dir()) {
+                if let Ok(maps) = find_previous_backups(root) {
+                    other_parts.extend(maps.into_iter().filter(|x| x.volume > 0 && x.id == oldest_map_id && x.output_folder != oldest_map_output_path).map(|x| x.output_folder));
+                }
+            }
+            for folder in other_parts {
+                println!("Deleting other part of oldest backup: {}", &folder);
+                if let Err(e) = std::fs::remove_dir_all(&folder) {
+                    println!("Can't remove other part of oldest backup {}: {}", &folder, e);
+                }
             }
         }
 
         println!("Max amount of backups reached, deleting oldest one: {}", &oldest_map_output_path);
         match std::fs::remove_dir_all(&oldest_map_output_path) {
             Ok(_) => {
-                match find_previous_backups(&self.map.output_folder) {
+                match find_previous_backups_strict(&self.map.output_folder) {
                     Ok(previous_maps) => self.previous_maps = previous_maps,
                     Err(e) => {
                         println!("Error: deleted folder is still on list, program thinks there are {} backups", self.previous_maps.len());
                         return Err(e);
                     }
                 }
+                // Removing chunks used only by deleted backup
+                let chunks_folder = format!("{}{}{}", &self.map.output_folder, S_SEPARATOR, S_CHUNKS);
+                if Path::new(&chunks_folder).is_dir() {
+                    match collect_garbage(&chunks_folder, &self.previous_maps) {
+                        Ok(removed) => println!("Removed {} chunks no longer used by any backup", removed),
+                        Err(e) => println!("Couldn't remove unused chunks: {}", e),
+                    }
+                }
                 Ok(())
             },
             Err(e) => {
@@ -150,6 +294,116 @@ impl BackupMultiple {
             }
         }
     }
+
+    /// Copies all entries of backup, filling one volume after another. When volume is full, map of part of backup copied to it is saved, and backup continues in new backup folder on next volume,
+    /// taken from configured volumes or asked from user.
+    ///
+    /// Every part gets the same id and number of its volume, part on the last volume is marked as last one. Files too big to fit on empty volume are skipped.
+    /// If there's no next volume, backup stops and already copied part is saved.
+    ///
+    /// Returns error if free space of volume can't be checked, map of any part can't be saved, or not all files were copied - then the last part isn't marked as last one, so backup is known to be incomplete.
+    fn copy_spanned(&mut self, mut copied: BackupMap) -> Result<(), String> {
+        copied.generate_metadata();
+        let first_folder = copied.output_folder.clone();
+        let dirs = std::mem::take(&mut copied.backup_dirs);
+        let mut volumes = copied.volumes.clone().into_iter();
+        let mut part = BackupMap { volume: 1, ..copied };
+        let mut part_dirs = empty_part_dirs(&dirs, &first_folder, &first_folder);
+        let mut part_files: usize = 0;
+        let mut skipped: usize = 0;
+        let mut stopped = false;
+
+        'dirs: for (index, dir) in dirs.iter().enumerate() {
+            println!("Copying folder {}...", &dir.root_input);
+            for entry in &dir.backup_entries {
+                let mut needed = 0;
+                if entry.is_file {
+                    let size = fs::metadata(&entry.input_path).map(|x| x.len()).unwrap_or(0);
+                    needed = size + size * part.parity as u64 / 100;
+                }
+                if !has_space_for(&part.output_folder, needed)? {
+                    if part_files == 0 {
+                        println!("File {} is too big to fit on empty volume, it won't be copied", &entry.input_path);
+                        skipped += 1;
+                        continue;
+                    }
+                    let message = format!("Volume {} with backup folder {} is full", part.volume, &part.output_folder);
+                    let next_root = match volumes.next() {
+                        Some(root) => root,
+                        None => ask_for_volume(&message),
+                    };
+                    if next_root.is_empty() {
+                        stopped = true;
+                        break 'dirs;
+                    }
+                    if !self.volume_roots.contains(&next_root) {
+                        self.volume_roots.push(next_root.clone());
+                    }
+                    part.backup_dirs = std::mem::take(&mut part_dirs);
+                    save_part(&mut part)?;
+
+                    // Next part is saved in new backup folder on next volume, which may contain its own previous backups
+                    self.map.output_folder = next_root;
+                    self.create_new_backup_folder()?;
+                    println!("Continuing backup on volume {} in {}", part.volume + 1, &self.map.output_folder);
+                    part.output_folder = self.map.output_folder.clone();
+                    part.volume += 1;
+                    part_dirs = empty_part_dirs(&dirs, &first_folder, &part.output_folder);
+                    part_files = 0;
+                    if !has_space_for(&part.output_folder, needed)? {
+                        println!("File {} is too big to fit on empty volume, it won't be copied", &entry.input_path);
+                        skipped += 1;
+                        continue;
+                    }
+                }
+                let entry = BackupEntry { output_path: entry.output_path.replacen(&first_folder, &part.output_folder, 1), ..entry.clone() };
+                match copy_entry(&entry) {
+                    Ok(entry) => {
+                        if entry.is_file {
+                            part_files += 1;
+                        }
+                        part_dirs[index].backup_entries.push(entry);
+                    }
+                    Err(e) => println!("{}", e),
+                }
+            }
+        }
+        part.backup_dirs = part_dirs;
+        part.last_volume = !stopped && skipped == 0;
+        save_part(&mut part)?;
+        if stopped {
+            return Err(format!("No next volume provided after volume {}, files that weren't copied yet aren't in this backup - backup is incomplete", part.volume));
+        }
+        if skipped > 0 {
+            return Err(format!("{} files were too big to fit on any volume and weren't copied - backup is incomplete", skipped));
+        }
+        println!("Backup was split into {} volumes", part.volume);
+        Ok(())
+    }
+}
+
+/// Returns number of backups in provided maps - parts of spanned backup (with the same id) are counted as one backup.
+fn count_backups(maps: &[BackupMap]) -> usize {
+    let spanned: HashSet<&str> = maps.iter().filter(|x| x.volume > 0).map(|x| x.id.as_str()).collect();
+    spanned.len() + maps.iter().filter(|x| x.volume == 0).count()
+}
+
+/// Creates dirs for part of spanned backup stored in provided backup folder - copies of provided dirs without entries, with root outputs moved from first backup folder.
+fn empty_part_dirs(dirs: &[BackupDir], first_folder: &str, folder: &str) -> Vec<BackupDir> {
+    dirs.iter().map(|x| BackupDir { root_input: x.root_input.clone(), root_output: x.root_output.replacen(first_folder, folder, 1), files: 0, folders: 0, backup_entries: vec![] }).collect()
+}
+
+/// Saves map of part of spanned backup in its backup folder, without dirs that have no entries on its volume.
+///
+/// Returns error if map can't be saved.
+fn save_part(part: &mut BackupMap) -> Result<(), String> {
+    part.backup_dirs.retain(|x| !x.backup_entries.is_empty());
+    for dir in &mut part.backup_dirs {
+        dir.files = dir.backup_entries.iter().filter(|x| x.is_file).count();
+        dir.folders = dir.backup_entries.iter().filter(|x| !x.is_file).count();
+    }
+    println!("Saving map of part {} of backup...", part.volume);
+    BackupMultiple::serialize_to_json(part).map_err(|e| format!("Map of part {} of backup couldn't be saved: {}", part.volume, e))
 }
 
 impl BackupCopy for BackupMultiple {}
@@ -162,8 +416,9 @@ impl Backup for BackupMultiple {
     ///
     /// First it creates backup folder, checking already created backups and deleting oldest folder before that (filled output folder is required to change it!), then creates all input maps, then ignores provided files and folders, then creates output maps,
     /// then copies all files and serializes map. All of this, except of creating folder and filling output maps is done by using traits.
+    /// If map has archive format chosen, files are added to one archive in backup folder instead of copying, and output paths in map are their paths inside archive. Zip archive also contains copy of map.
     ///
-    /// Function may panic if required variables are empty, or if functions in traits panic. Every non-panic error is printed to user.
+    /// Returns error if archive can't be created. Function may panic if required variables are empty, or if functions in traits panic. Every non-panic error is printed to user.
     ///
     /// # Example:
     /// To pass test you need to provide your own paths.
@@ -189,20 +444,65 @@ impl Backup for BackupMultiple {
             panic!("Trying to backup in multiple mode, but basic metadata is not filled. Program will stop");
         }
 
+        if self.map.archive != ArchiveFormat::Folder && self.map.encryption != EncryptionCipher::None {
+            return Err(String::from("Backups stored in archive can't be encrypted - turn off encryption or archive in config"));
+        }
+
+        let root_folder = self.map.output_folder.clone();
         if let Err(e) = self.create_new_backup_folder() {
             let message = format!("Couldn't create new backup folder: {}. Program will stop", e);
             panic!(message);
         }
         // Not very elegant, find better way without moving so much data
-        self.map.backup_dirs = BackupMultiple::create_input_maps(&self.map.input_folders);
+        // Input maps may be already created, when the same backup is copied to many output folders
+        if self.map.backup_dirs.is_empty() {
+            self.map.backup_dirs = BackupMultiple::create_input_maps_with_hashes(&self.map, &self.previous_maps.iter().collect::<Vec<_>>());
+        }
         let mut copied = self.map.clone();
         copied.backup_dirs = BackupMultiple::ignore_files_and_folders_parrarel(copied.backup_dirs, &copied.ignore_extensions, &copied.ignore_folders);
         let copied = copied.clone();
         let mut copied = BackupMultiple::create_output_map(copied);
-        copied.backup_dirs = BackupMultiple::copy_all(copied.backup_dirs);
+        if copied.archive == ArchiveFormat::Folder {
+            assign_compression(&mut copied.backup_dirs, &self.map.compression);
+            assign_encryption(&mut copied.backup_dirs, &self.map.encryption);
+            assign_parity(&mut copied.backup_dirs, self.map.parity);
+            // Chunks are shared by all backups, so they keep their names only with the same key
+            assign_name_key(&mut copied, &self.previous_maps);
+            if copied.obfuscate_names {
+                BackupMultiple::obfuscate_output_map(&mut copied);
+            }
+            if copied.chunking {
+                assign_chunking(&mut copied.backup_dirs, &format!("{}{}{}", &root_folder, S_SEPARATOR, S_CHUNKS), &copied.name_key);
+            }
+        }
+        if copied.spanning && copied.archive == ArchiveFormat::Folder {
+            return self.copy_spanned(copied);
+        }
+        match (&copied.archive, self.map.hardlinks, self.previous_maps.iter().max_by_key(|x| x.timestamp)) {
+            (ArchiveFormat::Tar, _, _) | (ArchiveFormat::TarZst, _, _) | (ArchiveFormat::Zip, _, _) => {
+                copied.backup_dirs = write_archive(&copied)?;
+            }
+            (_, true, Some(previous_map)) => {
+                let (to_copy, linked) = link_unchanged_files(copied.backup_dirs, previous_map);
+                let to_copy = to_copy.into_iter().filter(|x| !x.backup_entries.is_empty()).collect();
+                copied.backup_dirs = merge_linked_dirs(BackupMultiple::copy_all(to_copy), linked);
+            }
+            _ => copied.backup_dirs = BackupMultiple::copy_all(copied.backup_dirs),
+        }
         if let Err(e) = BackupMultiple::serialize_to_json(&mut copied) {
             println!("Map couldn't be saved to file, this backup won't be considered next time: {}", e);
         }
+        if copied.archive == ArchiveFormat::Zip {
+            if let Err(e) = embed_map_in_zip(&copied) {
+                println!("Map couldn't be added to zip archive: {}", e);
+            }
+        }
+        // Archive is one file, so it gets one parity file, created when archive won't change anymore
+        if copied.archive != ArchiveFormat::Folder && copied.parity > 0 {
+            if let Err(e) = create_parity(&archive_path(&copied), copied.parity) {
+                println!("{}", e);
+            }
+        }
         // self.map = copied.clone();
         Ok(())
     }
@@ -241,3 +541,79 @@ impl BackupOutput for BackupMultiple {
     }
 }
 
+/// Hardlinks files that didn't change since previous backup, instead of copying them, like rsync's ```--link-dest```.
+///
+/// File is unchanged if previous backup contains folder with the same root input, and file with the same path, hash, codec and cipher inside it. Linked files are independent entries in both backups,
+/// so deleting any of them doesn't affect the other one. If link can't be created (for example on file systems without hardlinks), file is copied.
+/// Unchanged chunked files aren't linked, they just get list of chunks of previous file, because chunks are shared by all backups.
+/// Nothing is linked if previous backup is stored in archive, because its files can't be linked.
+///
+/// Returns tuple of dirs with entries that still need to be copied (with all folders), and dirs with linked entries.
+pub fn link_unchanged_files(dirs: Vec<BackupDir>, previous_map: &BackupMap) -> (Vec<BackupDir>, Vec<BackupDir>) {
+    if previous_map.archive != ArchiveFormat::Folder {
+        println!("Previous backup {} is stored in archive, unchanged files can't be linked to it", &previous_map.output_folder);
+        return (dirs, vec![]);
+    }
+    println!("Linking unchanged files to previous backup {}...", &previous_map.output_folder);
+    let mut to_copy = vec![];
+    let mut linked = vec![];
+
+    for mut dir in dirs {
+        let mut linked_dir = BackupDir { root_input: dir.root_input.clone(), root_output: dir.root_output.clone(), files: 0, folders: 0, backup_entries: vec![] };
+        if let Some(previous_dir) = previous_map.backup_dirs.iter().find(|x| x.root_input == dir.root_input) {
+            let previous_files: HashMap<&str, &BackupEntry> = previous_dir.backup_entries.iter().filter(|x| x.is_file).map(|x| (x.input_path.as_str(), x)).collect();
+            let mut copy_entries = vec![];
+            for entry in dir.backup_entries {
+                let previous_entry = match previous_files.get(entry.input_path.as_str()) {
+                    Some(previous_entry) if entry.is_file && previous_entry.hash == entry.hash && previous_entry.compression == entry.compression && previous_entry.encryption == entry.encryption && previous_entry.chunked == entry.chunked => previous_entry,
+                    _ => {
+                        copy_entries.push(entry);
+                        continue;
+                    }
+                };
+                if entry.chunked {
+                    // Chunks are named with key of entry that stored them
+                    linked_dir.backup_entries.push(BackupEntry { chunks: previous_entry.chunks.clone(), chunk_key: previous_entry.chunk_key.clone(), ..entry });
+                    continue;
+                }
+                let result = create_parent_folder(&entry.output_path).and_then(|_| fs::hard_link(&previous_entry.output_path, &entry.output_path).map_err(|e| e.to_string()));
+                match result {
+                    Ok(_) => {
+                        // Parity of linked file is the same, if it's missing it's created after copying
+                        let _ = fs::hard_link(parity_path(&previous_entry.output_path), parity_path(&entry.output_path));
+                        linked_dir.backup_entries.push(entry)
+                    }
+                    Err(e) => {
+                        println!("Can't link {}: {}, it will be copied", &entry.output_path, e);
+                        copy_entries.push(entry);
+                    }
+                }
+            }
+            dir.backup_entries = copy_entries;
+        }
+        println!("Linked {} unchanged files from {}", linked_dir.backup_entries.len(), &dir.root_input);
+        linked_dir.files = linked_dir.backup_entries.len();
+        to_copy.push(dir);
+        linked.push(linked_dir);
+    }
+    (to_copy, linked)
+}
+
+/// Adds linked entries back to copied dirs with the same root input, so the map contains every entry of backup. Numbers of files and folders are counted again.
+pub fn merge_linked_dirs(mut copied: Vec<BackupDir>, linked: Vec<BackupDir>) -> Vec<BackupDir> {
+    for linked_dir in linked {
+        match copied.iter_mut().find(|x| x.root_input == linked_dir.root_input) {
+            Some(dir) => dir.backup_entries.extend(linked_dir.backup_entries),
+            None => {
+                if !linked_dir.backup_entries.is_empty() {
+                    copied.push(linked_dir);
+                }
+            }
+        }
+    }
+    for dir in &mut copied {
+        dir.files = dir.backup_entries.iter().filter(|x| x.is_file).count();
+        dir.folders = dir.backup_entries.iter().filter(|x| !x.is_file).count();
+    }
+    copied
+}
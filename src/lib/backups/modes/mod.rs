@@ -1,2 +1,4 @@
 pub mod backup_cloud;
-pub mod backup_multiple;
\ No newline at end of file
+pub mod backup_multiple;
+pub mod backup_dedup;
+pub mod backup_incremental;
@@ -1,13 +1,19 @@
 use crate::backups::map::backup_entry::BackupEntry;
 use std::path::{Path};
 use std::fs;
-use std::io::{BufReader, BufWriter};
+use std::io::{BufReader, BufWriter, Read};
 use crate::backups::map::backup_dir::BackupDir;
 use std::sync::{Arc, Mutex};
 use scoped_threadpool::Pool;
 use std::borrow::BorrowMut;
 use crate::backups::helpers::multithreading::arc_to_inner;
 use crate::backups::helpers::dirs::get_last_subdir;
+use crate::backups::helpers::compression::compress;
+use crate::backups::map::compression_codec::CompressionCodec;
+use crate::backups::map::encryption_cipher::EncryptionCipher;
+use crate::backups::helpers::encryption::EncryptWriter;
+use crate::backups::helpers::chunking::store_chunks;
+use crate::backups::helpers::parity::store_parity;
 
 #[cfg(test)]
 mod tests {
@@ -22,8 +28,8 @@ mod tests {
         let mut backup_dirs = vec![BackupDir::new(), BackupDir::new()];
         backup_dirs[0].root_output = String::from("/home/szymon/Downloads/backup/1");
         backup_dirs[1].root_output = String::from("/home/szymon/Downloads/backup/2");
-        let entry1 = BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/home/szymon/Downloads/backup/1/bookmarks.html"), is_file: true, hash: String::new()};
-        let entry2 = BackupEntry {input_path: String::from("/usr/bin/bash"), output_path: String::from("/home/szymon/Downloads/backup/2/bash"), is_file: true, hash: String::new()};
+        let entry1 = BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/home/szymon/Downloads/backup/1/bookmarks.html"), is_file: true, hash: String::new(), ..BackupEntry::new()};
+        let entry2 = BackupEntry {input_path: String::from("/usr/bin/bash"), output_path: String::from("/home/szymon/Downloads/backup/2/bash"), is_file: true, hash: String::new(), ..BackupEntry::new()};
         backup_dirs[0].backup_entries.push(entry1);
         backup_dirs[1].backup_entries.push(entry2);
         backup_dirs = BackupCloud::copy_all(backup_dirs);
@@ -35,7 +41,7 @@ mod tests {
     fn test_copy_folder() {
         let mut dir = BackupDir::new();
         dir.root_output = String::from("/home/szymon/Downloads/backup/1");
-        let entry1 = BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/home/szymon/Downloads/backup/1/bookmarks.html"), is_file: true, hash: String::new()};
+        let entry1 = BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/home/szymon/Downloads/backup/1/bookmarks.html"), is_file: true, hash: String::new(), ..BackupEntry::new()};
         dir.backup_entries.push(entry1);
         copy_folder(&mut dir).unwrap();
         assert!(Path::new(&dir.backup_entries[0].output_path).exists());
@@ -43,7 +49,7 @@ mod tests {
 
     #[test]
     fn test_copy_file() {
-        let entry = BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/home/szymon/Downloads/backup/1/bookmarks.html"), is_file: true, hash: String::new()};
+        let entry = BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/home/szymon/Downloads/backup/1/bookmarks.html"), is_file: true, hash: String::new(), ..BackupEntry::new()};
         copy_file(&entry).unwrap();
         assert!(Path::new(&entry.output_path).exists());
     }
@@ -89,8 +95,8 @@ pub trait BackupCopy {
     /// let mut backup_dirs = vec![BackupDir::new(), BackupDir::new()];
     /// backup_dirs[0].root_output = String::from("/home/szymon/Downloads/backup/1");
     /// backup_dirs[1].root_output = String::from("/home/szymon/Downloads/backup/2");
-    /// let entry1 = BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/home/szymon/Downloads/backup/1/bookmarks.html"), is_file: true, hash: String::new()};
-    /// let entry2 = BackupEntry {input_path: String::from("/usr/bin/bash"), output_path: String::from("/home/szymon/Downloads/backup/2/bash"), is_file: true, hash: String::new()};
+    /// let entry1 = BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/home/szymon/Downloads/backup/1/bookmarks.html"), is_file: true, hash: String::new(), ..BackupEntry::new()};
+    /// let entry2 = BackupEntry {input_path: String::from("/usr/bin/bash"), output_path: String::from("/home/szymon/Downloads/backup/2/bash"), is_file: true, hash: String::new(), ..BackupEntry::new()};
     /// backup_dirs[0].backup_entries.push(entry1);
     /// backup_dirs[1].backup_entries.push(entry2);
     /// backup_dirs = BackupCloud::copy_all(backup_dirs);
@@ -133,7 +139,7 @@ pub trait BackupCopy {
     }
 }
 
-/// Copies content of one BackupDir - for each entry, from input path to output path.
+/// Copies content of one BackupDir - for each entry, from input path to output path. Chunked files are stored as chunks, and their entries get lists of chunks. Parity files are created for entries with parity.
 ///
 /// Requires completely filled BackupDir, after all processing.
 ///
@@ -150,7 +156,7 @@ pub trait BackupCopy {
 /// use flash_backup::backups::traits::backup_copy::copy_folder;
 /// let mut dir = BackupDir::new();
 /// dir.root_output = String::from("/home/szymon/Downloads/backup/1");
-/// let entry1 = BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/home/szymon/Downloads/backup/1/bookmarks.html"), is_file: true, hash: String::new()};
+/// let entry1 = BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/home/szymon/Downloads/backup/1/bookmarks.html"), is_file: true, hash: String::new(), ..BackupEntry::new()};
 /// dir.backup_entries.push(entry1);
 /// copy_folder(&mut dir).unwrap();
 /// assert!(Path::new(&dir.backup_entries[0].output_path).exists());
@@ -172,24 +178,11 @@ pub fn copy_folder(folder: &mut BackupDir) -> Result<(), String> {
             continue;
         }
 
-        match entry.is_file {
-            true => {
-                match copy_file(&entry) {
-                    Ok(_) => copied_entries.push(entry.clone()),
-                    Err(e) => {
-                        println!("{}", e);
-                        continue;
-                    }
-                }
-            }
-            false => {
-                match create_folder(&entry.output_path) {
-                    Ok(_) => copied_entries.push(entry.clone()),
-                    Err(e) => {
-                        println!("{}", e);
-                        continue;
-                    }
-                }
+        match copy_entry(entry) {
+            Ok(entry) => copied_entries.push(entry),
+            Err(e) => {
+                println!("{}", e);
+                continue;
             }
         }
     }
@@ -216,10 +209,32 @@ pub fn copy_folder(folder: &mut BackupDir) -> Result<(), String> {
     }
 }
 
+/// Copies one entry from input path to output path - file is copied (or stored as chunks, if it's chunked) and gets parity file if entry has parity, folder is created.
+///
+/// Returns copied entry (chunked entry gets list of its chunks), or error if entry can't be copied. Error while creating parity is only printed to user.
+pub fn copy_entry(entry: &BackupEntry) -> Result<BackupEntry, String> {
+    if !entry.is_file {
+        return create_folder(&entry.output_path).map(|_| entry.clone());
+    }
+    let copied = match entry.chunked {
+        true => BackupEntry { chunks: store_chunks(entry, false)?, ..entry.clone() },
+        false => {
+            copy_file(entry)?;
+            entry.clone()
+        }
+    };
+    if let Err(e) = store_parity(&copied) {
+        println!("{}", e);
+    }
+    Ok(copied)
+}
+
 /// Copies file from input path to output path (in BackupEntry).
 ///
 /// Requires completely filled BackupEntry, after all processing.
 ///
+/// Files with codec or cipher set in BackupEntry are compressed and encrypted while copying.
+///
 /// Returns error if BackupEntry is not filled, if parent directory can't be created (if needed), if input file can't be opened, if destination file can't be created, or if copying can't be done.
 ///
 /// # Example:
@@ -228,7 +243,7 @@ pub fn copy_folder(folder: &mut BackupDir) -> Result<(), String> {
 /// use flash_backup::backups::map::backup_entry::BackupEntry;
 /// use flash_backup::backups::traits::backup_copy::copy_file;
 /// use std::path::Path;
-/// let entry = BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/home/szymon/Downloads/backup/1/bookmarks.html"), is_file: true, hash: String::new()};
+/// let entry = BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/home/szymon/Downloads/backup/1/bookmarks.html"), is_file: true, hash: String::new(), ..BackupEntry::new()};
 /// copy_file(&entry).unwrap();
 /// assert!(Path::new(&entry.output_path).exists());
 /// ```
@@ -252,6 +267,10 @@ pub fn copy_file(entry: &BackupEntry) -> Result<(), String> {
         }
         Ok(source) => {
             let mut buf_reader = BufReader::new(source);
+            if entry.compression != CompressionCodec::None || entry.encryption != EncryptionCipher::None {
+                println!("Compressing and encrypting: {}", &entry.input_path);
+                return compress_file(entry, &mut buf_reader);
+            }
             match fs::File::create(&entry.output_path) {
                 Err(e) => {
                     let message = format!("Couldn't copy file {} to destination {}: can't create destnation file: {}", &entry.input_path, &entry.output_path, e);
@@ -272,6 +291,29 @@ pub fn copy_file(entry: &BackupEntry) -> Result<(), String> {
     Ok(())
 }
 
+/// Compresses content of provided reader with codec of entry, then encrypts it with cipher of entry, and saves it to output path.
+///
+/// Data is written to temporary file next to output path first, and renamed only after compression and encryption are finished, so output path never contains incomplete file.
+///
+/// Returns error if temporary file can't be created, compression or encryption fails, or file can't be renamed.
+pub fn compress_file(entry: &BackupEntry, reader: &mut dyn Read) -> Result<(), String> {
+    let partial_path = format!("{}.part", &entry.output_path);
+    let result = fs::File::create(&partial_path)
+        .map_err(|e| format!("can't create destnation file: {}", e))
+        .and_then(|file| match entry.encryption {
+            EncryptionCipher::None => compress(reader, BufWriter::new(file), &entry.compression).map(|_| ()),
+            _ => EncryptWriter::new(BufWriter::new(file), &entry.encryption)
+                .and_then(|writer| compress(reader, writer, &entry.compression))
+                .and_then(|writer| writer.finish().map(|_| ()).map_err(|e| e.to_string())),
+        })
+        .and_then(|_| fs::rename(&partial_path, &entry.output_path).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&partial_path);
+        return Err(format!("Couldn't copy file {} to destination {}: {}", &entry.input_path, &entry.output_path, e));
+    }
+    Ok(())
+}
+
 /// Creates folder with all its parent folders (if they doesn't exist).
 ///
 /// May return error if path is empty or folder can't be created.
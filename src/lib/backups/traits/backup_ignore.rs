@@ -18,8 +18,8 @@ mod tests {
     #[test]
     fn test_ignore_extensions_single_folder() {
         let mut dir = BackupDir {files: 1, folders: 1, root_input: String::new(), root_output: String::new(), backup_entries: vec![
-            BackupEntry {input_path: String::from("/home/user/Downloads/X/node_modules"), output_path: String::from("/home/user/Downloads/backup/node_modules"), is_file: false, hash: String::new() },
-        BackupEntry{ input_path: String::from("/home/user/Downloads/X/node_modules/123.js"), output_path: String::from("/home/user/Downloads/backup/node_modules/123.json"), is_file: true, hash: String::from("12345") }]};
+            BackupEntry {input_path: String::from("/home/user/Downloads/X/node_modules"), output_path: String::from("/home/user/Downloads/backup/node_modules"), is_file: false, hash: String::new(), ..BackupEntry::new() },
+        BackupEntry{ input_path: String::from("/home/user/Downloads/X/node_modules/123.js"), output_path: String::from("/home/user/Downloads/backup/node_modules/123.json"), is_file: true, hash: String::from("12345"), ..BackupEntry::new() }]};
         ignore_folders_single_folder(&mut dir, &vec![String::from("/dupa_modules")]);
         assert_eq!(dir.backup_entries.len(), 2);
         ignore_folders_single_folder(&mut dir, &vec![String::from("/node_modules")]);
@@ -29,8 +29,8 @@ mod tests {
     #[test]
     fn test_ignore_folders_single_folder() {
         let mut dir = BackupDir {files: 1, folders: 1, root_input: String::new(), root_output: String::new(), backup_entries: vec![
-            BackupEntry {input_path: String::from("/home/user/Downloads/X/node_modules"), output_path: String::from("/home/user/Downloads/backup/node_modules"), is_file: false, hash: String::new() },
-            BackupEntry{ input_path: String::from("/home/user/Downloads/X/node_modules/123.js"), output_path: String::from("/home/user/Downloads/backup/node_modules/123.json"), is_file: true, hash: String::from("12345") }]};
+            BackupEntry {input_path: String::from("/home/user/Downloads/X/node_modules"), output_path: String::from("/home/user/Downloads/backup/node_modules"), is_file: false, hash: String::new(), ..BackupEntry::new() },
+            BackupEntry{ input_path: String::from("/home/user/Downloads/X/node_modules/123.js"), output_path: String::from("/home/user/Downloads/backup/node_modules/123.json"), is_file: true, hash: String::from("12345"), ..BackupEntry::new() }]};
         ignore_extensions_single_folder(&mut dir, &vec![String::from(".ts")]);
         assert_eq!(dir.backup_entries.len(), 2);
         ignore_extensions_single_folder(&mut dir, &vec![String::from(".js")]);
@@ -40,8 +40,8 @@ mod tests {
     #[test]
     fn test_ignore_files_and_folders_parrarel() {
         let mut dir = BackupDir {files: 1, folders: 1, root_input: String::new(), root_output: String::new(), backup_entries: vec![
-            BackupEntry {input_path: String::from("/home/user/Downloads/X/node_modules"), output_path: String::from("/home/user/Downloads/backup/node_modules"), is_file: false, hash: String::new() },
-            BackupEntry{ input_path: String::from("/home/user/Downloads/X/node_modules/123.js"), output_path: String::from("/home/user/Downloads/backup/node_modules/123.json"), is_file: true, hash: String::from("12345") }]};
+            BackupEntry {input_path: String::from("/home/user/Downloads/X/node_modules"), output_path: String::from("/home/user/Downloads/backup/node_modules"), is_file: false, hash: String::new(), ..BackupEntry::new() },
+            BackupEntry{ input_path: String::from("/home/user/Downloads/X/node_modules/123.js"), output_path: String::from("/home/user/Downloads/backup/node_modules/123.json"), is_file: true, hash: String::from("12345"), ..BackupEntry::new() }]};
 
         let res = BackupCloud::ignore_files_and_folders_parrarel(vec![dir.clone()], &vec![String::from(".ts")], &vec![String::from("/dupa_modules")]);
         assert_eq!(res[0].backup_entries.len(), 2);
@@ -74,8 +74,8 @@ pub trait BackupIgnore {
     /// use flash_backup::backups::modes::backup_cloud::BackupCloud;
     /// use flash_backup::backups::traits::backup_ignore::BackupIgnore;
     /// let mut dir = BackupDir {files: 1, folders: 1, root_input: String::new(), root_output: String::new(), backup_entries: vec![
-    ///BackupEntry {input_path: String::from("/home/user/Downloads/X/node_modules"), output_path: String::from("/home/user/Downloads/backup/node_modules"), is_file: false, hash: String::new() },
-    ///BackupEntry{ input_path: String::from("/home/user/Downloads/X/node_modules/123.js"), output_path: String::from("/home/user/Downloads/backup/node_modules/123.json"), is_file: true, hash: String::from("12345") }]};
+    ///BackupEntry {input_path: String::from("/home/user/Downloads/X/node_modules"), output_path: String::from("/home/user/Downloads/backup/node_modules"), is_file: false, hash: String::new(), ..BackupEntry::new() },
+    ///BackupEntry{ input_path: String::from("/home/user/Downloads/X/node_modules/123.js"), output_path: String::from("/home/user/Downloads/backup/node_modules/123.json"), is_file: true, hash: String::from("12345"), ..BackupEntry::new() }]};
     ///let res = BackupCloud::ignore_files_and_folders_parrarel(vec![dir.clone()], &vec![String::from(".ts")], &vec![String::from("/dupa_modules")]);
     ///assert_eq!(res[0].backup_entries.len(), 2);
     ///let res = BackupCloud::ignore_files_and_folders_parrarel(vec![dir], &vec![String::from(".js")], &vec![String::from("/node_modules")]);
@@ -117,8 +117,8 @@ pub trait BackupIgnore {
 /// use flash_backup::backups::map::backup_entry::BackupEntry;
 /// use flash_backup::backups::traits::backup_ignore::ignore_folders_single_folder;
 /// let mut dir = BackupDir {files: 1, folders: 1, root_input: String::new(), root_output: String::new(), backup_entries: vec![
-/// BackupEntry {input_path: String::from("/home/user/Downloads/X/node_modules"), output_path: String::from("/home/user/Downloads/backup/node_modules"), is_file: false, hash: String::new() },
-/// BackupEntry{ input_path: String::from("/home/user/Downloads/X/node_modules/123.js"), output_path: String::from("/home/user/Downloads/backup/node_modules/123.json"), is_file: true, hash: String::from("12345") }]};
+/// BackupEntry {input_path: String::from("/home/user/Downloads/X/node_modules"), output_path: String::from("/home/user/Downloads/backup/node_modules"), is_file: false, hash: String::new(), ..BackupEntry::new() },
+/// BackupEntry{ input_path: String::from("/home/user/Downloads/X/node_modules/123.js"), output_path: String::from("/home/user/Downloads/backup/node_modules/123.json"), is_file: true, hash: String::from("12345"), ..BackupEntry::new() }]};
 /// ignore_folders_single_folder(&mut dir, &vec![String::from("/dupa_modules")]);
 /// assert_eq!(dir.backup_entries.len(), 2);
 /// ignore_folders_single_folder(&mut dir, &vec![String::from("/node_modules")]);
@@ -158,8 +158,8 @@ pub fn ignore_extensions_single_folder(folder: &mut BackupDir, extensions_to_ign
 /// use flash_backup::backups::map::backup_entry::BackupEntry;
 /// use flash_backup::backups::traits::backup_ignore::ignore_extensions_single_folder;
 /// let mut dir = BackupDir {files: 1, folders: 1, root_input: String::new(), root_output: String::new(), backup_entries: vec![
-/// BackupEntry {input_path: String::from("/home/user/Downloads/X/node_modules"), output_path: String::from("/home/user/Downloads/backup/node_modules"), is_file: false, hash: String::new() },
-/// BackupEntry{ input_path: String::from("/home/user/Downloads/X/node_modules/123.js"), output_path: String::from("/home/user/Downloads/backup/node_modules/123.json"), is_file: true, hash: String::from("12345") }]};
+/// BackupEntry {input_path: String::from("/home/user/Downloads/X/node_modules"), output_path: String::from("/home/user/Downloads/backup/node_modules"), is_file: false, hash: String::new(), ..BackupEntry::new() },
+/// BackupEntry{ input_path: String::from("/home/user/Downloads/X/node_modules/123.js"), output_path: String::from("/home/user/Downloads/backup/node_modules/123.json"), is_file: true, hash: String::from("12345"), ..BackupEntry::new() }]};
 /// ignore_extensions_single_folder(&mut dir, &vec![String::from(".ts")]);
 /// assert_eq!(dir.backup_entries.len(), 2);
 /// ignore_extensions_single_folder(&mut dir, &vec![String::from(".js")]);
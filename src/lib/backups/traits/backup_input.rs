@@ -7,6 +7,12 @@ use std::sync::{Arc, Mutex};
 use std::borrow::BorrowMut;
 use scoped_threadpool::Pool;
 use crate::backups::helpers::multithreading::arc_to_inner;
+use crate::backups::helpers::hash_cache::{cache_folder, load_hash_cache, update_hash_cache, inode};
+use crate::S_CACHE_DIR;
+use crate::backups::map::backup_map::BackupMap;
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::time::UNIX_EPOCH;
 
 #[cfg(test)]
 mod tests {
@@ -40,6 +46,36 @@ mod tests {
         assert_eq!(backup_dirs[0].folders, 2);
     }
 
+    #[test]
+    fn test_fill_single_backup_dir_reusing() {
+        let root = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("a.txt"), "content").unwrap();
+        let root_input = root.to_str().unwrap().to_string();
+        let mut dir = BackupDir { root_input: root_input.clone(), ..BackupDir::new() };
+        fill_single_backup_dir(&mut dir);
+        let entry = dir.backup_entries[0].clone();
+        assert_eq!(entry.original_size, 7);
+        assert_ne!(entry.modified, 0);
+
+        // Unchanged file gets known hash, without hashing
+        let mut map = BackupMap::new(crate::backups::map::backup_mode::BackupMode::Multiple);
+        map.backup_dirs = vec![BackupDir { backup_entries: vec![BackupEntry { hash: String::from("known"), ..entry.clone() }], ..BackupDir::new() }];
+        let mut dir = BackupDir { root_input: root_input.clone(), ..BackupDir::new() };
+        fill_single_backup_dir_reusing(&mut dir, &known_hashes(&[&map], false));
+        assert_eq!(dir.backup_entries[0].hash, "known");
+        let mut dir = BackupDir { root_input: root_input.clone(), ..BackupDir::new() };
+        fill_single_backup_dir_reusing(&mut dir, &known_hashes(&[&map], true));
+        assert_eq!(dir.backup_entries[0].hash, entry.hash);
+
+        // Changed file is hashed again
+        std::fs::write(root.join("a.txt"), "changed content").unwrap();
+        let mut dir = BackupDir { root_input, ..BackupDir::new() };
+        fill_single_backup_dir_reusing(&mut dir, &known_hashes(&[&map], false));
+        assert_ne!(dir.backup_entries[0].hash, "known");
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
     #[test]
     fn test_all() {
         let paths = vec![String::from("/usr/bin/X11"), String::from("/usr/share/alsa"), String::from("/usr/share/gtk-doc"), String::from("/usr/share/help"), String::from("/usr/share/pulseaudio")];
@@ -73,9 +109,37 @@ pub trait BackupInput {
     /// assert_eq!(dir[0].folders, 2);
     /// ```
     fn create_input_maps(paths: &Vec<String>) -> Vec<BackupDir> {
+        Self::create_input_maps_reusing(paths, &HashMap::new())
+    }
+
+    /// Creates file map for input folders of provided map, reusing hashes of unchanged files from provided previous maps, and from hash cache if map uses it - hashes from cache, which contain inodes, are preferred.
+    /// In paranoid mode nothing is reused. Hash cache is updated with hashes of all files afterwards, so even files hashed for the first time aren't hashed again next time.
+    fn create_input_maps_with_hashes(map: &BackupMap, previous_maps: &[&BackupMap]) -> Vec<BackupDir> {
+        let mut known = known_hashes(previous_maps, map.paranoid);
+        let cache = match map.hash_cache {
+            true => cache_folder(),
+            false => None,
+        };
+        if let (Some(cache), false) = (&cache, map.paranoid) {
+            known.extend(load_hash_cache(cache));
+        }
+        let dirs = Self::create_input_maps_reusing(&map.input_folders, &known);
+        match (&cache, map.hash_cache) {
+            (Some(cache), _) => match update_hash_cache(cache, &dirs) {
+                Ok(saved) => println!("Saved {} hashes to cache {}", saved, cache),
+                Err(e) => println!("Couldn't update cache of hashes: {}", e),
+            },
+            (None, true) => println!("Can't find cache folder, set {} environment variable - hashes won't be cached", S_CACHE_DIR),
+            (None, false) => {}
+        }
+        dirs
+    }
+
+    /// Creates file map like ```create_input_maps```, but files with the same size and time of modification as in provided known hashes (created by ```known_hashes```) aren't hashed again - their known hash is used.
+    fn create_input_maps_reusing(paths: &Vec<String>, known: &HashMap<String, KnownHash>) -> Vec<BackupDir> {
         let backup_dirs = check_input_folders(paths);
         let mut backup_dirs = Arc::new(Mutex::new(backup_dirs));
-        backup_dirs = fill_backup_dirs_parallel(backup_dirs);
+        backup_dirs = fill_backup_dirs_parallel_reusing(backup_dirs, known);
         match arc_to_inner(backup_dirs) {
             Ok(dirs) => {
                 if !dirs.is_empty() {
@@ -150,6 +214,11 @@ pub fn check_input_folders(folders: &Vec<String>) -> Vec<BackupDir> {
 /// assert_eq!(backup_dirs[0].folders, 2);
 /// ```
 pub fn fill_backup_dirs_parallel(dirs: Arc<Mutex<Vec<BackupDir>>>) -> Arc<Mutex<Vec<BackupDir>>> {
+    fill_backup_dirs_parallel_reusing(dirs, &HashMap::new())
+}
+
+/// Fills data in every BackupDir concurrently, like ```fill_backup_dirs_parallel```, reusing hashes of unchanged files from provided known hashes - for further info check documentation of fill_single_backup_dir_reusing.
+pub fn fill_backup_dirs_parallel_reusing(dirs: Arc<Mutex<Vec<BackupDir>>>, known: &HashMap<String, KnownHash>) -> Arc<Mutex<Vec<BackupDir>>> {
     // Checking input
     let dirs_ref = Arc::clone(&dirs);
     if dirs_ref.lock().unwrap().is_empty() {
@@ -167,7 +236,7 @@ pub fn fill_backup_dirs_parallel(dirs: Arc<Mutex<Vec<BackupDir>>>) -> Arc<Mutex<
             let dirs_ref = Arc::clone(&dirs);
             scoped.execute(move || {
                 let mut dirs_temp = dirs_ref.lock().unwrap();
-                fill_single_backup_dir(dirs_temp[i].borrow_mut());
+                fill_single_backup_dir_reusing(dirs_temp[i].borrow_mut(), known);
             });
         }
     });
@@ -194,6 +263,14 @@ pub fn fill_backup_dirs_parallel(dirs: Arc<Mutex<Vec<BackupDir>>>) -> Arc<Mutex<
 /// assert_eq!(dir.folders, 2);
 /// ```
 pub fn fill_single_backup_dir(dir: &mut BackupDir) {
+    fill_single_backup_dir_reusing(dir, &HashMap::new())
+}
+
+/// Fills data of one BackupDir, like ```fill_single_backup_dir```, with size and time of modification of every file.
+///
+/// If provided known hashes contain file with the same path, size, time of modification and inode (if it's known), its known hash is used instead of hashing file again. Size and time are read before hashing,
+/// so file modified while it's hashed gets newer time of modification, and is hashed again next time.
+pub fn fill_single_backup_dir_reusing(dir: &mut BackupDir, known: &HashMap<String, KnownHash>) {
     // Checking input
     let as_path = Path::new(&dir.root_input);
     if dir.root_input.is_empty() || !as_path.exists() || as_path.is_file() {
@@ -202,13 +279,25 @@ pub fn fill_single_backup_dir(dir: &mut BackupDir) {
     }
 
     // Creating map
+    let mut reused: usize = 0;
     for entry in WalkDir::new(&dir.root_input).into_iter().skip(1).filter_map(|e| e.ok()) { // skip(1) because first value is always root input
         match entry.path().is_file() {
             true => {
                 let path = String::from(entry.path().to_str().expect("Unexpected error while creating maps"));
-                match generate_hash_meow_hash(&path) {
+                let (size, modified, inode) = match entry.metadata() {
+                    Ok(metadata) => (metadata.len(), modified_time(&metadata), inode(&metadata)),
+                    Err(_) => (0, 0, 0),
+                };
+                let hash = match known.get(&path) {
+                    Some(known) if modified != 0 && known.size == size && known.modified == modified && (known.inode == 0 || known.inode == inode) => {
+                        reused += 1;
+                        Ok(known.hash.clone())
+                    }
+                    _ => generate_hash_meow_hash(&path),
+                };
+                match hash {
                     Ok(hash) => {
-                        dir.backup_entries.push(BackupEntry { input_path: path, output_path: String::new(), is_file: true, hash })
+                        dir.backup_entries.push(BackupEntry { input_path: path, output_path: String::new(), is_file: true, hash, original_size: size, modified, ..BackupEntry::new() })
                     }
                     Err(e) => {
                         println!("{}, skipping...", e);
@@ -218,7 +307,7 @@ pub fn fill_single_backup_dir(dir: &mut BackupDir) {
             }
             false => {
                 let path = String::from(entry.path().to_str().expect("Unexpected error while creating maps"));
-                dir.backup_entries.push(BackupEntry { input_path: path, output_path: String::new(), is_file: false, hash: String::new() });
+                dir.backup_entries.push(BackupEntry { input_path: path, output_path: String::new(), is_file: false, hash: String::new(), ..BackupEntry::new() });
             }
         }
     }
@@ -231,4 +320,40 @@ pub fn fill_single_backup_dir(dir: &mut BackupDir) {
     } else {
         println!("Found {} files and {} folders in {}", dir.files, dir.folders, &dir.root_input)
     }
+    if reused > 0 {
+        println!("Reused hashes of {} unchanged files in {}", reused, &dir.root_input);
+    }
+}
+
+/// Hash of file saved in previous backup or in hash cache, with size, time of modification and inode of file when it was hashed.
+pub struct KnownHash {
+    pub size: u64,
+    pub modified: u64,
+    /// Inode of file, or 0 if it's unknown (maps don't contain inodes) - then it isn't compared.
+    pub inode: u64,
+    pub hash: String,
+}
+
+/// Returns time of last modification of file, in nanoseconds since UNIX epoch, or 0 if it can't be read.
+pub fn modified_time(metadata: &Metadata) -> u64 {
+    metadata.modified().ok().and_then(|x| x.duration_since(UNIX_EPOCH).ok()).map(|x| x.as_nanos() as u64).unwrap_or(0)
+}
+
+/// Collects hashes of files from provided previous maps, with their sizes and times of modification, as map of input path and known hash - newer maps replace entries of older ones.
+///
+/// Files without saved time of modification (from maps saved by older versions of program) are skipped. If ```paranoid``` is true, nothing is collected, so every file is hashed again.
+pub fn known_hashes(maps: &[&BackupMap], paranoid: bool) -> HashMap<String, KnownHash> {
+    let mut known = HashMap::new();
+    if paranoid {
+        println!("Paranoid mode - every file will be hashed");
+        return known;
+    }
+    let mut maps = maps.to_vec();
+    maps.sort_by_key(|x| x.timestamp);
+    for entry in maps.iter().flat_map(|x| x.backup_dirs.iter()).flat_map(|x| x.backup_entries.iter()) {
+        if entry.is_file && entry.modified != 0 && !entry.hash.is_empty() {
+            known.insert(entry.input_path.clone(), KnownHash { size: entry.original_size, modified: entry.modified, inode: 0, hash: entry.hash.clone() });
+        }
+    }
+    known
 }
@@ -1,4 +1,6 @@
 use crate::backups::map::backup_map::BackupMap;
+use crate::backups::helpers::encryption::{generate_name_key, obfuscated_name};
+use crate::S_SEPARATOR;
 
 /// Provides function that should fill BackupMap with output data.
 ///
@@ -12,4 +14,26 @@ pub trait BackupOutput {
     ///
     /// Implementations should require created BackupMap with all input data, after ignoring etc.
     fn create_output_map(map: BackupMap) -> BackupMap;
+
+    /// Replaces root output folders and output paths of all entries with opaque names, derived from their input paths and secret key saved in map, so backup doesn't reveal names of files and folders.
+    ///
+    /// Every entry is placed in subfolder named by first two characters of its name, inside root output folder. Key is generated if map doesn't have one yet.
+    /// Real paths are saved only in map, so it should be used only for encrypted backups.
+    ///
+    /// Function may panic if key can't be generated.
+    fn obfuscate_output_map(map: &mut BackupMap) {
+        if map.name_key.is_empty() {
+            match generate_name_key() {
+                Ok(key) => map.name_key = key,
+                Err(e) => panic!("Can't generate key to hide names of files: {}. Program will stop", e),
+            }
+        }
+        for dir in &mut map.backup_dirs {
+            dir.root_output = format!("{}{}{}", &map.output_folder, S_SEPARATOR, obfuscated_name(&map.name_key, &dir.root_input));
+            for entry in &mut dir.backup_entries {
+                let name = obfuscated_name(&map.name_key, &entry.input_path);
+                entry.output_path = format!("{}{}{}{}{}", &dir.root_output, S_SEPARATOR, &name[..2], S_SEPARATOR, &name[2..]);
+            }
+        }
+    }
 }
\ No newline at end of file
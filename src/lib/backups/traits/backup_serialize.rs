@@ -1,10 +1,16 @@
 use crate::backups::map::backup_map::BackupMap;
 use std::path::{Path, MAIN_SEPARATOR};
 use crate::S_MAP;
-use std::fs::{File, remove_file};
-use std::io::{Write};
+use std::fs::{File, remove_file, rename, metadata};
+use std::io::BufWriter;
 use crate::backups::map::backup_dir::BackupDir;
-use crate::backups::helpers::hashing::generate_hash_meow_hash;
+use crate::backups::helpers::compression::hash_entry;
+use crate::backups::helpers::encryption::EncryptWriter;
+use crate::backups::helpers::map_io::write_map;
+use crate::backups::helpers::map_checksum::seal_map;
+use crate::backups::helpers::chunking::stored_paths;
+use crate::backups::helpers::parity::fill_parity;
+use crate::backups::map::encryption_cipher::EncryptionCipher;
 use std::sync::{Arc, Mutex};
 use scoped_threadpool::Pool;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -21,6 +27,28 @@ mod tests {
     use crate::backups::map::backup_dir::BackupDir;
     use crate::backups::map::backup_entry::BackupEntry;
     use crate::backups::helpers::hashing::generate_hash_meow_hash;
+    use crate::backups::traits::backup_serialize::save_map_to_file;
+    use crate::backups::helpers::dirs::load_map;
+    use std::fs;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_save_map_keeps_old_map_on_error() {
+        let root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join(".map.json");
+        let mut map = BackupMap::new(BackupMode::Multiple);
+        map.id = String::from("old");
+        save_map_to_file(&mut map, path.to_str().unwrap()).unwrap();
+        assert!(!root.join(".map.json.part").exists());
+
+        // Temporary file can't be created, so map can't be saved
+        fs::create_dir_all(root.join(".map.json.part")).unwrap();
+        map.id = String::from("new");
+        assert!(save_map_to_file(&mut map, path.to_str().unwrap()).is_err());
+        assert_eq!(load_map(&path).unwrap().id, "old");
+        fs::remove_dir_all(root).unwrap();
+    }
 
     #[test]
     fn test_serialize_to_json() {
@@ -36,8 +64,8 @@ mod tests {
         let mut backup_dirs = vec![BackupDir::new(), BackupDir::new()];
         backup_dirs[0].root_output = String::from("/home/szymon/Downloads/backup/1");
         backup_dirs[1].root_output = String::from("/home/szymon/Downloads/backup/2");
-        let entry1 = BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/usr/lib/chromium/bookmarks.html"), is_file: true, hash: generate_hash_meow_hash("/usr/lib/chromium/bookmarks.html").unwrap()};
-        let entry2 = BackupEntry {input_path: String::from("/usr/bin/bash"), output_path: String::from("/usr/bin/bash"), is_file: true, hash: generate_hash_meow_hash("/usr/bin/bash").unwrap()};
+        let entry1 = BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/usr/lib/chromium/bookmarks.html"), is_file: true, hash: generate_hash_meow_hash("/usr/lib/chromium/bookmarks.html").unwrap(), ..BackupEntry::new()};
+        let entry2 = BackupEntry {input_path: String::from("/usr/bin/bash"), output_path: String::from("/usr/bin/bash"), is_file: true, hash: generate_hash_meow_hash("/usr/bin/bash").unwrap(), ..BackupEntry::new()};
         backup_dirs[0].backup_entries.push(entry1);
         backup_dirs[1].backup_entries.push(entry2);
         map.backup_dirs = backup_dirs;
@@ -49,7 +77,7 @@ mod tests {
     fn test_verify_one_folder() {
         let mut dir = BackupDir::new();
         dir.files = 1;
-        dir.backup_entries.push(BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/usr/lib/chromium/bookmarks.html"), is_file: true, hash: generate_hash_meow_hash("/usr/lib/chromium/bookmarks.html").unwrap()});
+        dir.backup_entries.push(BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/usr/lib/chromium/bookmarks.html"), is_file: true, hash: generate_hash_meow_hash("/usr/lib/chromium/bookmarks.html").unwrap(), ..BackupEntry::new()});
         let result = verify_one_folder(&dir).unwrap();
         assert_eq!(result, 0);
     }
@@ -89,7 +117,7 @@ pub trait BackupSerialize {
             }
         }
     }
-    /// Serializes map to JSON file.
+    /// Serializes map to file, in format chosen in map (JSON by default).
     ///
     /// Should be used with completely filled BackupMap, after all processing, requires filled path to main output folder.
     ///
@@ -117,33 +145,13 @@ pub trait BackupSerialize {
         }
 
         // Generating metadata, serializing
-        println!("Saving folder map to JSON file...");
+        println!("Saving folder map to {} file...", &map.map_format);
         map.generate_metadata();
-        match serde_json::to_string_pretty(map) {
-            Err(e) => {
-                let message = format!("Can't convert data to text: {}", e);
-                return Err(message);
-            }
-            Ok(json_string) => {
-                let json_path = format!("{}{}{}", &map.output_folder, &MAIN_SEPARATOR.to_string(), S_MAP);
-                match File::create(&json_path) {
-                    Err(e) => {
-                        let message = format!("Can't create JSON file {}: {}", json_path, e);
-                        return Err(message);
-                    }
-                    Ok(mut json_file) => {
-                        if let Err(e) = json_file.write_all(json_string.as_ref()) {
-                            let mut message = format!("Can't copy serialized map to JSON file: {}", e);
-                            if let Err(e) = remove_file(&json_path) {
-                                message = format!("{} and can't remove JSON file: {}", message, e);
-                            }
-                            return Err(message);
-                        }
-                    }
-                }
-            }
-        }
-        println!("JSON file created");
+        // Parity of files that weren't copied now (like linked files or existing objects) may be missing
+        fill_parity(&map.backup_dirs);
+        record_sizes(map);
+        save_map_to_json(map)?;
+        println!("Map file created");
         Ok(())
     }
 
@@ -169,8 +177,8 @@ pub trait BackupSerialize {
     /// let mut backup_dirs = vec![BackupDir::new(), BackupDir::new()];
     /// backup_dirs[0].root_output = String::from("/home/szymon/Downloads/backup/1");
     /// backup_dirs[1].root_output = String::from("/home/szymon/Downloads/backup/2");
-    /// let entry1 = BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/usr/lib/chromium/bookmarks.html"), is_file: true, hash: generate_hash_meow_hash("/usr/lib/chromium/bookmarks.html").unwrap()};
-    /// let entry2 = BackupEntry {input_path: String::from("/usr/bin/bash"), output_path: String::from("/usr/bin/bash"), is_file: true, hash: generate_hash_meow_hash("/usr/bin/bash").unwrap()};
+    /// let entry1 = BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/usr/lib/chromium/bookmarks.html"), is_file: true, hash: generate_hash_meow_hash("/usr/lib/chromium/bookmarks.html").unwrap(), ..BackupEntry::new()};
+    /// let entry2 = BackupEntry {input_path: String::from("/usr/bin/bash"), output_path: String::from("/usr/bin/bash"), is_file: true, hash: generate_hash_meow_hash("/usr/bin/bash").unwrap(), ..BackupEntry::new()};
     /// backup_dirs[0].backup_entries.push(entry1);
     /// backup_dirs[1].backup_entries.push(entry2);
     /// map.backup_dirs = backup_dirs;
@@ -228,7 +236,7 @@ pub trait BackupSerialize {
 /// use flash_backup::backups::map::backup_entry::BackupEntry;
 /// let mut dir = BackupDir::new();
 /// dir.files = 1;
-/// dir.backup_entries.push(BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/usr/lib/chromium/bookmarks.html"), is_file: true, hash: generate_hash_meow_hash("/usr/lib/chromium/bookmarks.html").unwrap()});
+/// dir.backup_entries.push(BackupEntry {input_path: String::from("/usr/lib/chromium/bookmarks.html"), output_path: String::from("/usr/lib/chromium/bookmarks.html"), is_file: true, hash: generate_hash_meow_hash("/usr/lib/chromium/bookmarks.html").unwrap(), ..BackupEntry::new()});
 /// let result = verify_one_folder(&dir).unwrap();
 /// assert_eq!(result, 0);
 /// ```
@@ -245,7 +253,7 @@ pub fn verify_one_folder(folder: &BackupDir) -> Result<usize, String> {
         if !entry.is_file {
             continue;
         }
-        match generate_hash_meow_hash(&entry.output_path) {
+        match hash_entry(entry) {
             Err(e) => {
                 println!("Can't generate hash of {} to verify integrity: {}", &entry.output_path, e);
                 corrupted += 1;
@@ -276,6 +284,62 @@ pub fn verify_one_folder(folder: &BackupDir) -> Result<usize, String> {
     }
 }
 
+/// Saves original and stored size of every file of map in its entry - stored size of chunked file is sum of sizes of its chunks. Original sizes of files that don't exist are not changed.
+pub fn record_sizes(map: &mut BackupMap) {
+    for entry in map.backup_dirs.iter_mut().flat_map(|x| x.backup_entries.iter_mut()).filter(|x| x.is_file) {
+        // Size read before hashing is kept, so it always matches hash
+        if entry.original_size == 0 && entry.modified == 0 {
+            if let Ok(metadata) = metadata(&entry.input_path) {
+                entry.original_size = metadata.len();
+            }
+        }
+        entry.stored_size = stored_paths(entry).iter().filter_map(|x| metadata(x).ok()).map(|x| x.len()).sum();
+    }
+}
+
+/// Saves map to file in its output folder, in format of map, without changing its metadata. Map is serialized directly to file, without keeping whole serialized map in memory.
+///
+/// If map has cipher set, whole file is encrypted - only header with cipher and parameters of key is left unencrypted.
+///
+/// May return error if map can't be serialized, if file can't be created, or if data can't be saved to file.
+pub fn save_map_to_json(map: &mut BackupMap) -> Result<(), String> {
+    let json_path = format!("{}{}{}", &map.output_folder, &MAIN_SEPARATOR.to_string(), S_MAP);
+    save_map_to_file(map, &json_path)
+}
+
+/// Saves map to file with provided path, in format of map, encrypted if map has cipher set, without changing its metadata other than checksum, which is calculated again.
+///
+/// Map is written to temporary file first, which replaces existing map only after it's completely saved, so existing map is never lost if saving fails.
+///
+/// May return error if map can't be serialized, if file can't be created, or if data can't be saved to file.
+pub fn save_map_to_file(map: &mut BackupMap, json_path: &str) -> Result<(), String> {
+    seal_map(map)?;
+    let partial_path = format!("{}.part", json_path);
+    match File::create(&partial_path) {
+        Err(e) => {
+            let message = format!("Can't create map file {}: {}", &partial_path, e);
+            Err(message)
+        }
+        Ok(json_file) => {
+            let writer = BufWriter::new(json_file);
+            let result = match map.encryption {
+                EncryptionCipher::None => write_map(map, writer).map(|_| ()),
+                _ => EncryptWriter::new(writer, &map.encryption)
+                    .and_then(|writer| write_map(map, writer))
+                    .and_then(|writer| writer.finish().map(|_| ()).map_err(|e| format!("Can't encrypt map: {}", e))),
+            };
+            if let Err(e) = result.and_then(|_| rename(&partial_path, json_path).map_err(|e| format!("Can't replace map file {}: {}", json_path, e))) {
+                let mut message = format!("Can't save serialized map to file: {}", e);
+                if let Err(e) = remove_file(&partial_path) {
+                    message = format!("{} and can't remove temporary map file: {}", message, e);
+                }
+                return Err(message);
+            }
+            Ok(())
+        }
+    }
+}
+
 /// Deletes from BackupDir all entries whose output path doesn't exist.
 ///
 /// Should be used straight before verification.
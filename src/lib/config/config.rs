@@ -1,23 +1,66 @@
-use crate::backups::map::backup_map::BackupMap;
+use crate::backups::map::backup_map::{BackupMap, MAP_FORMAT_VERSION};
 use std::path::Path;
 use std::{fs, io};
 use crate::backups::map::backup_mode::BackupMode;
+use crate::backups::map::archive_format::ArchiveFormat;
+use crate::backups::map::compression_codec::CompressionCodec;
+use crate::backups::map::encryption_cipher::EncryptionCipher;
+use crate::backups::map::map_format::MapFormat;
+use crate::config::output_path::OutputPath;
 use std::io::{BufRead, Write};
 use crate::{S_IGNORE, S_CONFIG, S_SEPARATOR};
+use crate::backups::helpers::pool::{select_drive, drive_label, load_status};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone)]
 #[derive(Serialize, Deserialize)]
 pub struct Config {
     pub input_paths: Vec<String>,
-    pub output_path: String,
+    /// Output folder, or list of output folders - the same backup is copied to each of them, and sources are read and hashed only once.
+    pub output_path: OutputPath,
     pub max_backups: usize,
     pub mode: BackupMode,
+    /// If true, unchanged files in new backup are hardlinked to newest previous backup, instead of copied (only for Multiple mode).
+    #[serde(default)]
+    pub hardlinks: bool,
+    /// Format in which every backup is stored - as folder, or as one archive (only for Multiple mode).
+    #[serde(default)]
+    pub archive: ArchiveFormat,
+    /// Codec used to compress every file copied to backup (not used for backups stored in archive).
+    #[serde(default)]
+    pub compression: CompressionCodec,
+    /// Cipher used to encrypt files and maps of backups - passphrase is asked during every run, or taken from ```FLASH_BACKUP_PASSPHRASE``` environment variable (not used for backups stored in archive).
+    #[serde(default)]
+    pub encryption: EncryptionCipher,
+    /// If true, files and folders in encrypted backups get opaque names, and their real paths are saved only in encrypted map.
+    #[serde(default)]
+    pub obfuscate_names: bool,
+    /// If true, big files are split into chunks, and only changed chunks are copied (only for Multiple and Cloud modes, not used for backups stored in archive).
+    #[serde(default)]
+    pub chunking: bool,
+    /// Format in which maps of backups are saved - JSON, or binary CBOR (optionally compressed with zstd), which is better for backups with millions of files.
+    #[serde(default)]
+    pub map_format: MapFormat,
+    /// Redundancy of parity files created for every stored file (or archive), in percent of its size - ```verify``` repairs damaged files from them. 0 means no parity.
+    #[serde(default)]
+    pub parity: u8,
+    /// If true, backup continues on next volume when output volume is full (only for Multiple mode, not used for backups stored in archive).
+    #[serde(default)]
+    pub spanning: bool,
+    /// Folders on next volumes (for example mount points of other drives), used in order when previous volume is full - if there's no more of them, user is asked for path of next volume.
+    #[serde(default)]
+    pub volumes: Vec<String>,
+    /// Output folders of drives used in rotation - backup is made on drive that is currently connected (its output folder contains marker file), instead of output path. Every drive keeps its own backups.
+    #[serde(default)]
+    pub pool: Vec<String>,
+    /// If true, hashes of source files are saved in cache in user's cache folder (or folder from ```FLASH_BACKUP_CACHE_DIR``` environment variable), so unchanged files aren't hashed again, even without previous backup.
+    #[serde(default)]
+    pub hash_cache: bool,
 }
 
 impl Config {
     pub fn new() -> Config {
-        Config { input_paths: vec![], output_path: String::new(), max_backups: 0, mode: BackupMode::Multiple }
+        Config { input_paths: vec![], output_path: OutputPath::default(), max_backups: 0, mode: BackupMode::Multiple, hardlinks: false, archive: ArchiveFormat::Folder, compression: CompressionCodec::None, encryption: EncryptionCipher::None, obfuscate_names: false, chunking: false, map_format: MapFormat::Json, parity: 0, spanning: false, volumes: vec![], pool: vec![], hash_cache: false }
     }
 
     /// Creates BackupMap struct based on initially processed and checked arguments.
@@ -38,7 +81,21 @@ impl Config {
         } else {
             config = self.create_and_save_config();
         }
-        let mut map = BackupMap { max_backups: config.max_backups, output_folder: config.output_path, input_folders: config.input_paths, backup_mode: config.mode, backup_dirs: vec![], files: 0, folders: 0, timestamp: 0, id: String::new(), ignore_folders: vec![], ignore_extensions: vec![] };
+        let mut map = BackupMap { max_backups: config.max_backups, output_folder: config.output_path.first(), input_folders: config.input_paths, backup_mode: config.mode, backup_dirs: vec![], files: 0, folders: 0, timestamp: 0, id: String::new(), ignore_folders: vec![], ignore_extensions: vec![], last_verified: 0, hardlinks: config.hardlinks, parent_id: String::new(), deleted: vec![], archive: config.archive, compression: config.compression, encryption: config.encryption, obfuscate_names: config.obfuscate_names, name_key: String::new(), chunking: config.chunking, map_format: config.map_format, format_version: MAP_FORMAT_VERSION, parity: config.parity, spanning: config.spanning, volumes: config.volumes, volume: 0, last_volume: false, mirrors: config.output_path.paths().into_iter().skip(1).collect(), paranoid: false, pooled: false, hash_cache: config.hash_cache, checksum: String::new() };
+
+        if !config.pool.is_empty() {
+            if !map.mirrors.is_empty() {
+                panic!("Backup to drives from pool can't be copied to many output folders - remove pool or other output paths from config. Program will stop");
+            }
+            match select_drive(&config.pool, &load_status()) {
+                Ok(folder) => {
+                    println!("Using drive {} from pool", drive_label(&folder));
+                    map.output_folder = folder;
+                    map.pooled = true;
+                }
+                Err(e) => panic!("Couldn't choose drive from pool: {}. Program will stop", e)
+            }
+        }
 
         let ignore_path;
         if custom_ignore_path.is_empty() {
@@ -98,18 +155,47 @@ impl Config {
     /// Panics if data provided by user isn't valid.
     pub fn create_and_save_config(&mut self) -> Config {
         println!("Couldn't find config file, create one:");
-        let mut config = Config {output_path: String::new(), input_paths: vec![], max_backups: 0, mode: BackupMode::Multiple };
+        let mut config = Config {output_path: OutputPath::default(), input_paths: vec![], max_backups: 0, mode: BackupMode::Multiple, hardlinks: false, archive: ArchiveFormat::Folder, compression: CompressionCodec::None, encryption: EncryptionCipher::None, obfuscate_names: false, chunking: false, map_format: MapFormat::Json, parity: 0, spanning: false, volumes: vec![], pool: vec![], hash_cache: false };
         config.input_paths = self.get_input_paths_from_user();
-        config.output_path = self.get_output_path_from_user();
+        let mut output_paths = vec![self.get_output_path_from_user()];
+        while self.get_yes_no_from_user("Would you like to copy the same backup to another destination folder? (y/n):") {
+            output_paths.push(self.get_output_path_from_user());
+        }
+        config.output_path = match output_paths.len() {
+            1 => OutputPath::One(output_paths.remove(0)),
+            _ => OutputPath::Many(output_paths),
+        };
         config.mode = self.get_mode_from_user();
         match config.mode {
             BackupMode::Cloud => {
                 config.max_backups = 1;
+                config.chunking = self.get_yes_no_from_user("Do you want to split big files into chunks, to copy only their changed parts (y/n)?:");
             }
             BackupMode::Multiple => {
                 config.max_backups = self.get_max_backups_amount_from_user();
+                config.archive = self.get_archive_format_from_user();
+                if config.archive == ArchiveFormat::Folder && config.output_path.paths().len() == 1 {
+                    config.spanning = self.get_yes_no_from_user("Do you want to continue backup on next drive when output drive is full (y/n)?:");
+                }
+                if config.archive == ArchiveFormat::Folder && !config.spanning {
+                    config.hardlinks = self.get_yes_no_from_user("Do you want to link unchanged files to previous backup, to save space (y/n)?:");
+                    config.chunking = self.get_yes_no_from_user("Do you want to split big files into chunks, to copy only their changed parts (y/n)?:");
+                }
+            }
+            BackupMode::Dedup | BackupMode::Incremental => {
+                config.max_backups = self.get_max_backups_amount_from_user();
+            }
+        }
+        if config.archive == ArchiveFormat::Folder {
+            config.compression = self.get_compression_from_user();
+            config.encryption = self.get_encryption_from_user();
+            if config.encryption != EncryptionCipher::None {
+                config.obfuscate_names = self.get_yes_no_from_user("Do you want to hide names of files and folders in backup (y/n)?:");
             }
         }
+        config.hash_cache = self.get_yes_no_from_user("Do you want to keep cache of hashes of source files, so unchanged files aren't hashed again (y/n)?:");
+        config.parity = self.get_parity_from_user();
+        config.map_format = self.get_map_format_from_user();
         if let Err(_) = self.save_config_to_json(&config) {
             println!("Couldn't write config to file, config won't be saved");
         }
@@ -256,12 +342,142 @@ impl Config {
         1
     }
 
-    /// Asks user about mode of backup (m/multiple or c/cloud), checks if it's valid, if yes, amount is returned.
+    /// Asks user provided yes/no question, returns true if user answers "y" or "yes", and false if user answers "n" or "no".
+    ///
+    /// If user writes invalid string, he's asked to do it again.
+    pub fn get_yes_no_from_user(&mut self, question: &str) -> bool {
+        let mut answer = String::new();
+        println!("{}", question);
+        if io::stdin().read_line(&mut answer).is_err() {
+            println!("Error reading your answer, asking again...");
+            return self.get_yes_no_from_user(question);
+        }
+
+        match answer.trim() {
+            "y" | "yes" => true,
+            "n" | "no" => false,
+            _ => {
+                println!("Wrong input provided, please write 'y' or 'n'");
+                self.get_yes_no_from_user(question)
+            }
+        }
+    }
+
+    /// Asks user about format of every backup (f/folder, t/tar, z/zst or zip), checks if it's valid, if yes, format is returned.
+    ///
+    /// If user writes invalid string, he's asked to do it again.
+    pub fn get_archive_format_from_user(&mut self) -> ArchiveFormat {
+        let mut format = String::new();
+        println!("Do you want to store every backup as folder, tar archive, compressed tar.zst archive or zip archive (f/t/z/zip)?:");
+        if io::stdin().read_line(&mut format).is_err() {
+            println!("Error reading input for format, asking again...");
+            return self.get_archive_format_from_user();
+        }
+
+        match format.trim() {
+            "f" | "folder" => ArchiveFormat::Folder,
+            "t" | "tar" => ArchiveFormat::Tar,
+            "z" | "zst" => ArchiveFormat::TarZst,
+            "zip" => ArchiveFormat::Zip,
+            _ => {
+                println!("Wrong input provided, please write 'f' for folder, 't' for tar archive, 'z' for tar.zst archive or 'zip' for zip archive");
+                self.get_archive_format_from_user()
+            }
+        }
+    }
+
+    /// Asks user about codec used to compress files (n/none, z/zstd or g/gzip), checks if it's valid, if yes, codec is returned.
+    ///
+    /// If user writes invalid string, he's asked to do it again.
+    pub fn get_compression_from_user(&mut self) -> CompressionCodec {
+        let mut codec = String::new();
+        println!("Do you want to compress files with zstd, gzip, or not compress them at all (z/g/n)?:");
+        if io::stdin().read_line(&mut codec).is_err() {
+            println!("Error reading input for compression, asking again...");
+            return self.get_compression_from_user();
+        }
+
+        match codec.trim() {
+            "n" | "none" => CompressionCodec::None,
+            "z" | "zstd" => CompressionCodec::Zstd,
+            "g" | "gzip" => CompressionCodec::Gzip,
+            _ => {
+                println!("Wrong input provided, please write 'z' for zstd, 'g' for gzip or 'n' for no compression");
+                self.get_compression_from_user()
+            }
+        }
+    }
+
+    /// Asks user about redundancy of parity files in percent (0-100, 0 means no parity), checks if it's valid, if yes, it's returned.
+    ///
+    /// If user writes invalid string, he's asked to do it again.
+    pub fn get_parity_from_user(&mut self) -> u8 {
+        let mut percent = String::new();
+        println!("How much parity data do you want to keep to repair damaged files, in percent of their size (0 - none, up to 100)?:");
+        if io::stdin().read_line(&mut percent).is_err() {
+            println!("Error reading input for parity, asking again...");
+            return self.get_parity_from_user();
+        }
+
+        match percent.trim().parse::<u8>() {
+            Ok(percent) if percent <= 100 => percent,
+            _ => {
+                println!("Wrong input provided, please write number from 0 to 100");
+                self.get_parity_from_user()
+            }
+        }
+    }
+
+    /// Asks user about format of maps of backups (j/json, c/cbor or z/zst), checks if it's valid, if yes, format is returned.
+    ///
+    /// If user writes invalid string, he's asked to do it again.
+    pub fn get_map_format_from_user(&mut self) -> MapFormat {
+        let mut format = String::new();
+        println!("Do you want to save maps of backups as JSON, binary CBOR, or binary CBOR compressed with zstd - best for millions of files (j/c/z)?:");
+        if io::stdin().read_line(&mut format).is_err() {
+            println!("Error reading input for format of maps, asking again...");
+            return self.get_map_format_from_user();
+        }
+
+        match format.trim() {
+            "j" | "json" => MapFormat::Json,
+            "c" | "cbor" => MapFormat::Cbor,
+            "z" | "zst" => MapFormat::CborZst,
+            _ => {
+                println!("Wrong input provided, please write 'j' for JSON, 'c' for CBOR or 'z' for CBOR compressed with zstd");
+                self.get_map_format_from_user()
+            }
+        }
+    }
+
+    /// Asks user about cipher used to encrypt backups (n/none, c/chacha or a/aes), checks if it's valid, if yes, cipher is returned.
+    ///
+    /// If user writes invalid string, he's asked to do it again.
+    pub fn get_encryption_from_user(&mut self) -> EncryptionCipher {
+        let mut cipher = String::new();
+        println!("Do you want to encrypt backups with passphrase, using ChaCha20-Poly1305 or AES-256-GCM (n/c/a)?:");
+        if io::stdin().read_line(&mut cipher).is_err() {
+            println!("Error reading input for encryption, asking again...");
+            return self.get_encryption_from_user();
+        }
+
+        match cipher.trim() {
+            "n" | "none" => EncryptionCipher::None,
+            "c" | "chacha" => EncryptionCipher::ChaCha20Poly1305,
+            "a" | "aes" => EncryptionCipher::Aes256Gcm,
+            _ => {
+                println!("Wrong input provided, please write 'c' for ChaCha20-Poly1305, 'a' for AES-256-GCM or 'n' for no encryption");
+                self.get_encryption_from_user()
+            }
+        }
+    }
+
+    /// Asks user about mode of backup (m/multiple, c/cloud, d/dedup or i/incremental), checks if it's valid, if yes, amount is returned.
     ///
     /// If user writes invalid string, he's asked to do it again.
     pub fn get_mode_from_user(&mut self) -> BackupMode {
         let mut mode = String::new();
-        println!("Do you want to use multiple or cloud mode (m/c)?:");
+        println!("Do you want to use multiple, cloud, dedup or incremental mode (m/c/d/i)?:");
         if let Err(_) = io::stdin().read_line(&mut mode) {
             println!("Error reading input for mode, asking again...");
             self.get_mode_from_user();
@@ -270,8 +486,10 @@ impl Config {
         match mode.trim() {
             "m" | "multiple" => BackupMode::Multiple,
             "c" | "cloud" => BackupMode::Cloud,
+            "d" | "dedup" => BackupMode::Dedup,
+            "i" | "incremental" => BackupMode::Incremental,
             _ => {
-                println!("Wrong input provided, please write 'm' for multiple mode or 'c' for cloud mode");
+                println!("Wrong input provided, please write 'm' for multiple mode, 'c' for cloud mode, 'd' for dedup mode or 'i' for incremental mode");
                 return self.get_mode_from_user();
             }
         }
@@ -1,2 +1,3 @@
 pub mod modes;
-pub mod config;
\ No newline at end of file
+pub mod config;
+pub mod output_path;
\ No newline at end of file
@@ -1,16 +1,20 @@
 use std::path::Path;
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches, SubCommand};
 use crate::config::config::Config;
 use crate::backups::map::backup_map::BackupMap;
+use crate::backups::helpers::dirs::{find_backup, find_previous_backups};
+use crate::backups::actions::backup_restore::{RestoreOptions, ConflictPolicy};
+use crate::backups::modes::backup_incremental::resolve_map;
+use crate::backups::helpers::pool::{select_drive, load_status};
 
-/// Gets program arguments, checks them and then returns initially filled ```BackupMap``` based on them.
+/// Reads program arguments and returns them for further processing.
 ///
 /// Possible arguments are: ```-n``` / ```--new``` for confirming that user wants to create new config and overwrite existing one (with possible values 0 or 1),
 /// ```-c``` / ```--config``` with path as value, to load config file from this path, or ```-i``` / ```--ignore```, which does the same thing with ignore file.
 ///
-/// Function may panic if arguments are invalid.
-pub fn args_to_map() -> BackupMap {
-    let matches = App::new("Flash Backup")
+/// Possible subcommands are: ```restore```, which copies files from backup back to their original locations. If no subcommand is provided, backup is made.
+pub fn get_args() -> ArgMatches<'static> {
+    App::new("Flash Backup")
         .version("0.9")
         .author("Szymon Trybała <szymon.trybala@protonmail.com")
         .about("Simple app to help copying set of folders to another folder, like for example flash drive")
@@ -29,15 +33,305 @@ pub fn args_to_map() -> BackupMap {
             .long(".ignore")
             .value_name("IGNORE")
             .help("Loads your custom .ignore file. If not provided program will copy every folder and file from source directories"))
-        .get_matches();
+        .arg(Arg::with_name("paranoid")
+            .long("paranoid")
+            .help("Hashes every source file, even if its size and time of modification didn't change since previous backup"))
+        .subcommand(SubCommand::with_name("restore")
+            .about("Restores files from backup to their original locations")
+            .arg(Arg::with_name("from")
+                .short("f")
+                .long("from")
+                .value_name("FROM")
+                .help("Folder with backups. If not provided, output folder from config is used"))
+            .arg(Arg::with_name("id")
+                .long("id")
+                .value_name("ID")
+                .help("Id of backup to restore. If not provided, newest backup is restored"))
+            .arg(Arg::with_name("to")
+                .short("t")
+                .long("to")
+                .value_name("TO")
+                .help("Restores every folder inside provided folder, instead of its original location"))
+            .arg(Arg::with_name("remap")
+                .short("r")
+                .long("remap")
+                .value_name("OLD=NEW")
+                .multiple(true)
+                .number_of_values(1)
+                .help("Replaces beginning of original folder location, for example '-r /home/olduser=/home/newuser'. Can be used multiple times"))
+            .arg(Arg::with_name("conflict")
+                .long("conflict")
+                .value_name("CONFLICT")
+                .possible_values(&["overwrite", "skip", "keep-newer", "rename"])
+                .help("What to do with files that already exist: overwrite (default), skip, keep-newer or rename")))
+        .subcommand(SubCommand::with_name("versions")
+            .about("Lists all versions of file found in backups, and restores chosen one")
+            .arg(Arg::with_name("path")
+                .value_name("PATH")
+                .required(true)
+                .help("Original path of file"))
+            .arg(Arg::with_name("from")
+                .short("f")
+                .long("from")
+                .value_name("FROM")
+                .help("Folder with backups. If not provided, output folder from config is used"))
+            .arg(Arg::with_name("version")
+                .long("version")
+                .value_name("VERSION")
+                .help("Number of version to restore. If not provided, program will ask for it"))
+            .arg(Arg::with_name("to")
+                .short("t")
+                .long("to")
+                .value_name("TO")
+                .help("Path to which chosen version will be restored. If not provided, versions are only listed")))
+        .subcommand(SubCommand::with_name("list")
+            .about("Lists all backups in folder with backups")
+            .arg(Arg::with_name("from")
+                .short("f")
+                .long("from")
+                .value_name("FROM")
+                .help("Folder with backups. If not provided, output folder from config is used"))
+            .arg(Arg::with_name("json")
+                .long("json")
+                .help("Prints list as JSON")))
+        .subcommand(SubCommand::with_name("show")
+            .about("Shows details of one backup")
+            .arg(Arg::with_name("id")
+                .value_name("ID")
+                .required(true)
+                .help("Id of backup"))
+            .arg(Arg::with_name("from")
+                .short("f")
+                .long("from")
+                .value_name("FROM")
+                .help("Folder with backups. If not provided, output folder from config is used"))
+            .arg(Arg::with_name("json")
+                .long("json")
+                .help("Prints details as JSON")))
+        .subcommand(SubCommand::with_name("diff")
+            .about("Shows files added, removed, modified and moved between two backups")
+            .arg(Arg::with_name("old")
+                .value_name("OLD_ID")
+                .required(true)
+                .help("Id of older backup"))
+            .arg(Arg::with_name("new")
+                .value_name("NEW_ID")
+                .required(true)
+                .help("Id of newer backup"))
+            .arg(Arg::with_name("from")
+                .short("f")
+                .long("from")
+                .value_name("FROM")
+                .help("Folder with backups. If not provided, output folder from config is used"))
+            .arg(Arg::with_name("json")
+                .long("json")
+                .help("Prints differences as JSON")))
+        .subcommand(SubCommand::with_name("verify")
+            .about("Checks every file of backups against hashes saved in maps, and reports missing, corrupted and extra files")
+            .arg(Arg::with_name("id")
+                .long("id")
+                .value_name("ID")
+                .help("Id of backup to verify. If not provided, all backups are verified"))
+            .arg(Arg::with_name("from")
+                .short("f")
+                .long("from")
+                .value_name("FROM")
+                .help("Folder with backups. If not provided, output folder from config is used"))
+            .arg(Arg::with_name("save")
+                .long("save")
+                .help("Saves time of verification in maps"))
+            .arg(Arg::with_name("json")
+                .long("json")
+                .help("Prints reports as JSON")))
+        .subcommand(SubCommand::with_name("repair")
+            .about("Copies again from source every missing or corrupted file of backups, if source wasn't modified")
+            .arg(Arg::with_name("id")
+                .long("id")
+                .value_name("ID")
+                .help("Id of backup to repair. If not provided, all backups are repaired"))
+            .arg(Arg::with_name("from")
+                .short("f")
+                .long("from")
+                .value_name("FROM")
+                .help("Folder with backups. If not provided, output folder from config is used"))
+            .arg(Arg::with_name("json")
+                .long("json")
+                .help("Prints reports as JSON")))
+        .subcommand(SubCommand::with_name("migrate")
+            .about("Rewrites maps of all backups in current format version, so they can be used by this version of program without migrating them every time")
+            .arg(Arg::with_name("from")
+                .short("f")
+                .long("from")
+                .value_name("FROM")
+                .help("Folder with backups. If not provided, output folder from config is used"))
+            .arg(Arg::with_name("json")
+                .long("json")
+                .help("Prints report as JSON")))
+        .subcommand(SubCommand::with_name("status")
+            .about("Shows when every output folder, or every drive from pool, was updated, and warns about ones that weren't updated for too long")
+            .arg(Arg::with_name("days")
+                .short("d")
+                .long("days")
+                .value_name("DAYS")
+                .help("Number of days after which output folder is reported as not updated. If not provided, 7 days are used"))
+            .arg(Arg::with_name("json")
+                .long("json")
+                .help("Prints report as JSON")))
+        .get_matches()
+}
 
+/// Checks arguments, then returns initially filled ```BackupMap``` based on them.
+///
+/// Function may panic if arguments are invalid.
+pub fn args_to_map(matches: &ArgMatches) -> BackupMap {
     let custom_config_path = matches.value_of("config").unwrap_or("");
     let custom_ignore_path = matches.value_of(".ignore").unwrap_or("");
     let run_new_config = matches.value_of("new").unwrap_or("0");
-    let map = check_and_send_args(run_new_config, custom_config_path, custom_ignore_path);
+    let mut map = check_and_send_args(run_new_config, custom_config_path, custom_ignore_path);
+    map.paranoid = matches.is_present("paranoid");
     return map;
 }
 
+/// Finds map of backup chosen with ```id``` argument of subcommand, or newest backup if it isn't provided.
+///
+/// Panics if folder with backups can't be found, or there is no backup with provided id.
+pub fn args_to_backup_map(matches: &ArgMatches, subcommand_matches: &ArgMatches) -> BackupMap {
+    let output_folder = get_output_folder(matches, subcommand_matches);
+    let id = subcommand_matches.value_of("id").unwrap_or("");
+    match find_backup(&output_folder, id) {
+        Ok(map) => map,
+        Err(e) => panic!("Couldn't find backup: {}. Program will stop", e)
+    }
+}
+
+/// Finds map of backup chosen with ```id``` argument of subcommand, or newest backup if it isn't provided, with complete content of backup - backups of incremental mode are resolved with all previous backups in their chain.
+///
+/// Panics if folder with backups can't be found, there is no backup with provided id, or its chain can't be resolved.
+pub fn args_to_resolved_map(matches: &ArgMatches, subcommand_matches: &ArgMatches) -> BackupMap {
+    let map = args_to_backup_map(matches, subcommand_matches);
+    match resolve_map(map, &args_to_maps(matches, subcommand_matches)) {
+        Ok(map) => map,
+        Err(e) => panic!("Couldn't resolve chain of backups: {}. Program will stop", e)
+    }
+}
+
+/// Finds map of backup chosen with ```id``` argument of subcommand, or maps of all backups if it isn't provided.
+///
+/// Panics if folder with backups can't be found, or there is no backup with provided id.
+pub fn args_to_chosen_maps(matches: &ArgMatches, subcommand_matches: &ArgMatches) -> Vec<BackupMap> {
+    match subcommand_matches.is_present("id") {
+        true => vec![args_to_backup_map(matches, subcommand_matches)],
+        false => args_to_maps(matches, subcommand_matches),
+    }
+}
+
+/// Finds maps of two backups compared by ```diff``` subcommand, returns them as tuple of older and newer one. Backups of incremental mode are resolved with all previous backups in their chain.
+///
+/// Panics if folder with backups can't be found, there is no backup with any of provided ids, or chain of any of them can't be resolved.
+pub fn args_to_diff_maps(matches: &ArgMatches, diff_matches: &ArgMatches) -> (BackupMap, BackupMap) {
+    let output_folder = get_output_folder(matches, diff_matches);
+    let old = find_backup(&output_folder, diff_matches.value_of("old").unwrap_or(""));
+    let new = find_backup(&output_folder, diff_matches.value_of("new").unwrap_or(""));
+    let (old, new) = match (old, new) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(e), _) | (_, Err(e)) => panic!("Couldn't find backup: {}. Program will stop", e)
+    };
+    let maps = args_to_maps(matches, diff_matches);
+    match (resolve_map(old, &maps), resolve_map(new, &maps)) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(e), _) | (_, Err(e)) => panic!("Couldn't resolve chain of backups: {}. Program will stop", e)
+    }
+}
+
+/// Finds maps of all backups in folder with backups, based on arguments of subcommand.
+///
+/// Panics if folder with backups can't be found or searched.
+pub fn args_to_maps(matches: &ArgMatches, subcommand_matches: &ArgMatches) -> Vec<BackupMap> {
+    let output_folder = get_output_folder(matches, subcommand_matches);
+    match find_previous_backups(&output_folder) {
+        Ok(maps) => maps,
+        Err(e) => panic!("Couldn't find backups in {}: {}. Program will stop", output_folder, e)
+    }
+}
+
+/// Returns number of version chosen in arguments of ```versions``` subcommand, or 0 if it isn't provided.
+///
+/// Panics if version isn't valid number.
+pub fn args_to_version(versions_matches: &ArgMatches) -> usize {
+    match versions_matches.value_of("version") {
+        None => 0,
+        Some(version) => match version.trim().parse::<usize>() {
+            Ok(version) if version > 0 => version,
+            _ => panic!("Argument 'version' isn't valid number, provide number of version starting from 1. Program will stop")
+        }
+    }
+}
+
+/// Creates ```RestoreOptions``` based on arguments of ```restore``` subcommand.
+///
+/// Panics if any remap argument doesn't contain '=' sign.
+pub fn args_to_restore_options(restore_matches: &ArgMatches) -> RestoreOptions {
+    let mut remaps = vec![];
+    if let Some(remap_args) = restore_matches.values_of("remap") {
+        for remap in remap_args {
+            match remap.find('=') {
+                Some(index) => remaps.push((String::from(&remap[..index]), String::from(&remap[index + 1..]))),
+                None => panic!("Remap {} isn't valid, use OLD=NEW syntax. Program will stop", remap)
+            }
+        }
+    }
+    let conflict = match restore_matches.value_of("conflict").unwrap_or("overwrite") {
+        "skip" => ConflictPolicy::Skip,
+        "keep-newer" => ConflictPolicy::KeepNewer,
+        "rename" => ConflictPolicy::Rename,
+        _ => ConflictPolicy::Overwrite,
+    };
+    RestoreOptions { target_root: String::from(restore_matches.value_of("to").unwrap_or("")), remaps, conflict }
+}
+
+/// Returns folder with backups - from ```--from``` argument of subcommand if it's provided, or from existing config file.
+///
+/// Panics if argument isn't provided and config file can't be loaded.
+pub fn get_output_folder(matches: &ArgMatches, subcommand_matches: &ArgMatches) -> String {
+    if let Some(path) = subcommand_matches.value_of("from") {
+        return String::from(path);
+    }
+    let custom_config_path = matches.value_of("config").unwrap_or("");
+    match Config::new().load_existing_config(custom_config_path) {
+        Ok(config) if !config.pool.is_empty() => match select_drive(&config.pool, &load_status()) {
+            Ok(folder) => folder,
+            Err(e) => panic!("Couldn't find folder with backups, provide it with --from argument: {}. Program will stop", e)
+        },
+        Ok(config) => config.output_path.first(),
+        Err(e) => panic!("Couldn't find folder with backups, provide it with --from argument: {}. Program will stop", e)
+    }
+}
+
+/// Returns output folders checked by ```status``` subcommand, from existing config file - drives from pool, or all output folders if pool isn't configured - with true if they are pool.
+///
+/// Panics if config file can't be loaded.
+pub fn args_to_status_folders(matches: &ArgMatches) -> (Vec<String>, bool) {
+    let custom_config_path = matches.value_of("config").unwrap_or("");
+    match Config::new().load_existing_config(custom_config_path) {
+        Ok(config) if !config.pool.is_empty() => (config.pool, true),
+        Ok(config) => (config.output_path.paths(), false),
+        Err(e) => panic!("Couldn't load config with output folders: {}. Program will stop", e)
+    }
+}
+
+/// Returns number of days from ```days``` argument of ```status``` subcommand, or 7 if it isn't provided.
+///
+/// Panics if argument isn't valid number.
+pub fn args_to_days(status_matches: &ArgMatches) -> u64 {
+    match status_matches.value_of("days") {
+        None => 7,
+        Some(days) => match days.trim().parse::<u64>() {
+            Ok(days) => days,
+            _ => panic!("Argument 'days' isn't valid number. Program will stop")
+        }
+    }
+}
+
 /// Checks integrity of arguments, if every one of them is ok it gets map from ```Config``` struct and returns it.
 ///
 /// Panics if arguments are invalid - ```run_new_config``` has to be 0 or 1 (in other cases it will be changed to 0), and ```config_path``` and ```ignore_path``` must exist and be a file, if those strings are not empty.
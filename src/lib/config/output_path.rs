@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_path() {
+        let one: OutputPath = serde_json::from_str("\"/backup\"").unwrap();
+        assert_eq!(one, OutputPath::One(String::from("/backup")));
+        assert_eq!(one.paths(), vec![String::from("/backup")]);
+        let many: OutputPath = serde_json::from_str("[\"/home_drive\", \"/offsite_drive\"]").unwrap();
+        assert_eq!(many.paths().len(), 2);
+        assert_eq!(many.first(), "/home_drive");
+        assert_eq!(serde_json::to_string(&one).unwrap(), "\"/backup\"");
+    }
+}
+
+/// Enum to store output folder of config - one folder, or list of folders to which the same backup is copied in one run (for example drive at home and offsite drive).
+///
+/// Serialized as plain string or list of strings, so older configs with one output folder still work.
+#[derive(Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug)]
+#[serde(untagged)]
+pub enum OutputPath {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OutputPath {
+    /// Returns all output folders, in order.
+    pub fn paths(&self) -> Vec<String> {
+        match self {
+            OutputPath::One(path) => vec![path.clone()],
+            OutputPath::Many(paths) => paths.clone(),
+        }
+    }
+
+    /// Returns first output folder - the only one, if there's one - or empty string if list is empty.
+    pub fn first(&self) -> String {
+        self.paths().into_iter().next().unwrap_or_default()
+    }
+}
+
+impl Default for OutputPath {
+    fn default() -> OutputPath {
+        OutputPath::One(String::new())
+    }
+}
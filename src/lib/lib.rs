@@ -1,8 +1,26 @@
 use crate::backups::map::backup_map::BackupMap;
 use crate::backups::map::backup_mode::BackupMode;
+use crate::backups::map::archive_format::ArchiveFormat;
+use crate::backups::map::encryption_cipher::EncryptionCipher;
+use crate::backups::helpers::encryption::passphrase;
+use crate::backups::helpers::volumes::{next_missing_volume, ask_for_volume};
+use crate::backups::helpers::dirs::{find_previous_backups, find_backup};
+use crate::backups::helpers::pool::{record_backup, status_path, load_status};
+use crate::backups::actions::backup_status::pool_status;
 use crate::backups::modes::backup_multiple::BackupMultiple;
 use crate::backups::traits::backup::Backup;
+use crate::backups::traits::backup_input::BackupInput;
 use crate::backups::modes::backup_cloud::BackupCloud;
+use crate::backups::modes::backup_dedup::BackupDedup;
+use crate::backups::modes::backup_incremental::BackupIncremental;
+use crate::backups::actions::backup_restore::{BackupRestore, RestoreOptions};
+use crate::backups::actions::backup_diff::diff_maps;
+use crate::backups::actions::backup_list::{list_backups, show_backup};
+use crate::backups::actions::backup_repair::{repair_map, RepairReport};
+use crate::backups::actions::backup_migrate::migrate_folder;
+use crate::backups::actions::backup_verify::{verify_map, save_verification, VerifyReport};
+use crate::backups::actions::backup_versions::{find_file_versions, print_file_versions, get_version_from_user, restore_file_version};
+use std::panic::{self, AssertUnwindSafe};
 
 pub mod backups;
 pub mod config;
@@ -19,8 +37,101 @@ pub static S_MAP: &str = ".map.json";
 pub static S_CONFIG: &str = ".config.json";
 /// Default name of file containing file with ignores.
 pub static S_IGNORE: &str = ".ignore";
+/// Default name of folder containing objects in dedup mode.
+pub static S_OBJECTS: &str = ".objects";
+/// Name of folder with chunks of big files, shared by all backups in output folder.
+pub static S_CHUNKS: &str = ".chunks";
+/// Default name of archive containing backup, without extension.
+pub static S_ARCHIVE: &str = "backup";
+/// Name of environment variable containing passphrase of encrypted backups.
+pub static S_PASSPHRASE: &str = "FLASH_BACKUP_PASSPHRASE";
+/// Name of environment variable containing key used to authenticate maps.
+pub static S_MAP_KEY: &str = "FLASH_BACKUP_MAP_KEY";
+/// Name of marker file in output folder of every drive from pool - drive is connected only if its output folder contains it. Its content is name of drive.
+pub static S_POOL_MARKER: &str = ".flash_backup_pool";
+/// Name of environment variable containing folder with cache of hashes of source files - if it isn't set, user's cache folder is used.
+pub static S_CACHE_DIR: &str = "FLASH_BACKUP_CACHE_DIR";
+/// Name of file in cache folder containing times of last backups on every drive from pool, used to rotate drives and by status command.
+pub static S_STATUS: &str = "status.json";
 
-pub fn make_backup(map: BackupMap) {
+pub fn make_backup(mut map: BackupMap) {
+    if map.encryption != EncryptionCipher::None && map.archive != ArchiveFormat::Folder {
+        panic!("Backups stored in archive can't be encrypted - turn off encryption or archive in config. Program will stop");
+    }
+    if map.obfuscate_names && (map.encryption == EncryptionCipher::None || map.archive != ArchiveFormat::Folder) {
+        println!("Names of files can be hidden only in encrypted backups stored in folders, they won't be hidden");
+        map.obfuscate_names = false;
+    }
+    if map.chunking && (map.backup_mode == BackupMode::Dedup || map.backup_mode == BackupMode::Incremental || map.archive != ArchiveFormat::Folder) {
+        println!("Big files can be split into chunks only in multiple and cloud modes, in backups stored in folders - they won't be split");
+        map.chunking = false;
+    }
+    if map.spanning && (map.backup_mode != BackupMode::Multiple || map.archive != ArchiveFormat::Folder) {
+        println!("Backup can continue on next volume only in multiple mode, in backups stored in folders - it won't be split into volumes");
+        map.spanning = false;
+    }
+    if map.spanning && !map.mirrors.is_empty() {
+        println!("Backup copied to many output folders can't continue on next volume - it won't be split into volumes");
+        map.spanning = false;
+    }
+    if map.spanning && (map.hardlinks || map.chunking) {
+        println!("Backup split into volumes can't link unchanged files or split big files into chunks, all files will be copied");
+        map.hardlinks = false;
+        map.chunking = false;
+    }
+    // Passphrase is confirmed before anything is done, so backup can't be encrypted with mistyped one
+    if map.encryption != EncryptionCipher::None && map.archive == ArchiveFormat::Folder {
+        if let Err(e) = passphrase(true) {
+            panic!("Couldn't get passphrase to encrypt backup: {}. Program will stop", e);
+        }
+    }
+    if map.mirrors.is_empty() {
+        let output_folder = map.output_folder.clone();
+        let pooled = map.pooled;
+        run_backup(map);
+        if pooled {
+            if let Err(e) = status_path().and_then(|path| record_backup(&path, &output_folder)) {
+                println!("{}", e);
+            }
+        }
+        return;
+    }
+
+    // Sources are read and hashed once, then the same input maps are copied to every output folder
+    let mut output_folders = vec![map.output_folder.clone()];
+    output_folders.append(&mut map.mirrors);
+    println!("Copying backup to {} output folders...", output_folders.len());
+    // Hashes of unchanged files are reused from backups in first output folder
+    let previous_maps: Vec<BackupMap> = find_previous_backups(&output_folders[0]).unwrap_or_default().into_iter().filter(|x| x.backup_mode == map.backup_mode).collect();
+    map.backup_dirs = BackupMultiple::create_input_maps_with_hashes(&map, &previous_maps.iter().collect::<Vec<_>>());
+    let mut failed = vec![];
+    for output_folder in output_folders.iter() {
+        println!("Backing up to {}...", output_folder);
+        let mirror = BackupMap { output_folder: output_folder.clone(), ..map.clone() };
+        let mode = mirror.backup_mode.clone();
+        // Failure of one output folder (for example disconnected drive) doesn't stop backup to others
+        if panic::catch_unwind(AssertUnwindSafe(|| run_backup(mirror))).is_err() {
+            println!("Backup to {} failed, continuing with next output folder", output_folder);
+            failed.push(output_folder.clone());
+            continue;
+        }
+        // Cloud and incremental modes verify copied files themselves
+        if mode == BackupMode::Multiple || mode == BackupMode::Dedup {
+            match find_backup(output_folder, "") {
+                Ok(backup) => println!("{}", verify_map(&backup).to_text()),
+                Err(e) => println!("Couldn't verify backup in {}: {}", output_folder, e),
+            }
+        }
+    }
+    if !failed.is_empty() {
+        panic!("Backup failed in {} of {} output folders: {}. Program will stop", failed.len(), output_folders.len(), failed.join(", "));
+    }
+}
+
+/// Runs backup of provided map in its mode, to its output folder.
+///
+/// Panics if backup can't be done.
+fn run_backup(map: BackupMap) {
     match map.backup_mode {
         BackupMode::Multiple => {
             let mut multiple = BackupMultiple::new(map);
@@ -33,7 +144,196 @@ pub fn make_backup(map: BackupMap) {
             if let Err(e) = cloud.backup() {
                 panic!("Couldn't backup: {}. Program will stop", e);
             }        }
+        BackupMode::Dedup => {
+            let mut dedup = BackupDedup::new(map);
+            if let Err(e) = dedup.backup() {
+                panic!("Couldn't backup: {}. Program will stop", e);
+            }
+        }
+        BackupMode::Incremental => {
+            let mut incremental = BackupIncremental::new(map);
+            if let Err(e) = incremental.backup() {
+                panic!("Couldn't backup: {}. Program will stop", e);
+            }
+        }
+    }
+}
+
+/// Restores provided backup map, copying every file from backup back to its original location, or location changed by options.
+///
+/// Backup split into volumes is restored part after part - user is asked for path to folder with backups on every volume that wasn't restored yet, until all of them are restored or user writes nothing.
+///
+/// Panics if restore can't be done.
+pub fn make_restore(map: BackupMap, options: RestoreOptions) {
+    let id = map.id.clone();
+    let mut restored = vec![];
+    let mut map = map;
+    loop {
+        let restore = BackupRestore::new(map.clone(), options.clone());
+        if let Err(e) = restore.restore() {
+            panic!("Couldn't restore: {}. Program will stop", e);
+        }
+        restored.push(map);
+        let volume = match next_missing_volume(&restored) {
+            Some(volume) => volume,
+            None => break,
+        };
+        // Next part is searched until user provides folder that contains it
+        map = loop {
+            let folder = ask_for_volume(&format!("Backup {} is split into volumes, connect volume {}", &id, volume));
+            if folder.is_empty() {
+                println!("Volume {} of backup {} wasn't restored", volume, &id);
+                return;
+            }
+            match find_previous_backups(&folder).map(|x| x.into_iter().find(|x| x.id == id && x.volume == volume)) {
+                Ok(Some(part)) => break part,
+                Ok(None) => println!("No volume {} of backup {} found in {}", volume, &id, folder),
+                Err(e) => println!("Couldn't find backups in {}: {}", folder, e),
+            }
+        };
+    }
+}
+
+/// Prints all distinct versions of file with provided input path, found in provided maps. If destination isn't empty, chosen version is restored to it.
+///
+/// Version is numbered from 1, from oldest to newest. If it's equal to 0, user is asked to choose it.
+///
+/// Panics if file isn't found in any backup, version doesn't exist or it can't be restored.
+pub fn make_file_restore(maps: Vec<BackupMap>, input_path: &str, version: usize, destination: &str) {
+    let versions = find_file_versions(&maps, input_path);
+    if versions.is_empty() {
+        panic!("File {} not found in any backup. Program will stop", input_path);
+    }
+    println!("Found {} versions of {}:", versions.len(), input_path);
+    print_file_versions(&versions);
+    if destination.is_empty() {
+        return;
+    }
+
+    let version = match version {
+        0 => get_version_from_user(versions.len()),
+        _ => version,
+    };
+    if version > versions.len() {
+        panic!("Version {} doesn't exist. Program will stop", version);
+    }
+    if let Err(e) = restore_file_version(&versions[version - 1], destination) {
+        panic!("Couldn't restore file: {}. Program will stop", e);
+    }
+}
+
+/// Prints list of provided backups, as human-readable text or JSON.
+///
+/// Panics if JSON can't be created.
+pub fn make_list(maps: Vec<BackupMap>, json: bool) {
+    match list_backups(&maps, json) {
+        Ok(text) => println!("{}", text),
+        Err(e) => panic!("Couldn't list backups: {}. Program will stop", e)
+    }
+}
+
+/// Prints details of provided backup, as human-readable text or JSON.
+///
+/// Panics if JSON can't be created.
+pub fn make_show(map: BackupMap, json: bool) {
+    match show_backup(&map, json) {
+        Ok(text) => println!("{}", text),
+        Err(e) => panic!("Couldn't show backup: {}. Program will stop", e)
+    }
+}
+
+/// Prints differences between two provided backups, as human-readable text or JSON.
+///
+/// Panics if JSON can't be created.
+pub fn make_diff(old: BackupMap, new: BackupMap, json: bool) {
+    let diff = diff_maps(&old, &new);
+    match json {
+        true => match serde_json::to_string_pretty(&diff) {
+            Ok(text) => println!("{}", text),
+            Err(e) => panic!("Couldn't convert differences to JSON: {}. Program will stop", e)
+        },
+        false => println!("{}", diff.to_text()),
+    }
+}
+
+/// Verifies every file of provided backups against hashes saved in their maps, then prints report as human-readable text or JSON.
+///
+/// If ```save``` is true, time of verification is saved in maps.
+///
+/// Returns true if no problems were found in any backup. Panics if JSON can't be created.
+pub fn make_verify(maps: Vec<BackupMap>, save: bool, json: bool) -> bool {
+    let mut reports: Vec<VerifyReport> = vec![];
+    for mut map in maps {
+        let report = verify_map(&map);
+        if save {
+            if let Err(e) = save_verification(&mut map) {
+                println!("Couldn't save time of verification in map of backup {}: {}", &map.id, e);
+            }
+        }
+        reports.push(report);
+    }
+
+    match json {
+        true => match serde_json::to_string_pretty(&reports) {
+            Ok(text) => println!("{}", text),
+            Err(e) => panic!("Couldn't convert verification reports to JSON: {}. Program will stop", e)
+        },
+        false => {
+            for report in &reports {
+                println!("{}", report.to_text());
+            }
+        }
+    }
+    reports.iter().all(|x| x.is_ok())
+}
+
+/// Verifies every file of provided backups, then copies again from source every missing or corrupted file, and prints report as human-readable text or JSON.
+///
+/// Returns true if all damaged files were repaired. Panics if JSON can't be created.
+pub fn make_repair(maps: Vec<BackupMap>, json: bool) -> bool {
+    let reports: Vec<RepairReport> = maps.iter().map(|x| repair_map(x, &verify_map(x))).collect();
+    match json {
+        true => match serde_json::to_string_pretty(&reports) {
+            Ok(text) => println!("{}", text),
+            Err(e) => panic!("Couldn't convert repair reports to JSON: {}. Program will stop", e)
+        },
+        false => {
+            for report in &reports {
+                println!("{}", report.to_text());
+            }
+        }
+    }
+    reports.iter().all(|x| x.unrepairable.is_empty())
+}
+
+/// Prints when every output folder (or drive from pool) was updated, with warning for every one that wasn't updated for more than provided amount of days.
+///
+/// Returns true if every output folder was updated recently enough.
+pub fn make_status(folders: &[String], markers: bool, days: u64, json: bool) -> bool {
+    let report = pool_status(folders, markers, &load_status(), days);
+    match json {
+        true => match serde_json::to_string_pretty(&report) {
+            Ok(text) => println!("{}", text),
+            Err(e) => panic!("Couldn't convert status report to JSON: {}. Program will stop", e)
+        },
+        false => println!("{}", report.to_text()),
+    }
+    report.is_ok()
+}
+
+/// Rewrites every map in provided folder with backups in current format version, then prints report as human-readable text or JSON.
+///
+/// Returns true if all maps are in current format version. Panics if JSON can't be created.
+pub fn make_migrate(folder: &str, json: bool) -> bool {
+    let report = migrate_folder(folder);
+    match json {
+        true => match serde_json::to_string_pretty(&report) {
+            Ok(text) => println!("{}", text),
+            Err(e) => panic!("Couldn't convert migration report to JSON: {}. Program will stop", e)
+        },
+        false => println!("{}", report.to_text()),
     }
+    report.failed.is_empty()
 }
 
 // TODO - CREATE NICE README
\ No newline at end of file